@@ -0,0 +1,673 @@
+use std::sync::LazyLock;
+
+use cgmath::{Matrix, Matrix3, SquareMatrix, Vector3};
+
+use crate::types::RgbPixel;
+
+// ICC profiles use D50 as profile connection space white point
+const D50_WHITE: [f32; 3] = [0.9642, 1.0, 0.8249];
+const D65_CHROMATICITY: (f32, f32) = (0.3127, 0.3290);
+// Rec. 2020 primaries, which contain the gamut of Display P3 and Adobe RGB images
+const WIDE_GAMUT_PRIMARIES: ((f32, f32), (f32, f32), (f32, f32)) = ((0.708, 0.292), (0.170, 0.797), (0.131, 0.046));
+// Converting pixels on the CPU happens per pixel, so the matrix is only calculated once
+static WIDE_GAMUT_TO_LINEAR_SRGB: LazyLock<Matrix3<f32>> = LazyLock::new(wide_gamut_to_linear_srgb);
+// ICC profiles store the colorants with 16 bit fractions, and profiles of the same space differ slightly
+const SRGB_PROFILE_TOLERANCE: f32 = 2e-3;
+
+/**
+ * sRGB images are kept as they are. Images with another embedded ICC profile are converted on import to Rec. 2020
+ * primaries with the sRGB transfer function, so the texture still decodes them to linear values. The shader converts
+ * either to linear sRGB (which is what Oklab expects) with values outside 0-1 for wider colors, and the result is
+ * only mapped into the gamut of a color space on export.
+ */
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ColorSpace {
+    #[default]
+    Srgb,
+    DisplayP3,
+    AdobeRgb
+}
+
+impl std::fmt::Display for ColorSpace {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.description())
+    }
+}
+
+impl std::str::FromStr for ColorSpace {
+    type Err = ();
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "Srgb" => Ok(Self::Srgb),
+            "DisplayP3" => Ok(Self::DisplayP3),
+            "AdobeRgb" => Ok(Self::AdobeRgb),
+            _ => Err(())
+        }
+    }
+}
+
+impl ColorSpace {
+    pub const ALL: [ColorSpace; 3] = [ColorSpace::Srgb, ColorSpace::DisplayP3, ColorSpace::AdobeRgb];
+
+    pub fn to_name(self) -> String {
+        format!("{:?}", self)
+    }
+
+    pub fn tone_curve(&self) -> ToneCurve {
+        match self {
+            Self::Srgb => ToneCurve::Parametric(ParametricCurve::srgb()),
+            Self::DisplayP3 => ToneCurve::Parametric(ParametricCurve::srgb()),
+            Self::AdobeRgb => ToneCurve::Parametric(ParametricCurve::gamma(563.0 / 256.0))
+        }
+    }
+
+    /**
+     * Linear RGB to XYZ (D50 adapted), i.e. the colorant matrix of the ICC profile
+     */
+    pub fn to_xyz_d50(self) -> Matrix3<f32> {
+        let (red, green, blue) = self.primaries();
+        let to_xyz = rgb_to_xyz_matrix(red, green, blue, D65_CHROMATICITY);
+        let d65_white = chromaticity_to_xyz(D65_CHROMATICITY);
+        bradford_adaptation(d65_white, Vector3::from(D50_WHITE)) * to_xyz
+    }
+
+    /**
+     * Linear sRGB (what the shader works in) to linear RGB in this color space
+     */
+    pub fn conversion_from_linear_srgb(self) -> Matrix3<f32> {
+        self.to_xyz_d50().invert().unwrap() * ColorSpace::Srgb.to_xyz_d50()
+    }
+
+    pub fn to_icc_profile(self) -> Vec<u8> {
+        IccProfileWriter::new(self.description(), self.to_xyz_d50(), self.tone_curve()).write()
+    }
+
+    fn description(&self) -> &'static str {
+        match self {
+            Self::Srgb => "sRGB",
+            Self::DisplayP3 => "Display P3",
+            Self::AdobeRgb => "Adobe RGB (1998)"
+        }
+    }
+
+    fn primaries(&self) -> ((f32, f32), (f32, f32), (f32, f32)) {
+        match self {
+            Self::Srgb => ((0.64, 0.33), (0.30, 0.60), (0.15, 0.06)),
+            Self::DisplayP3 => ((0.680, 0.320), (0.265, 0.690), (0.150, 0.060)),
+            Self::AdobeRgb => ((0.64, 0.33), (0.21, 0.71), (0.15, 0.06))
+        }
+    }
+}
+
+/**
+ * ICC parametric curve (function type 4). The other function types are special cases of this one:
+ * Y = (a * X + b)^g + e if X >= d, otherwise Y = c * X + f
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParametricCurve {
+    pub g: f32,
+    pub a: f32,
+    pub b: f32,
+    pub c: f32,
+    pub d: f32,
+    pub e: f32,
+    pub f: f32
+}
+
+impl ParametricCurve {
+    pub fn gamma(g: f32) -> Self {
+        Self { g, a: 1.0, b: 0.0, c: 0.0, d: 0.0, e: 0.0, f: 0.0 }
+    }
+
+    pub fn srgb() -> Self {
+        Self { g: 2.4, a: 1.0 / 1.055, b: 0.055 / 1.055, c: 1.0 / 12.92, d: 0.04045, e: 0.0, f: 0.0 }
+    }
+
+    fn to_linear(&self, value: f32) -> f32 {
+        if value >= self.d {
+            (self.a * value + self.b).max(0.0).powf(self.g) + self.e
+        } else {
+            self.c * value + self.f
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ToneCurve {
+    Parametric(ParametricCurve),
+    Table(Vec<f32>)
+}
+
+impl ToneCurve {
+    pub fn to_linear(&self, value: f32) -> f32 {
+        match self {
+            Self::Parametric(curve) => curve.to_linear(value),
+            Self::Table(table) => interpolate_table(table, value)
+        }
+    }
+}
+
+fn interpolate_table(table: &[f32], value: f32) -> f32 {
+    match table.len() {
+        0 => value,
+        1 => table[0],
+        length => {
+            let position = value.clamp(0.0, 1.0) * (length - 1) as f32;
+            let index = (position.floor() as usize).min(length - 2);
+            let fraction = position - index as f32;
+            table[index] * (1.0 - fraction) + table[index + 1] * fraction
+        }
+    }
+}
+
+pub fn wide_gamut_to_xyz_d50() -> Matrix3<f32> {
+    let (red, green, blue) = WIDE_GAMUT_PRIMARIES;
+    let to_xyz = rgb_to_xyz_matrix(red, green, blue, D65_CHROMATICITY);
+    let d65_white = chromaticity_to_xyz(D65_CHROMATICITY);
+    bradford_adaptation(d65_white, Vector3::from(D50_WHITE)) * to_xyz
+}
+
+pub fn wide_gamut_to_linear_srgb() -> Matrix3<f32> {
+    ColorSpace::Srgb.to_xyz_d50().invert().unwrap() * wide_gamut_to_xyz_d50()
+}
+
+/**
+ * Converts the linear values the texture decodes to into linear sRGB
+ */
+pub fn source_to_linear_srgb(is_wide_gamut: bool) -> Matrix3<f32> {
+    if is_wide_gamut {
+        *WIDE_GAMUT_TO_LINEAR_SRGB
+    } else {
+        Matrix3::identity()
+    }
+}
+
+/**
+ * Converts a wide gamut pixel to sRGB. Colors outside of sRGB are kept as values outside 0-1.
+ */
+pub fn wide_gamut_to_srgb(pixel: &RgbPixel) -> RgbPixel {
+    let linear = *WIDE_GAMUT_TO_LINEAR_SRGB * Vector3::new(
+        srgb_to_linear(pixel.red),
+        srgb_to_linear(pixel.green),
+        srgb_to_linear(pixel.blue));
+    RgbPixel {
+        red: linear_to_srgb(linear.x),
+        green: linear_to_srgb(linear.y),
+        blue: linear_to_srgb(linear.z)
+    }
+}
+
+pub fn srgb_to_linear(value: f32) -> f32 {
+    if value <= 0.04045 {
+        value / 12.92
+    } else {
+        ((value + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+pub fn linear_to_srgb(value: f32) -> f32 {
+    if value <= 0.0031308 {
+        value * 12.92
+    } else {
+        1.055 * value.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+/**
+ * Matrix/TRC based RGB profile. This covers the profiles typically embedded by cameras and image editors
+ * (sRGB, Display P3, Adobe RGB, ProPhoto, etc.). LUT based profiles are not supported.
+ */
+#[derive(Debug, Clone)]
+pub struct IccProfile {
+    pub to_xyz_d50: Matrix3<f32>,
+    pub tone_curves: [ToneCurve; 3]
+}
+
+impl IccProfile {
+    pub fn parse(data: &[u8]) -> Option<Self> {
+        let reader = IccReader { data };
+        if reader.signature(16)? != *b"RGB " || reader.signature(20)? != *b"XYZ " {
+            return None;
+        }
+
+        let red = reader.xyz_tag(b"rXYZ")?;
+        let green = reader.xyz_tag(b"gXYZ")?;
+        let blue = reader.xyz_tag(b"bXYZ")?;
+
+        Some(Self {
+            to_xyz_d50: Matrix3::from_cols(red, green, blue),
+            tone_curves: [
+                reader.curve_tag(b"rTRC")?,
+                reader.curve_tag(b"gTRC")?,
+                reader.curve_tag(b"bTRC")?
+            ]
+        })
+    }
+
+    /**
+     * Whether pixels encoded with this profile can be used without converting them
+     */
+    pub fn is_srgb(&self) -> bool {
+        let srgb = ColorSpace::Srgb.to_xyz_d50();
+        let srgb_curve = ColorSpace::Srgb.tone_curve();
+        let is_srgb_colorants = (0..3)
+            .all(|column| (0..3).all(|row| (self.to_xyz_d50[column][row] - srgb[column][row]).abs() < SRGB_PROFILE_TOLERANCE));
+        let is_srgb_curve = self.tone_curves.iter()
+            .all(|curve| (0..=16).map(|index| index as f32 / 16.0)
+                .all(|value| (curve.to_linear(value) - srgb_curve.to_linear(value)).abs() < SRGB_PROFILE_TOLERANCE));
+        is_srgb_colorants && is_srgb_curve
+    }
+
+    /**
+     * Creates a function that converts pixels encoded with this profile into wide gamut pixels. Only colors outside of
+     * Rec. 2020 are clamped.
+     */
+    pub fn to_wide_gamut_converter(&self) -> impl Fn(&RgbPixel) -> RgbPixel + Sync + '_ {
+        let transform = wide_gamut_to_xyz_d50().invert().unwrap() * self.to_xyz_d50;
+        move |pixel| {
+            let linear = Vector3::new(
+                self.tone_curves[0].to_linear(pixel.red),
+                self.tone_curves[1].to_linear(pixel.green),
+                self.tone_curves[2].to_linear(pixel.blue));
+            let wide_gamut = transform * linear;
+            RgbPixel {
+                red: linear_to_srgb(wide_gamut.x.clamp(0.0, 1.0)),
+                green: linear_to_srgb(wide_gamut.y.clamp(0.0, 1.0)),
+                blue: linear_to_srgb(wide_gamut.z.clamp(0.0, 1.0))
+            }
+        }
+    }
+}
+
+struct IccReader<'a> {
+    data: &'a [u8]
+}
+
+impl<'a> IccReader<'a> {
+    fn bytes(&self, offset: usize, length: usize) -> Option<&'a [u8]> {
+        self.data.get(offset..offset.checked_add(length)?)
+    }
+
+    fn signature(&self, offset: usize) -> Option<[u8; 4]> {
+        self.bytes(offset, 4)?.try_into().ok()
+    }
+
+    fn u16(&self, offset: usize) -> Option<u16> {
+        self.bytes(offset, 2).map(|bytes| u16::from_be_bytes([bytes[0], bytes[1]]))
+    }
+
+    fn u32(&self, offset: usize) -> Option<u32> {
+        self.signature(offset).map(u32::from_be_bytes)
+    }
+
+    fn s15_fixed16(&self, offset: usize) -> Option<f32> {
+        self.signature(offset).map(|bytes| i32::from_be_bytes(bytes) as f32 / 65536.0)
+    }
+
+    fn find_tag(&self, signature: &[u8; 4]) -> Option<usize> {
+        let tag_count = self.u32(128)? as usize;
+        (0..tag_count)
+            .map(|index| 132 + index * 12)
+            .find(|entry| self.signature(*entry) == Some(*signature))
+            .and_then(|entry| self.u32(entry + 4))
+            .map(|offset| offset as usize)
+    }
+
+    fn xyz_tag(&self, signature: &[u8; 4]) -> Option<Vector3<f32>> {
+        let offset = self.find_tag(signature)?;
+        if self.signature(offset)? != *b"XYZ " {
+            return None;
+        }
+        Some(Vector3::new(
+            self.s15_fixed16(offset + 8)?,
+            self.s15_fixed16(offset + 12)?,
+            self.s15_fixed16(offset + 16)?))
+    }
+
+    fn curve_tag(&self, signature: &[u8; 4]) -> Option<ToneCurve> {
+        let offset = self.find_tag(signature)?;
+        match &self.signature(offset)? {
+            b"curv" => self.curv(offset),
+            b"para" => self.para(offset),
+            _ => None
+        }
+    }
+
+    fn curv(&self, offset: usize) -> Option<ToneCurve> {
+        let count = self.u32(offset + 8)? as usize;
+        match count {
+            0 => Some(ToneCurve::Parametric(ParametricCurve::gamma(1.0))),
+            1 => {
+                let gamma = self.u16(offset + 12)? as f32 / 256.0;
+                Some(ToneCurve::Parametric(ParametricCurve::gamma(gamma)))
+            },
+            _ => {
+                let table: Option<Vec<f32>> = (0..count)
+                    .map(|index| self.u16(offset + 12 + index * 2).map(|value| value as f32 / 65535.0))
+                    .collect();
+                table.map(ToneCurve::Table)
+            }
+        }
+    }
+
+    fn para(&self, offset: usize) -> Option<ToneCurve> {
+        let function_type = self.u16(offset + 8)?;
+        let parameter = |index: usize| self.s15_fixed16(offset + 12 + index * 4);
+        let g = parameter(0)?;
+        let curve = match function_type {
+            0 => ParametricCurve::gamma(g),
+            1 => {
+                let (a, b) = (parameter(1)?, parameter(2)?);
+                ParametricCurve { g, a, b, c: 0.0, d: -b / a, e: 0.0, f: 0.0 }
+            },
+            2 => {
+                let (a, b, c) = (parameter(1)?, parameter(2)?, parameter(3)?);
+                ParametricCurve { g, a, b, c: 0.0, d: -b / a, e: c, f: c }
+            },
+            3 => {
+                let (a, b, c, d) = (parameter(1)?, parameter(2)?, parameter(3)?, parameter(4)?);
+                ParametricCurve { g, a, b, c, d, e: 0.0, f: 0.0 }
+            },
+            4 => {
+                let (a, b, c, d) = (parameter(1)?, parameter(2)?, parameter(3)?, parameter(4)?);
+                let (e, f) = (parameter(5)?, parameter(6)?);
+                ParametricCurve { g, a, b, c, d, e, f }
+            },
+            _ => return None
+        };
+        Some(ToneCurve::Parametric(curve))
+    }
+}
+
+/**
+ * Writes a minimal ICC v2 display profile (matrix/TRC)
+ */
+struct IccProfileWriter {
+    description: &'static str,
+    to_xyz_d50: Matrix3<f32>,
+    tone_curve: ToneCurve
+}
+
+impl IccProfileWriter {
+    fn new(description: &'static str, to_xyz_d50: Matrix3<f32>, tone_curve: ToneCurve) -> Self {
+        Self { description, to_xyz_d50, tone_curve }
+    }
+
+    fn write(&self) -> Vec<u8> {
+        let columns = [self.to_xyz_d50.x, self.to_xyz_d50.y, self.to_xyz_d50.z];
+        let curve = self.curve_tag();
+        let tags: Vec<(&[u8; 4], Vec<u8>)> = vec![
+            (b"desc", self.description_tag()),
+            (b"cprt", Self::text_tag("No copyright, use freely")),
+            (b"wtpt", Self::xyz_tag(Vector3::from(D50_WHITE))),
+            (b"rXYZ", Self::xyz_tag(columns[0])),
+            (b"gXYZ", Self::xyz_tag(columns[1])),
+            (b"bXYZ", Self::xyz_tag(columns[2])),
+            (b"rTRC", curve.clone()),
+            (b"gTRC", curve.clone()),
+            (b"bTRC", curve),
+        ];
+
+        let mut tag_table: Vec<u8> = Vec::new();
+        let mut tag_data: Vec<u8> = Vec::new();
+        let data_start = 128 + 4 + tags.len() * 12;
+        tag_table.extend((tags.len() as u32).to_be_bytes());
+        for (signature, data) in &tags {
+            tag_table.extend(*signature);
+            tag_table.extend(((data_start + tag_data.len()) as u32).to_be_bytes());
+            tag_table.extend((data.len() as u32).to_be_bytes());
+            tag_data.extend(data);
+            while !tag_data.len().is_multiple_of(4) {
+                tag_data.push(0);
+            }
+        }
+
+        let mut profile = self.header(data_start + tag_data.len());
+        profile.extend(tag_table);
+        profile.extend(tag_data);
+        profile
+    }
+
+    fn header(&self, size: usize) -> Vec<u8> {
+        let mut header: Vec<u8> = Vec::with_capacity(128);
+        header.extend((size as u32).to_be_bytes());
+        header.extend([0; 4]); // Preferred CMM
+        header.extend([0x02, 0x10, 0x00, 0x00]); // Version 2.1
+        header.extend(b"mntr");
+        header.extend(b"RGB ");
+        header.extend(b"XYZ ");
+        header.extend([0; 12]); // Date and time
+        header.extend(b"acsp");
+        header.extend([0; 24]); // Platform, flags, manufacturer, model and attributes
+        header.extend([0; 4]); // Rendering intent: perceptual
+        for value in D50_WHITE {
+            header.extend(Self::s15_fixed16(value));
+        }
+        header.resize(128, 0);
+        header
+    }
+
+    fn description_tag(&self) -> Vec<u8> {
+        let mut data: Vec<u8> = Vec::new();
+        data.extend(b"desc");
+        data.extend([0; 4]);
+        data.extend((self.description.len() as u32 + 1).to_be_bytes());
+        data.extend(self.description.as_bytes());
+        data.push(0);
+        data.extend([0; 8]); // No unicode description
+        data.extend([0; 3 + 67]); // No ScriptCode description
+        data
+    }
+
+    fn text_tag(text: &str) -> Vec<u8> {
+        let mut data: Vec<u8> = Vec::new();
+        data.extend(b"text");
+        data.extend([0; 4]);
+        data.extend(text.as_bytes());
+        data.push(0);
+        data
+    }
+
+    fn xyz_tag(xyz: Vector3<f32>) -> Vec<u8> {
+        let mut data: Vec<u8> = Vec::new();
+        data.extend(b"XYZ ");
+        data.extend([0; 4]);
+        for value in [xyz.x, xyz.y, xyz.z] {
+            data.extend(Self::s15_fixed16(value));
+        }
+        data
+    }
+
+    fn curve_tag(&self) -> Vec<u8> {
+        // Parametric curves are a v4 feature, so a sampled curve is used for compatibility with v2 readers
+        const CURVE_SIZE: usize = 1024;
+        let mut data: Vec<u8> = Vec::new();
+        data.extend(b"curv");
+        data.extend([0; 4]);
+        data.extend((CURVE_SIZE as u32).to_be_bytes());
+        for index in 0..CURVE_SIZE {
+            let value = self.tone_curve.to_linear(index as f32 / (CURVE_SIZE - 1) as f32);
+            data.extend(((value.clamp(0.0, 1.0) * 65535.0).round() as u16).to_be_bytes());
+        }
+        data
+    }
+
+    fn s15_fixed16(value: f32) -> [u8; 4] {
+        ((value * 65536.0).round() as i32).to_be_bytes()
+    }
+}
+
+fn chromaticity_to_xyz((x, y): (f32, f32)) -> Vector3<f32> {
+    Vector3::new(x / y, 1.0, (1.0 - x - y) / y)
+}
+
+fn rgb_to_xyz_matrix(red: (f32, f32), green: (f32, f32), blue: (f32, f32), white: (f32, f32)) -> Matrix3<f32> {
+    let primaries = Matrix3::from_cols(
+        chromaticity_to_xyz(red),
+        chromaticity_to_xyz(green),
+        chromaticity_to_xyz(blue));
+    let scale = primaries.invert().unwrap() * chromaticity_to_xyz(white);
+    primaries * Matrix3::from_diagonal(scale)
+}
+
+fn bradford_adaptation(source_white: Vector3<f32>, target_white: Vector3<f32>) -> Matrix3<f32> {
+    let bradford = Matrix3::new(
+        0.8951, 0.2664, -0.1614,
+        -0.7502, 1.7135, 0.0367,
+        0.0389, -0.0685, 1.0296).transpose(); // Matrix3::new takes columns
+    let source_cone = bradford * source_white;
+    let target_cone = bradford * target_white;
+    let scale = Matrix3::from_diagonal(Vector3::new(
+        target_cone.x / source_cone.x,
+        target_cone.y / source_cone.y,
+        target_cone.z / source_cone.z));
+    bradford.invert().unwrap() * scale * bradford
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::*;
+
+    use cgmath::InnerSpace;
+
+    #[rstest]
+    #[case(ColorSpace::Srgb, [0.4361, 0.2225, 0.0139], [0.3851, 0.7169, 0.0971], [0.1431, 0.0606, 0.7141])]
+    #[case(ColorSpace::DisplayP3, [0.5151, 0.2412, -0.0011], [0.2920, 0.6922, 0.0419], [0.1571, 0.0666, 0.7841])]
+    #[case(ColorSpace::AdobeRgb, [0.6098, 0.3111, 0.0195], [0.2053, 0.6257, 0.0609], [0.1492, 0.0632, 0.7448])]
+    fn test_to_xyz_d50(
+            #[case] color_space: ColorSpace,
+            #[case] expected_red: [f32; 3],
+            #[case] expected_green: [f32; 3],
+            #[case] expected_blue: [f32; 3]) {
+        // Arrange
+        let expected = Matrix3::from_cols(
+            Vector3::from(expected_red),
+            Vector3::from(expected_green),
+            Vector3::from(expected_blue));
+
+        // Act
+        let actual = color_space.to_xyz_d50();
+
+        // Assert
+        assert_matrix_equal(&actual, &expected, 1e-3);
+    }
+
+    #[rstest]
+    #[case(ColorSpace::Srgb)]
+    #[case(ColorSpace::DisplayP3)]
+    #[case(ColorSpace::AdobeRgb)]
+    fn test_icc_profile_round_trip(#[case] color_space: ColorSpace) {
+        // Arrange
+        let data = color_space.to_icc_profile();
+
+        // Act
+        let actual = IccProfile::parse(&data).unwrap();
+
+        // Assert
+        assert_eq!(data.len(), u32::from_be_bytes(data[0..4].try_into().unwrap()) as usize);
+        assert_matrix_equal(&actual.to_xyz_d50, &color_space.to_xyz_d50(), 1e-4);
+        for value in [0.0, 0.02, 0.2, 0.5, 0.8, 1.0] {
+            let expected = color_space.tone_curve().to_linear(value);
+            for tone_curve in &actual.tone_curves {
+                assert!((tone_curve.to_linear(value) - expected).abs() < 1e-3, "Expected: {:?}, was: {:?}", expected, tone_curve.to_linear(value));
+            }
+        }
+    }
+
+    #[rstest]
+    #[case(0.0)]
+    #[case(0.002)]
+    #[case(0.04045)]
+    #[case(0.5)]
+    #[case(1.0)]
+    fn test_srgb_transfer_round_trip(#[case] value: f32) {
+        // Act
+        let actual = linear_to_srgb(ColorSpace::Srgb.tone_curve().to_linear(value));
+
+        // Assert
+        assert!((actual - value).abs() < 1e-5, "Expected: {:?}, was: {:?}", value, actual);
+    }
+
+    #[rstest]
+    #[case(0.0, 0.0, 0.0)]
+    #[case(1.0, 1.0, 1.0)]
+    #[case(0.25, 0.5, 0.75)]
+    fn test_srgb_profile_conversion_round_trip(#[case] red: f32, #[case] green: f32, #[case] blue: f32) {
+        // Arrange
+        let profile = IccProfile::parse(&ColorSpace::Srgb.to_icc_profile()).unwrap();
+        let converter = profile.to_wide_gamut_converter();
+
+        // Act
+        let actual = wide_gamut_to_srgb(&converter(&RgbPixel { red, green, blue }));
+
+        // Assert
+        assert!((actual.red - red).abs() < 1e-3, "Expected: {:?}, was: {:?}", red, actual.red);
+        assert!((actual.green - green).abs() < 1e-3, "Expected: {:?}, was: {:?}", green, actual.green);
+        assert!((actual.blue - blue).abs() < 1e-3, "Expected: {:?}, was: {:?}", blue, actual.blue);
+    }
+
+    #[rstest]
+    fn test_wide_gamut_color_is_kept_until_export() {
+        // Arrange
+        let profile = IccProfile::parse(&ColorSpace::DisplayP3.to_icc_profile()).unwrap();
+        let wide_gamut = profile.to_wide_gamut_converter()(&RgbPixel { red: 1.0, green: 0.0, blue: 0.0 });
+
+        // Act
+        let srgb = wide_gamut_to_srgb(&wide_gamut);
+        let linear_srgb = Vector3::new(srgb_to_linear(srgb.red), srgb_to_linear(srgb.green), srgb_to_linear(srgb.blue));
+        let actual = ColorSpace::DisplayP3.conversion_from_linear_srgb() * linear_srgb;
+
+        // Assert
+        assert!(srgb.red > 1.0 && srgb.green < 0.0, "Was: {:?}", srgb);
+        assert!((actual - Vector3::new(1.0, 0.0, 0.0)).magnitude() < 1e-2, "Was: {:?}", actual);
+    }
+
+    #[rstest]
+    #[case(ColorSpace::Srgb, true)]
+    #[case(ColorSpace::DisplayP3, false)]
+    #[case(ColorSpace::AdobeRgb, false)]
+    fn test_is_srgb(#[case] color_space: ColorSpace, #[case] expected: bool) {
+        // Arrange
+        let profile = IccProfile::parse(&color_space.to_icc_profile()).unwrap();
+
+        // Act
+        let actual = profile.is_srgb();
+
+        // Assert
+        assert_eq!(actual, expected);
+    }
+
+    #[rstest]
+    fn test_wide_gamut_to_linear_srgb() {
+        // Arrange
+        let expected = Matrix3::new(
+            1.6605, -0.1246, -0.0182,
+            -0.5876, 1.1329, -0.1006,
+            -0.0728, -0.0083, 1.1187);
+
+        // Act
+        let actual = wide_gamut_to_linear_srgb();
+
+        // Assert
+        assert_matrix_equal(&actual, &expected, 1e-3);
+    }
+
+    #[rstest]
+    fn test_parse_invalid_profile() {
+        // Act
+        let actual = IccProfile::parse(&[0; 64]);
+
+        // Assert
+        assert!(actual.is_none());
+    }
+
+    fn assert_matrix_equal(actual: &Matrix3<f32>, expected: &Matrix3<f32>, epsilon: f32) {
+        for column in 0..3 {
+            for row in 0..3 {
+                assert!((actual[column][row] - expected[column][row]).abs() < epsilon, "Expected: {:?}, was: {:?}", expected, actual);
+            }
+        }
+    }
+}
@@ -0,0 +1,29 @@
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+pub enum ComparisonMode {
+    #[default]
+    Off,
+    SplitVertical,
+    SplitHorizontal,
+    SideBySide
+}
+
+impl ComparisonMode {
+    pub fn next(&self) -> ComparisonMode {
+        match self {
+            ComparisonMode::Off => ComparisonMode::SplitVertical,
+            ComparisonMode::SplitVertical => ComparisonMode::SplitHorizontal,
+            ComparisonMode::SplitHorizontal => ComparisonMode::SideBySide,
+            ComparisonMode::SideBySide => ComparisonMode::Off
+        }
+    }
+
+    /**
+     * Side by side draws the image twice: once edited and once offset to the left as the original
+     */
+    pub fn instance_count(&self) -> u32 {
+        match self {
+            ComparisonMode::SideBySide => 2,
+            _ => 1
+        }
+    }
+}
@@ -0,0 +1,71 @@
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+pub enum CompositionGuide {
+    #[default]
+    Grid,
+    RuleOfThirds,
+    GoldenRatio,
+    GoldenSpiral,
+    Diagonals,
+    Triangles,
+    CenterCross
+}
+
+impl CompositionGuide {
+    pub const ALL: [CompositionGuide; 7] = [
+        CompositionGuide::Grid,
+        CompositionGuide::RuleOfThirds,
+        CompositionGuide::GoldenRatio,
+        CompositionGuide::GoldenSpiral,
+        CompositionGuide::Diagonals,
+        CompositionGuide::Triangles,
+        CompositionGuide::CenterCross
+    ];
+
+    pub fn next(&self) -> CompositionGuide {
+        match self {
+            CompositionGuide::Grid => CompositionGuide::RuleOfThirds,
+            CompositionGuide::RuleOfThirds => CompositionGuide::GoldenRatio,
+            CompositionGuide::GoldenRatio => CompositionGuide::GoldenSpiral,
+            CompositionGuide::GoldenSpiral => CompositionGuide::Diagonals,
+            CompositionGuide::Diagonals => CompositionGuide::Triangles,
+            CompositionGuide::Triangles => CompositionGuide::CenterCross,
+            CompositionGuide::CenterCross => CompositionGuide::Grid
+        }
+    }
+
+    /**
+     * Only the spiral and the triangles look different when mirrored
+     */
+    pub fn has_orientation(&self) -> bool {
+        matches!(self, CompositionGuide::GoldenSpiral | CompositionGuide::Triangles)
+    }
+
+    /**
+     * Matches the guide indices in `draw_grid` in the shader
+     */
+    pub fn index(&self) -> i32 {
+        match self {
+            CompositionGuide::Grid => 0,
+            CompositionGuide::RuleOfThirds => 1,
+            CompositionGuide::GoldenRatio => 2,
+            CompositionGuide::GoldenSpiral => 3,
+            CompositionGuide::Diagonals => 4,
+            CompositionGuide::Triangles => 5,
+            CompositionGuide::CenterCross => 6
+        }
+    }
+}
+
+impl std::fmt::Display for CompositionGuide {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CompositionGuide::Grid => write!(f, "Grid"),
+            CompositionGuide::RuleOfThirds => write!(f, "Rule of thirds"),
+            CompositionGuide::GoldenRatio => write!(f, "Golden ratio"),
+            CompositionGuide::GoldenSpiral => write!(f, "Golden spiral"),
+            CompositionGuide::Diagonals => write!(f, "Diagonals"),
+            CompositionGuide::Triangles => write!(f, "Triangles"),
+            CompositionGuide::CenterCross => write!(f, "Center cross")
+        }
+    }
+}
@@ -1,5 +1,8 @@
 // #![windows_subsystem = "windows"]
 mod types;
+mod color_profile;
+mod comparison_mode;
+mod composition_guide;
 mod pipeline;
 mod repository;
 mod update;
@@ -12,24 +15,35 @@ use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
 use std::time::Duration;
 
+use color_profile::ColorSpace;
 use directories::ProjectDirs;
 use iced;
 use iced::keyboard::key::Named;
 use pipeline::viewport;
 use repository::album_repository::AlbumRepository;
 use repository::album_repository_factory::AlbumRepositoryFactory;
+use repository::crop_preset_repository::CropPresetRepository;
+use repository::crop_preset_repository_factory::CropPresetRepositoryFactory;
 use repository::parameter_name::ParameterName;
+use repository::preset_repository::PresetRepository;
+use repository::preset_repository_factory::PresetRepositoryFactory;
 use repository::settings_repository::SettingsRepository;
+use repository::snapshot_repository::SnapshotRepository;
+use repository::snapshot_repository_factory::SnapshotRepositoryFactory;
 use repository::settings_repository_factory::SettingRepositoryFactory;
 use rusqlite::Connection;
 use ui::welcome_window::WelcomeWindow;
 use view_mode::ViewMode;
 use workspace::album::Album;
+use workspace::histogram::HistogramCache;
 use workspace::image_manager::ImageManager;
-use workspace::parameters::Parameters;
+use workspace::parameters::{CropPreset, Parameters};
+use workspace::parameter_group::ParameterGroup;
+use workspace::preset::Preset;
+use workspace::snapshot::Snapshot;
 use workspace::workspace::Workspace;
 use ui::message::{KeyboardMessage, Message, MouseState};
-use ui::main_window::MainWindow;
+use ui::main_window::{CropPresetInput, GroupDialogInput, MainWindow, MainWindowState, PresetInput};
 use viewport::Viewport;
 
 pub fn main() -> iced::Result {
@@ -48,12 +62,29 @@ struct Main {
 
     album_repository: Arc<AlbumRepository>,
     settings_repository: Arc<SettingsRepository>,
+    crop_preset_repository: CropPresetRepository,
+    preset_repository: PresetRepository,
+    snapshot_repository: SnapshotRepository,
     image_manager: ImageManager,
 
     viewport: Option<Viewport>,
+    histogram_cache: HistogramCache,
     clipboard_parameters: Option<Parameters>,
 
-    is_save_active: bool
+    is_save_active: bool,
+    export_color_space: ColorSpace,
+    crop_presets: Vec<CropPreset>,
+    crop_preset_input: CropPresetInput,
+    presets: Vec<Preset>,
+    preset_input: PresetInput,
+    // Snapshots of the current photo
+    snapshots: Vec<Snapshot>,
+    snapshot_name: String,
+    // Groups pasted with plain paste, the last choice in the paste dialog
+    paste_groups: Vec<ParameterGroup>,
+    sync_groups: Vec<ParameterGroup>,
+    group_dialog_input: Option<GroupDialogInput>,
+    keyboard_modifiers: iced::keyboard::Modifiers
 }
 
 fn init() -> (Main, iced::Task<Message>) {
@@ -85,6 +116,7 @@ fn handle_key_press_character(character: &str, modifiers: iced::keyboard::Modifi
     if modifiers.control() && modifiers.shift() {
         match character {
             "z" => Some(KeyboardMessage::Redo),
+            "v" => Some(KeyboardMessage::PasteSpecial),
             _ => None
         }
     } else if modifiers.control() {
@@ -100,8 +132,16 @@ fn handle_key_press_character(character: &str, modifiers: iced::keyboard::Modifi
             "a" => Some(KeyboardMessage::PreviousImage),
             "q" => Some(KeyboardMessage::CropRotateLeft),
             "e" => Some(KeyboardMessage::CropRotateRight),
+            "w" => Some(KeyboardMessage::CropFlipHorizontal),
+            "s" => Some(KeyboardMessage::CropFlipVertical),
             "f" => Some(KeyboardMessage::ToggleFavorite),
             "c" => Some(KeyboardMessage::ToggleCropMode),
+            "r" => Some(KeyboardMessage::ToggleStraighten),
+            "j" => Some(KeyboardMessage::ToggleClipping),
+            "o" => Some(KeyboardMessage::ToggleMaskOverlay),
+            "y" => Some(KeyboardMessage::ToggleComparisonMode),
+            "g" => Some(KeyboardMessage::CycleCompositionGuide),
+            "G" => Some(KeyboardMessage::RotateCompositionGuide),
             _ => None
         }
     }
@@ -130,6 +170,9 @@ impl Main {
         let connection = Arc::new(Mutex::new(Connection::open(db_path).unwrap()));
         let album_repository = Arc::new(AlbumRepositoryFactory::new(connection.clone()).create());
         let settings_repository = Arc::new(SettingRepositoryFactory::new(connection.clone()).create());
+        let crop_preset_repository = CropPresetRepositoryFactory::new(connection.clone()).create();
+        let preset_repository = PresetRepositoryFactory::new(connection.clone()).create();
+        let snapshot_repository = SnapshotRepositoryFactory::new(connection.clone()).create();
 
         let image_manager = ImageManager::create_from(album_repository.clone());
         let album = Album::new(image_manager.get_all_album_images());
@@ -138,22 +181,54 @@ impl Main {
             .map(Workspace::new);
         
         let viewport = workspace.as_ref().and_then(Viewport::try_new);
+        let histogram_cache = HistogramCache::default();
         let clipboard_parameters = None;
 
         let is_save_active = settings_repository.get_parameter_value(ParameterName::ExportPath).unwrap().is_some();
+        let export_color_space = settings_repository.get_parameter_value(ParameterName::ExportColorSpace).unwrap()
+            .and_then(|value| value.parse().ok())
+            .unwrap_or_default();
+        let crop_presets = crop_preset_repository.get_crop_presets().unwrap();
+        let presets = preset_repository.get_presets().unwrap();
+        let snapshots = album.get_photo_id()
+            .map(|photo_id| snapshot_repository.get_snapshots(photo_id).unwrap())
+            .unwrap_or_default();
+        let paste_groups = Self::load_parameter_groups(&settings_repository, ParameterName::PasteGroups);
+        let sync_groups = Self::load_parameter_groups(&settings_repository, ParameterName::SyncGroups);
     
         Self {
             album,
             workspace,
             album_repository,
             settings_repository,
+            crop_preset_repository,
+            preset_repository,
+            snapshot_repository,
             image_manager,
             viewport,
+            histogram_cache,
             clipboard_parameters,
-            is_save_active
+            is_save_active,
+            export_color_space,
+            crop_presets,
+            crop_preset_input: CropPresetInput::default(),
+            presets,
+            preset_input: PresetInput::default(),
+            snapshots,
+            snapshot_name: String::new(),
+            paste_groups,
+            sync_groups,
+            group_dialog_input: None,
+            keyboard_modifiers: iced::keyboard::Modifiers::default()
         }
     }
 
+    fn load_parameter_groups(settings_repository: &SettingsRepository, parameter_name: ParameterName) -> Vec<ParameterGroup> {
+        settings_repository.get_parameter_value(parameter_name).unwrap()
+            .and_then(|value| serde_json::from_str(&value).ok())
+            .unwrap_or_else(|| ParameterGroup::BASE.to_vec())
+    }
+
     fn create_db_path() -> PathBuf {
         let config_dir = ProjectDirs::from("com", "Photo Editor", "Photo Editor")
             .unwrap()
@@ -172,7 +247,19 @@ impl Main {
                 &self.album,
                 &workspace,
                 &self.viewport,
-                self.is_save_active);
+                self.histogram_cache.get(),
+                MainWindowState {
+                    is_save_active: self.is_save_active,
+                    export_color_space: self.export_color_space,
+                    custom_crop_presets: &self.crop_presets,
+                    crop_preset_input: &self.crop_preset_input,
+                    presets: &self.presets,
+                    preset_input: &self.preset_input,
+                    snapshots: &self.snapshots,
+                    snapshot_name: &self.snapshot_name,
+                    can_paste: self.clipboard_parameters.is_some(),
+                    group_dialog_input: self.group_dialog_input.as_ref()
+                });
             window.view()
         } else {
             let window: WelcomeWindow = WelcomeWindow::new();
@@ -182,8 +269,10 @@ impl Main {
 
     pub fn subscription(&self) -> iced::Subscription<Message> {
         iced::Subscription::batch(vec![
-            iced::event::listen_with(|event, _status, _window| match event {
-                iced::Event::Keyboard(keyboard_event) => handle_keyboard_event(keyboard_event).map(Message::KeyboardMessage),
+            iced::event::listen_with(|event, status, _window| match (event, status) {
+                (iced::Event::Keyboard(iced::keyboard::Event::ModifiersChanged(modifiers)), _) => Some(Message::KeyboardMessage(KeyboardMessage::ModifiersChanged(modifiers))),
+                // Captured key presses are typed into a text input, e.g. a mask name
+                (iced::Event::Keyboard(keyboard_event), iced::event::Status::Ignored) => handle_keyboard_event(keyboard_event).map(Message::KeyboardMessage),
                 _ => None
             }),
             iced::window::close_requests().map(Message::OnWindowCloseMessage),
@@ -3,7 +3,13 @@ mod vertex;
 mod camera_uniform;
 mod parameter_uniform;
 mod crop_uniform;
-mod radial_parameter;
+pub mod radial_parameter;
+mod export_uniform;
+mod split_uniform;
+mod brush_parameter;
+pub mod brush_raster;
+pub mod cpu_pipeline;
+pub mod lens_correction;
 pub mod export_image;
 pub mod transform;
 pub mod pipeline_factory;
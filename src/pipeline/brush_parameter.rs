@@ -0,0 +1,39 @@
+use crate::view_mode::ViewMode;
+
+use super::brush_raster::MAX_BRUSH_MASKS;
+use super::parameter_uniform::ParameterUniform;
+use super::viewport::{self, ViewportWorkspace};
+
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+#[repr(C)]
+pub struct BrushParameters {
+    // The adjustments are 32 bytes, so the array keeps the 16 byte alignment of uniform arrays
+    entries: [ParameterUniform; MAX_BRUSH_MASKS],
+    count: u32,
+    cursor_x: f32,
+    cursor_y: f32,
+    cursor_radius: f32
+}
+
+impl BrushParameters {
+    pub fn new(workspace: &ViewportWorkspace) -> BrushParameters {
+        let mut entries = [ParameterUniform::default(); MAX_BRUSH_MASKS];
+        for (index, brush_mask) in workspace.parameters.brush_masks.iter().take(MAX_BRUSH_MASKS).enumerate() {
+            entries[index] = ParameterUniform::from_base_parameters(&brush_mask.base_parameters());
+        }
+        // The brush outline is only drawn while painting
+        let cursor_radius = match workspace.view_mode {
+            ViewMode::Brush(_) => {
+                workspace.brush_radius * workspace.get_image_width().max(workspace.get_image_height()) as f32
+            },
+            _ => 0.0
+        };
+        BrushParameters {
+            entries,
+            count: workspace.parameters.brush_masks.len().min(MAX_BRUSH_MASKS) as u32,
+            cursor_x: viewport::get_image_mouse_x() as f32,
+            cursor_y: viewport::get_image_mouse_y() as f32,
+            cursor_radius
+        }
+    }
+}
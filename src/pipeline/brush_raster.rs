@@ -0,0 +1,264 @@
+use crate::workspace::parameters::{BrushMask, BrushPoint, BrushStroke};
+
+pub const MAX_BRUSH_MASKS: usize = 8;
+pub const MAX_BRUSH_MASK_SIZE: usize = 1024;
+
+/**
+ * Size of the rasterized mask for an image. The mask keeps the aspect ratio of the image, but is never larger than
+ * `max_size` on its longest side.
+ */
+pub fn brush_mask_size(image_width: usize, image_height: usize, max_size: usize) -> (usize, usize) {
+    let max_side = image_width.max(image_height).max(1) as f32;
+    let scale = (max_size as f32 / max_side).min(1.0);
+    let width = ((image_width as f32 * scale).round() as usize).max(1);
+    let height = ((image_height as f32 * scale).round() as usize).max(1);
+    (width, height)
+}
+
+/**
+ * Returns the mask alpha for each pixel, row by row. Strokes are applied in order, where painting builds up towards
+ * 1.0 and erasing towards 0.0 based on the flow of the stroke.
+ */
+pub fn rasterize(brush_mask: &BrushMask, width: usize, height: usize) -> Vec<f32> {
+    let mut mask: Vec<f32> = vec![0.0; width * height];
+    let mut coverage: Vec<f32> = vec![0.0; width * height];
+    let scale = width.max(height) as f32;
+
+    for stroke in &brush_mask.strokes {
+        let Some(bounds) = stroke_bounds(stroke, scale, width, height) else {
+            continue;
+        };
+
+        for y in bounds.min_y..bounds.max_y {
+            coverage[y * width + bounds.min_x..y * width + bounds.max_x].fill(0.0);
+        }
+        draw_stroke_coverage(stroke, scale, width, &bounds, &mut coverage);
+
+        for y in bounds.min_y..bounds.max_y {
+            for x in bounds.min_x..bounds.max_x {
+                let index = y * width + x;
+                let strength = coverage[index] * stroke.flow;
+                if stroke.is_erase {
+                    mask[index] *= 1.0 - strength;
+                } else {
+                    mask[index] += (1.0 - mask[index]) * strength;
+                }
+            }
+        }
+    }
+
+    mask
+}
+
+struct PixelBounds {
+    min_x: usize,
+    min_y: usize,
+    max_x: usize,
+    max_y: usize
+}
+
+fn stroke_bounds(stroke: &BrushStroke, scale: f32, width: usize, height: usize) -> Option<PixelBounds> {
+    let radius = stroke.radius * scale;
+    let (min_x, min_y, max_x, max_y) = stroke.points.iter()
+        .fold((f32::MAX, f32::MAX, f32::MIN, f32::MIN), |(min_x, min_y, max_x, max_y), point| {
+            (min_x.min(point.x * scale), min_y.min(point.y * scale), max_x.max(point.x * scale), max_y.max(point.y * scale))
+        });
+
+    if stroke.points.is_empty() || radius <= 0.0 {
+        return None;
+    }
+
+    let bounds = PixelBounds {
+        min_x: (min_x - radius).floor().clamp(0.0, width as f32) as usize,
+        min_y: (min_y - radius).floor().clamp(0.0, height as f32) as usize,
+        max_x: (max_x + radius).ceil().clamp(0.0, width as f32) as usize,
+        max_y: (max_y + radius).ceil().clamp(0.0, height as f32) as usize
+    };
+    if bounds.min_x < bounds.max_x && bounds.min_y < bounds.max_y {
+        Some(bounds)
+    } else {
+        None
+    }
+}
+
+/**
+ * Stamps dabs along the stroke. Overlapping dabs within the same stroke don't build up, so a single stroke has an even
+ * strength given by its flow.
+ */
+fn draw_stroke_coverage(stroke: &BrushStroke, scale: f32, width: usize, bounds: &PixelBounds, coverage: &mut [f32]) {
+    let radius = stroke.radius * scale;
+    let spacing = (radius * 0.25).max(0.5);
+
+    let first = [stroke.points[0]];
+    let segments = first.iter().chain(stroke.points.iter())
+        .zip(stroke.points.iter());
+    for (from, to) in segments {
+        let length = ((to.x - from.x) * scale).hypot((to.y - from.y) * scale);
+        let steps = (length / spacing).ceil().max(1.0) as usize;
+        for step in 1..=steps {
+            let t = step as f32 / steps as f32;
+            let center = BrushPoint {
+                x: (from.x + (to.x - from.x) * t) * scale,
+                y: (from.y + (to.y - from.y) * t) * scale
+            };
+            draw_dab(&center, radius, stroke.feather, width, bounds, coverage);
+        }
+    }
+}
+
+fn draw_dab(center: &BrushPoint, radius: f32, feather: f32, width: usize, bounds: &PixelBounds, coverage: &mut [f32]) {
+    let min_x = ((center.x - radius).floor().max(0.0) as usize).max(bounds.min_x);
+    let min_y = ((center.y - radius).floor().max(0.0) as usize).max(bounds.min_y);
+    let max_x = ((center.x + radius).ceil().max(0.0) as usize).min(bounds.max_x);
+    let max_y = ((center.y + radius).ceil().max(0.0) as usize).min(bounds.max_y);
+
+    for y in min_y..max_y {
+        for x in min_x..max_x {
+            let distance = (x as f32 + 0.5 - center.x).hypot(y as f32 + 0.5 - center.y) / radius;
+            let alpha = calculate_alpha(distance, feather);
+            let index = y * width + x;
+            coverage[index] = coverage[index].max(alpha);
+        }
+    }
+}
+
+fn calculate_alpha(distance: f32, feather: f32) -> f32 {
+    if distance >= 1.0 {
+        0.0
+    } else if feather <= 0.0 {
+        1.0
+    } else {
+        let x = ((distance - (1.0 - feather)) / feather).clamp(0.0, 1.0);
+        2.0 * x * x * x - 3.0 * x * x + 1.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::*;
+
+    fn create_stroke(points: &[(f32, f32)], radius: f32, flow: f32, is_erase: bool) -> BrushStroke {
+        BrushStroke {
+            points: points.iter().map(|(x, y)| BrushPoint { x: *x, y: *y }).collect(),
+            radius,
+            feather: 0.0,
+            flow,
+            is_erase
+        }
+    }
+
+    #[rstest]
+    #[case(4000, 3000, 1024, (1024, 768))]
+    #[case(3000, 4000, 1024, (768, 1024))]
+    #[case(500, 200, 1024, (500, 200))]
+    #[case(0, 0, 1024, (1, 1))]
+    fn test_brush_mask_size(
+            #[case] image_width: usize,
+            #[case] image_height: usize,
+            #[case] max_size: usize,
+            #[case] expected: (usize, usize)) {
+        // Act
+        let actual = brush_mask_size(image_width, image_height, max_size);
+
+        // Assert
+        assert_eq!(actual, expected);
+    }
+
+    #[rstest]
+    fn test_rasterize_single_point() {
+        // Arrange
+        let brush_mask = BrushMask {
+            strokes: vec![create_stroke(&[(0.5, 0.5)], 0.1, 1.0, false)],
+            ..BrushMask::default()
+        };
+
+        // Act
+        let actual = rasterize(&brush_mask, 100, 100);
+
+        // Assert
+        assert_eq!(actual[50 * 100 + 50], 1.0);
+        assert_eq!(actual[50 * 100 + 65], 0.0);
+        assert_eq!(actual[0], 0.0);
+    }
+
+    #[rstest]
+    fn test_rasterize_is_resolution_independent() {
+        // Arrange
+        let brush_mask = BrushMask {
+            strokes: vec![create_stroke(&[(0.2, 0.2), (0.8, 0.2)], 0.05, 1.0, false)],
+            ..BrushMask::default()
+        };
+
+        // Act
+        let small = rasterize(&brush_mask, 100, 50);
+        let large = rasterize(&brush_mask, 400, 200);
+
+        // Assert
+        assert_eq!(small[20 * 100 + 50], 1.0);
+        assert_eq!(large[80 * 400 + 200], 1.0);
+        assert_eq!(small[40 * 100 + 50], 0.0);
+        assert_eq!(large[160 * 400 + 200], 0.0);
+    }
+
+    #[rstest]
+    #[case(1.0, 1.0)]
+    #[case(0.5, 0.75)]
+    #[case(0.25, 0.4375)]
+    fn test_rasterize_flow_builds_up(#[case] flow: f32, #[case] expected: f32) {
+        // Arrange
+        let brush_mask = BrushMask {
+            strokes: vec![
+                create_stroke(&[(0.5, 0.5)], 0.1, flow, false),
+                create_stroke(&[(0.5, 0.5)], 0.1, flow, false)
+            ],
+            ..BrushMask::default()
+        };
+
+        // Act
+        let actual = rasterize(&brush_mask, 100, 100);
+
+        // Assert
+        assert!((actual[50 * 100 + 50] - expected).abs() < 1e-5, "Was: {}", actual[50 * 100 + 50]);
+    }
+
+    #[rstest]
+    fn test_rasterize_erase() {
+        // Arrange
+        let brush_mask = BrushMask {
+            strokes: vec![
+                create_stroke(&[(0.2, 0.5), (0.8, 0.5)], 0.1, 1.0, false),
+                create_stroke(&[(0.5, 0.5)], 0.05, 1.0, true)
+            ],
+            ..BrushMask::default()
+        };
+
+        // Act
+        let actual = rasterize(&brush_mask, 100, 100);
+
+        // Assert
+        assert_eq!(actual[50 * 100 + 50], 0.0);
+        assert_eq!(actual[50 * 100 + 25], 1.0);
+    }
+
+    #[rstest]
+    fn test_rasterize_feather() {
+        // Arrange
+        let brush_mask = BrushMask {
+            strokes: vec![BrushStroke {
+                feather: 1.0,
+                ..create_stroke(&[(0.5, 0.5)], 0.2, 1.0, false)
+            }],
+            ..BrushMask::default()
+        };
+
+        // Act
+        let actual = rasterize(&brush_mask, 100, 100);
+
+        // Assert
+        let center = actual[50 * 100 + 50];
+        let middle = actual[50 * 100 + 60];
+        let edge = actual[50 * 100 + 69];
+        assert!(center > middle && middle > edge && edge > 0.0, "Was: {}, {}, {}", center, middle, edge);
+    }
+}
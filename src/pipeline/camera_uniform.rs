@@ -1,5 +1,6 @@
 use cgmath::{self, Matrix};
 
+use crate::comparison_mode::ComparisonMode;
 use crate::pipeline::transform::{transform, Rectangle};
 
 use super::viewport::{ViewportCrop, ViewportWorkspace};
@@ -14,6 +15,7 @@ pub struct CameraUniform {
     view_to_crop: [[f32; 4]; 4],
     base_to_image_area: [[f32; 4]; 4],
     base_to_export_area: [[f32; 4]; 4],
+    edited_to_original_window: [[f32; 4]; 4],
 }
 
 pub fn point_to_image_position(
@@ -33,6 +35,48 @@ pub fn point_to_image_position(
     }
 }
 
+/**
+ * Position within the displayed view, where (0, 0) is the top left corner and (1, 1) the bottom right corner
+ */
+pub fn point_to_view_position(
+        point: &iced::Point,
+        bounds: &Rectangle,
+        crop: &ViewportCrop) -> iced::Point {
+    let viewport_area: Rectangle = create_viewport_area(bounds, crop);
+    let uv_area: Rectangle = create_uv_area();
+
+    let transformed_point = transform(&viewport_area, &uv_area).transpose() * cgmath::vec4(point.x, point.y, 0.0, 1.0);
+    iced::Point {
+        x: transformed_point.x / transformed_point.w,
+        y: transformed_point.y / transformed_point.w,
+    }
+}
+
+/**
+ * In side by side comparison the edited image takes the right half of the bounds and the original the left half
+ */
+pub fn edited_bounds(bounds: &Rectangle, comparison_mode: ComparisonMode) -> Rectangle {
+    match comparison_mode {
+        ComparisonMode::SideBySide => Rectangle {
+            center_x: bounds.center_x + bounds.width / 4.0,
+            width: bounds.width / 2.0,
+            ..*bounds
+        },
+        _ => Rectangle { ..*bounds }
+    }
+}
+
+fn original_bounds(bounds: &Rectangle, comparison_mode: ComparisonMode) -> Rectangle {
+    match comparison_mode {
+        ComparisonMode::SideBySide => Rectangle {
+            center_x: bounds.center_x - bounds.width / 4.0,
+            width: bounds.width / 2.0,
+            ..*bounds
+        },
+        _ => Rectangle { ..*bounds }
+    }
+}
+
 fn create_render_area() -> Rectangle {
     Rectangle {
         center_x: 0.0,
@@ -43,7 +87,7 @@ fn create_render_area() -> Rectangle {
     }
 }
 
-fn create_viewport_area(bounds: &Rectangle, crop: &ViewportCrop) -> Rectangle {
+pub fn create_viewport_area(bounds: &Rectangle, crop: &ViewportCrop) -> Rectangle {
     let crop_aspect_ratio: f32 = (crop.width as f32) / (crop.height as f32);
     let bounds_aspect_ratio: f32 = bounds.width / bounds.height;
     let width: f32 = bounds.width * (crop_aspect_ratio / bounds_aspect_ratio).min(1.0) / crop.scale;
@@ -58,11 +102,12 @@ fn create_viewport_area(bounds: &Rectangle, crop: &ViewportCrop) -> Rectangle {
 }
 
 fn create_crop_area(crop: &ViewportCrop) -> Rectangle {
+    let (width, height) = crop.signed_size();
     Rectangle {
         center_x: crop.center_x as f32,
         center_y: crop.center_y as f32,
-        width: crop.width as f32,
-        height: crop.height as f32,
+        width,
+        height,
         angle_degrees: crop.angle_degrees
     }
 }
@@ -77,8 +122,9 @@ fn create_crop_relative_area(crop: &ViewportCrop, image_width: usize, image_heig
     let offset_y: f32 = (image_width_f32 - image_height_f32).max(0.0) / 2.0;
     let center_x: f32 = (crop.center_x as f32 + offset_x) / max_side;
     let center_y: f32 = (crop.center_y as f32 + offset_y) / max_side;
-    let width: f32 = (crop.width as f32) / image_width_f32;
-    let height: f32 = (crop.height as f32) / image_height_f32;
+    let (signed_width, signed_height) = crop.signed_size();
+    let width: f32 = signed_width / image_width_f32;
+    let height: f32 = signed_height / image_height_f32;
     Rectangle {
         center_x,
         center_y,
@@ -91,8 +137,7 @@ fn create_crop_relative_area(crop: &ViewportCrop, image_width: usize, image_heig
 fn create_crop_image_area(crop: &ViewportCrop) -> Rectangle {
     let center_x: f32 = crop.center_x as f32;
     let center_y: f32 = crop.center_y as f32;
-    let width: f32 = crop.width as f32;
-    let height: f32 = crop.height as f32;
+    let (width, height) = crop.signed_size();
     Rectangle {
         center_x,
         center_y,
@@ -147,8 +192,12 @@ impl CameraUniform {
         let image_width = workspace.image.width;
         let image_height = workspace.image.height;
                 
+        let edited_bounds: Rectangle = edited_bounds(bounds, workspace.comparison_mode);
+        let original_bounds: Rectangle = original_bounds(bounds, workspace.comparison_mode);
+
         let render_area: Rectangle = create_render_area();
-        let viewport_area: Rectangle = create_viewport_area(bounds, view);
+        let viewport_area: Rectangle = create_viewport_area(&edited_bounds, view);
+        let original_viewport_area: Rectangle = create_viewport_area(&original_bounds, view);
         let view_area: Rectangle = create_crop_relative_area(view, image_width, image_height);
         let crop_area: Rectangle = create_crop_relative_area(crop, image_width, image_height);
         let image_area: Rectangle = create_crop_image_area(view);
@@ -168,6 +217,40 @@ impl CameraUniform {
             view_to_crop: (base_to_aspect * crop_to_uv * aspect_to_base).into(),
             base_to_image_area: transform(&uv_area, &image_area).into(),
             base_to_export_area: transform(&uv_area, &export_area).into(),
+            edited_to_original_window: transform(&viewport_area, &original_viewport_area).into(),
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::*;
+
+    #[rstest]
+    #[case(false, false, (400.0, 450.0))]
+    #[case(true, false, (600.0, 450.0))]
+    #[case(false, true, (400.0, 550.0))]
+    #[case(true, true, (600.0, 550.0))]
+    fn test_point_to_image_position_flipped(#[case] flip_horizontal: bool, #[case] flip_vertical: bool, #[case] expected: (f32, f32)) {
+        // Arrange
+        let bounds = Rectangle { center_x: 50.0, center_y: 50.0, width: 100.0, height: 100.0, angle_degrees: 0.0 };
+        let crop = ViewportCrop {
+            center_x: 500,
+            center_y: 500,
+            width: 200,
+            height: 200,
+            angle_degrees: 0.0,
+            scale: 1.0,
+            flip_horizontal,
+            flip_vertical
+        };
+        let point = iced::Point { x: 0.0, y: 25.0 };
+
+        // Act
+        let actual = point_to_image_position(&point, &bounds, &crop);
+
+        // Assert
+        assert!((actual.x - expected.0).abs() < 1e-3 && (actual.y - expected.1).abs() < 1e-3, "Was: {:?}", actual);
+    }
+}
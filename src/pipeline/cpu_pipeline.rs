@@ -0,0 +1,542 @@
+use crate::color_profile::linear_to_srgb;
+use crate::types::{LabPixel, RgbPixel};
+
+use super::brush_raster;
+use super::parameter_uniform::ParameterUniform;
+use super::radial_parameter::{RadialParameter, RadialParameters};
+use super::viewport::ViewportParameters;
+
+/**
+ * CPU implementation of the parameter adjustments done in `shaders/image.wgsl`. It's slow compared to the shader,
+ * so it's meant for analysis on a small set of samples (histogram, etc.) rather than rendering. Keep it in sync with
+ * the shader.
+ */
+pub struct CpuPipeline {
+    parameters: ParameterUniform,
+    radial_parameters: Vec<RadialParameter>,
+    brush_masks: Vec<CpuBrushMask>,
+    image_width: f32,
+    image_height: f32
+}
+
+// Rasterized at a lower resolution than the shader, which is plenty for sampled analysis
+const CPU_BRUSH_MASK_SIZE: usize = 256;
+
+struct CpuBrushMask {
+    alpha: Vec<f32>,
+    width: usize,
+    height: usize,
+    adjustments: ParameterUniform
+}
+
+impl CpuPipeline {
+    pub fn new(parameters: &ViewportParameters, image_width: usize, image_height: usize) -> Self {
+        let (width, height) = brush_raster::brush_mask_size(image_width, image_height, CPU_BRUSH_MASK_SIZE);
+        let brush_masks = parameters.brush_masks.iter()
+            .take(brush_raster::MAX_BRUSH_MASKS)
+            .map(|brush_mask| CpuBrushMask {
+                alpha: brush_raster::rasterize(brush_mask, width, height),
+                width,
+                height,
+                adjustments: ParameterUniform::from_base_parameters(&brush_mask.base_parameters())
+            })
+            .collect();
+        Self {
+            parameters: ParameterUniform::new(parameters),
+            radial_parameters: RadialParameters::entries(parameters),
+            brush_masks,
+            image_width: image_width as f32,
+            image_height: image_height as f32
+        }
+    }
+
+    /**
+     * Source color in Oklab, as sampled by `LensCorrection::sample`. Output is sRGB encoded, position is in image
+     * coordinates.
+     */
+    pub fn process(&self, lab: LabPixel, image_x: f32, image_y: f32) -> RgbPixel {
+        let applied = self.apply_parameters(lab, image_x, image_y);
+        let processed = lab_to_rgb_pixel(&applied);
+        RgbPixel {
+            red: linear_to_srgb(processed.red.clamp(0.0, 1.0)),
+            green: linear_to_srgb(processed.green.clamp(0.0, 1.0)),
+            blue: linear_to_srgb(processed.blue.clamp(0.0, 1.0))
+        }
+    }
+
+    fn apply_parameters(&self, lab: LabPixel, image_x: f32, image_y: f32) -> LabPixel {
+        let globally_applied = self.apply_global_parameters(lab.clone());
+        let masked = self.radial_parameters.iter()
+            .fold(globally_applied, |applied, radial_parameter| {
+                apply_radial_parameters(radial_parameter, applied, &lab, image_x, image_y)
+            });
+        let view_x = image_x / self.image_width;
+        let view_y = image_y / self.image_height;
+        self.brush_masks.iter()
+            .fold(masked, |applied, brush_mask| apply_brush_parameters(brush_mask, applied, view_x, view_y))
+    }
+
+    fn apply_global_parameters(&self, lab: LabPixel) -> LabPixel {
+        apply_adjustments(lab, &self.parameters)
+    }
+}
+
+fn apply_adjustments(lab: LabPixel, adjustments: &ParameterUniform) -> LabPixel {
+    // Color adjustment
+    let mut applied = LabPixel {
+        lightness: lab.lightness,
+        tint: (lab.tint + adjustments.tint) * adjustments.saturation,
+        temperature: (lab.temperature + adjustments.temperature) * adjustments.saturation
+    };
+
+    applied = to_lightness_adjustment_space(applied);
+
+    // Lightness adjustment
+    let exposure_value = calculate_exposure_value(&applied, adjustments);
+    applied = apply_exposure(applied, exposure_value);
+    applied.lightness = (applied.lightness - 0.5) * adjustments.contrast + 0.5;
+
+    from_lightness_adjustment_space(applied)
+}
+
+fn calculate_exposure_value(lab: &LabPixel, adjustments: &ParameterUniform) -> f32 {
+    let shadows_modifier = cubic_hermite(1.0 - (0.75 - lab.lightness) / 0.75) * 2.0;
+    let midtones_modifier = cubic_hermite((lab.lightness - 0.5).abs() / 0.5);
+    let highlights_modifier = cubic_hermite(1.0 - (lab.lightness - 0.25) / 0.75) * 0.5;
+
+    shadows_modifier * adjustments.shadows +
+        midtones_modifier * adjustments.midtones +
+        highlights_modifier * adjustments.highlights +
+        adjustments.exposure
+}
+
+fn apply_radial_parameters(
+        radial_parameter: &RadialParameter,
+        lab: LabPixel,
+        lab_source: &LabPixel,
+        image_x: f32,
+        image_y: f32) -> LabPixel {
+    if radial_parameter.is_hidden != 0 {
+        return lab;
+    }
+
+    let alpha = calculate_mask_alpha(radial_parameter, lab_source, image_x, image_y);
+
+    if alpha > 0.0 {
+        let applied = apply_adjustments(lab.clone(), &radial_parameter.adjustments);
+        mix(&lab, &applied, alpha)
+    } else {
+        lab
+    }
+}
+
+fn calculate_mask_alpha(radial_parameter: &RadialParameter, lab_source: &LabPixel, image_x: f32, image_y: f32) -> f32 {
+    let is_in_bounds = image_x >= radial_parameter.bounds_min_x && image_x <= radial_parameter.bounds_max_x &&
+        image_y >= radial_parameter.bounds_min_y && image_y <= radial_parameter.bounds_max_y;
+    let mut alpha = if is_in_bounds {
+        calculate_alpha(radial_parameter, image_x, image_y)
+    } else {
+        0.0
+    };
+    if alpha > 0.0 {
+        alpha *= calculate_range_alpha(radial_parameter, lab_source);
+    }
+    if radial_parameter.is_inverted != 0 {
+        alpha = 1.0 - alpha;
+    }
+    alpha
+}
+
+fn calculate_alpha(radial_parameter: &RadialParameter, image_x: f32, image_y: f32) -> f32 {
+    let (sin, cos) = radial_parameter.angle.sin_cos();
+    let delta_x = radial_parameter.center_x - image_x;
+    let delta_y = radial_parameter.center_y - image_y;
+    let difference_x = delta_x * cos - delta_y * sin;
+    let difference_y = delta_x * sin + delta_y * cos;
+
+    if difference_x < 0.0 && 1.0 / radial_parameter.height == 0.0 {
+        return 1.0;
+    }
+
+    let distance = (difference_x * difference_x / (radial_parameter.width * radial_parameter.width) +
+        difference_y * difference_y / (radial_parameter.height * radial_parameter.height)).sqrt();
+    if radial_parameter.feather <= 0.0 {
+        if distance > 1.0 {
+            0.0
+        } else {
+            1.0
+        }
+    } else {
+        let feathered_distance = (distance - (1.0 - radial_parameter.feather)) / radial_parameter.feather;
+        cubic_hermite(feathered_distance)
+    }
+}
+
+fn calculate_range_alpha(radial_parameter: &RadialParameter, lab_source: &LabPixel) -> f32 {
+    calculate_luminance_alpha(radial_parameter, lab_source.lightness) * calculate_color_alpha(radial_parameter, lab_source)
+}
+
+fn calculate_luminance_alpha(radial_parameter: &RadialParameter, lightness: f32) -> f32 {
+    let distance = (radial_parameter.luminance_low - lightness)
+        .max(lightness - radial_parameter.luminance_high)
+        .max(0.0);
+    if radial_parameter.luminance_falloff <= 0.0 {
+        if distance <= 0.0 {
+            1.0
+        } else {
+            0.0
+        }
+    } else {
+        cubic_hermite(distance / radial_parameter.luminance_falloff)
+    }
+}
+
+fn calculate_color_alpha(radial_parameter: &RadialParameter, lab_source: &LabPixel) -> f32 {
+    let distance = ((lab_source.lightness - radial_parameter.color_lightness).powi(2) +
+        (lab_source.tint - radial_parameter.color_tint).powi(2) +
+        (lab_source.temperature - radial_parameter.color_temperature).powi(2)).sqrt();
+    cubic_hermite(distance / radial_parameter.color_tolerance)
+}
+
+fn apply_brush_parameters(brush_mask: &CpuBrushMask, lab: LabPixel, view_x: f32, view_y: f32) -> LabPixel {
+    let x = ((view_x * brush_mask.width as f32) as usize).min(brush_mask.width - 1);
+    let y = ((view_y * brush_mask.height as f32) as usize).min(brush_mask.height - 1);
+    let alpha = brush_mask.alpha[y * brush_mask.width + x];
+
+    if alpha > 0.0 {
+        let applied = apply_adjustments(lab.clone(), &brush_mask.adjustments);
+        mix(&lab, &applied, alpha)
+    } else {
+        lab
+    }
+}
+
+fn to_lightness_adjustment_space(lab: LabPixel) -> LabPixel {
+    let factor = lab.lightness + 0.1;
+    LabPixel {
+        lightness: lab.lightness,
+        tint: lab.tint / factor,
+        temperature: lab.temperature / factor
+    }
+}
+
+fn from_lightness_adjustment_space(lab: LabPixel) -> LabPixel {
+    let factor = lab.lightness + 0.1;
+    LabPixel {
+        lightness: lab.lightness,
+        tint: lab.tint * factor,
+        temperature: lab.temperature * factor
+    }
+}
+
+fn apply_exposure(lab: LabPixel, exposure: f32) -> LabPixel {
+    LabPixel {
+        lightness: lab.lightness * (exposure * 0.01 + 1.0),
+        ..lab
+    }
+}
+
+fn mix(from: &LabPixel, to: &LabPixel, alpha: f32) -> LabPixel {
+    LabPixel {
+        lightness: from.lightness * (1.0 - alpha) + to.lightness * alpha,
+        tint: from.tint * (1.0 - alpha) + to.tint * alpha,
+        temperature: from.temperature * (1.0 - alpha) + to.temperature * alpha
+    }
+}
+
+fn cubic_hermite(x: f32) -> f32 {
+    if x > 1.0 {
+        0.0
+    } else if x < 0.0 {
+        1.0
+    } else {
+        2.0 * x * x * x - 3.0 * x * x + 1.0
+    }
+}
+
+/**
+ * Conversion based on: https://bottosson.github.io/posts/oklab/
+ */
+#[allow(clippy::excessive_precision)]
+fn lab_to_rgb_pixel(lab: &LabPixel) -> RgbPixel {
+    let l = (lab.lightness + 0.3963377774 * lab.tint + 0.2158037573 * lab.temperature).powi(3);
+    let m = (lab.lightness - 0.1055613458 * lab.tint - 0.0638541728 * lab.temperature).powi(3);
+    let s = (lab.lightness - 0.0894841775 * lab.tint - 1.2914855480 * lab.temperature).powi(3);
+
+    RgbPixel {
+        red:    4.0767416621 * l - 3.3077115913 * m + 0.2309699292 * s,
+        green: -1.2684380046 * l + 2.6097574011 * m - 0.3413193965 * s,
+        blue:  -0.0041960863 * l - 0.7034186147 * m + 1.7076147010 * s,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color_profile::srgb_to_linear;
+    use crate::types::rgb_pixel_to_lab;
+    use crate::workspace::parameters::{BrushMask, BrushPoint, BrushStroke, ColorRange, LuminanceRange, MaskAdjustments, RadialMask};
+    use rstest::*;
+
+    fn process_srgb(pipeline: &CpuPipeline, rgb: &RgbPixel, image_x: f32, image_y: f32) -> RgbPixel {
+        let linear = RgbPixel {
+            red: srgb_to_linear(rgb.red),
+            green: srgb_to_linear(rgb.green),
+            blue: srgb_to_linear(rgb.blue)
+        };
+        pipeline.process(rgb_pixel_to_lab(linear), image_x, image_y)
+    }
+
+    #[rstest]
+    #[case(0.0, 0.0, 0.0)]
+    #[case(1.0, 1.0, 1.0)]
+    #[case(0.2, 0.5, 0.8)]
+    #[case(0.9, 0.1, 0.3)]
+    fn test_process_without_parameters(#[case] red: f32, #[case] green: f32, #[case] blue: f32) {
+        // Arrange
+        let pipeline = CpuPipeline::new(&ViewportParameters::default(), 100, 100);
+        let rgb = RgbPixel { red, green, blue };
+
+        // Act
+        let actual = process_srgb(&pipeline, &rgb, 0.0, 0.0);
+
+        // Assert
+        assert_rgb_equal(&actual, &rgb);
+    }
+
+    #[rstest]
+    fn test_process_exposure_brightens() {
+        // Arrange
+        let parameters = ViewportParameters {
+            exposure: 20.0,
+            ..ViewportParameters::default()
+        };
+        let pipeline = CpuPipeline::new(&parameters, 100, 100);
+        let rgb = RgbPixel { red: 0.5, green: 0.5, blue: 0.5 };
+
+        // Act
+        let actual = process_srgb(&pipeline, &rgb, 0.0, 0.0);
+
+        // Assert
+        assert!(actual.red > rgb.red && actual.green > rgb.green && actual.blue > rgb.blue, "Was: {:?}", actual);
+    }
+
+    #[rstest]
+    #[case(25.0, 25.0, true)]
+    #[case(75.0, 25.0, false)]
+    fn test_process_brush_mask_brightens_painted_area(#[case] image_x: f32, #[case] image_y: f32, #[case] expected: bool) {
+        // Arrange
+        let brush_mask = BrushMask {
+            strokes: vec![BrushStroke {
+                points: vec![BrushPoint { x: 0.25, y: 0.1 }, BrushPoint { x: 0.25, y: 0.4 }],
+                radius: 0.1,
+                feather: 0.0,
+                flow: 1.0,
+                is_erase: false
+            }],
+            brightness: 20.0,
+            ..BrushMask::default()
+        };
+        let parameters = ViewportParameters {
+            brush_masks: vec![brush_mask],
+            ..ViewportParameters::default()
+        };
+        let pipeline = CpuPipeline::new(&parameters, 100, 50);
+        let rgb = RgbPixel { red: 0.5, green: 0.5, blue: 0.5 };
+
+        // Act
+        let actual = process_srgb(&pipeline, &rgb, image_x, image_y);
+
+        // Assert
+        assert_eq!(actual.red - rgb.red > 0.01, expected, "Was: {:?}", actual);
+    }
+
+    #[rstest]
+    #[case(0.1, true)]
+    #[case(0.9, false)]
+    fn test_process_luminance_range_selects_shadows(#[case] value: f32, #[case] expected: bool) {
+        // Arrange
+        let radial_mask = RadialMask {
+            brightness: 20.0,
+            is_global: true,
+            luminance_range: Some(LuminanceRange { low: 0.0, high: 50.0, falloff: 10.0 }),
+            ..RadialMask::default()
+        };
+        let parameters = ViewportParameters {
+            radial_masks: vec![radial_mask],
+            ..ViewportParameters::default()
+        };
+        let pipeline = CpuPipeline::new(&parameters, 100, 100);
+        let rgb = RgbPixel { red: value, green: value, blue: value };
+
+        // Act
+        let actual = process_srgb(&pipeline, &rgb, 50.0, 50.0);
+
+        // Assert
+        assert_eq!(actual.red - rgb.red > 0.01, expected, "Was: {:?}", actual);
+    }
+
+    #[rstest]
+    #[case(RgbPixel { red: 0.8, green: 0.1, blue: 0.1 }, true)]
+    #[case(RgbPixel { red: 0.1, green: 0.1, blue: 0.8 }, false)]
+    fn test_process_color_range_selects_similar_color(#[case] rgb: RgbPixel, #[case] expected: bool) {
+        // Arrange
+        let sample = rgb_pixel_to_lab(RgbPixel {
+            red: srgb_to_linear(0.8),
+            green: srgb_to_linear(0.1),
+            blue: srgb_to_linear(0.1)
+        });
+        let radial_mask = RadialMask {
+            brightness: 20.0,
+            is_global: true,
+            color_range: Some(ColorRange {
+                lightness: sample.lightness,
+                tint: sample.tint,
+                temperature: sample.temperature,
+                tolerance: 20.0
+            }),
+            ..RadialMask::default()
+        };
+        let parameters = ViewportParameters {
+            radial_masks: vec![radial_mask],
+            ..ViewportParameters::default()
+        };
+        let pipeline = CpuPipeline::new(&parameters, 100, 100);
+
+        // Act
+        let actual = process_srgb(&pipeline, &rgb, 50.0, 50.0);
+
+        // Assert
+        assert_eq!(actual.red - rgb.red > 0.01, expected, "Was: {:?}", actual);
+    }
+
+    #[rstest]
+    fn test_process_range_is_intersected_with_geometry() {
+        // Arrange
+        let radial_mask = RadialMask {
+            center_x: 10,
+            center_y: 10,
+            width: 5,
+            height: 5,
+            brightness: 20.0,
+            luminance_range: Some(LuminanceRange { low: 0.0, high: 100.0, falloff: 0.0 }),
+            ..RadialMask::default()
+        };
+        let parameters = ViewportParameters {
+            radial_masks: vec![radial_mask],
+            ..ViewportParameters::default()
+        };
+        let pipeline = CpuPipeline::new(&parameters, 100, 100);
+        let rgb = RgbPixel { red: 0.5, green: 0.5, blue: 0.5 };
+
+        // Act
+        let inside = process_srgb(&pipeline, &rgb, 10.0, 10.0);
+        let outside = process_srgb(&pipeline, &rgb, 50.0, 50.0);
+
+        // Assert
+        assert!(inside.red - rgb.red > 0.01, "Was: {:?}", inside);
+        assert_rgb_equal(&outside, &rgb);
+    }
+
+    #[rstest]
+    #[case(false, false, 10.0, true)]
+    #[case(false, false, 50.0, false)]
+    #[case(true, false, 10.0, false)]
+    #[case(true, false, 50.0, true)]
+    #[case(false, true, 10.0, false)]
+    #[case(true, true, 50.0, false)]
+    fn test_process_inverted_and_hidden_mask(
+            #[case] is_inverted: bool,
+            #[case] is_hidden: bool,
+            #[case] image_x: f32,
+            #[case] expected: bool) {
+        // Arrange
+        let radial_mask = RadialMask {
+            center_x: 10,
+            center_y: 10,
+            width: 5,
+            height: 5,
+            brightness: 20.0,
+            is_inverted,
+            is_hidden,
+            ..RadialMask::default()
+        };
+        let parameters = ViewportParameters {
+            radial_masks: vec![radial_mask],
+            ..ViewportParameters::default()
+        };
+        let pipeline = CpuPipeline::new(&parameters, 100, 100);
+        let rgb = RgbPixel { red: 0.5, green: 0.5, blue: 0.5 };
+
+        // Act
+        let actual = process_srgb(&pipeline, &rgb, image_x, 10.0);
+
+        // Assert
+        assert_eq!(actual.red - rgb.red > 0.01, expected, "Was: {:?}", actual);
+    }
+
+    #[rstest]
+    fn test_process_mask_adjustments_desaturate() {
+        // Arrange
+        let radial_mask = RadialMask {
+            is_global: true,
+            adjustments: MaskAdjustments {
+                saturation: -100.0,
+                ..MaskAdjustments::default()
+            },
+            ..RadialMask::default()
+        };
+        let parameters = ViewportParameters {
+            radial_masks: vec![radial_mask],
+            ..ViewportParameters::default()
+        };
+        let pipeline = CpuPipeline::new(&parameters, 100, 100);
+        let rgb = RgbPixel { red: 0.8, green: 0.4, blue: 0.2 };
+
+        // Act
+        let actual = process_srgb(&pipeline, &rgb, 50.0, 50.0);
+
+        // Assert
+        assert!((actual.red - actual.green).abs() < 1e-2, "Was: {:?}", actual);
+        assert!((actual.green - actual.blue).abs() < 1e-2, "Was: {:?}", actual);
+    }
+
+    #[rstest]
+    #[case(25.0, 25.0, true)]
+    #[case(75.0, 25.0, false)]
+    fn test_process_brush_mask_adjustments_desaturate_painted_area(#[case] image_x: f32, #[case] image_y: f32, #[case] expected: bool) {
+        // Arrange
+        let brush_mask = BrushMask {
+            strokes: vec![BrushStroke {
+                points: vec![BrushPoint { x: 0.25, y: 0.1 }, BrushPoint { x: 0.25, y: 0.4 }],
+                radius: 0.1,
+                feather: 0.0,
+                flow: 1.0,
+                is_erase: false
+            }],
+            adjustments: MaskAdjustments {
+                saturation: -100.0,
+                ..MaskAdjustments::default()
+            },
+            ..BrushMask::default()
+        };
+        let parameters = ViewportParameters {
+            brush_masks: vec![brush_mask],
+            ..ViewportParameters::default()
+        };
+        let pipeline = CpuPipeline::new(&parameters, 100, 50);
+        let rgb = RgbPixel { red: 0.8, green: 0.4, blue: 0.2 };
+
+        // Act
+        let actual = process_srgb(&pipeline, &rgb, image_x, image_y);
+
+        // Assert
+        assert_eq!((actual.red - actual.blue).abs() < 1e-2, expected, "Was: {:?}", actual);
+    }
+
+    fn assert_rgb_equal(actual: &RgbPixel, expected: &RgbPixel) {
+        assert!((actual.red - expected.red).abs() < 1e-3, "Expected: {:?}, was: {:?}", expected, actual);
+        assert!((actual.green - expected.green).abs() < 1e-3, "Expected: {:?}, was: {:?}", expected, actual);
+        assert!((actual.blue - expected.blue).abs() < 1e-3, "Expected: {:?}, was: {:?}", expected, actual);
+    }
+}
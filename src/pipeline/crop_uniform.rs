@@ -1,3 +1,5 @@
+use crate::workspace::{handles, straighten::StraightenLine};
+
 use super::{transform::Rectangle, viewport::ViewportWorkspace};
 
 #[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
@@ -7,6 +9,22 @@ pub struct CropUniform {
     height: f32,
     ratio: f32,
     display_grid: i32,
+    display_clipping: i32,
+    display_mask_overlay: i32,
+    handle_radius: f32,
+    display_straighten_line: i32,
+    straighten_start_x: f32,
+    straighten_start_y: f32,
+    straighten_end_x: f32,
+    straighten_end_y: f32,
+    perspective_vertical: f32,
+    perspective_horizontal: f32,
+    distortion: f32,
+    composition_guide: i32,
+    composition_orientation: u32,
+    red_scale: f32,
+    blue_scale: f32,
+    defringe: f32,
 }
 
 impl CropUniform {
@@ -17,11 +35,28 @@ impl CropUniform {
         } else {
             ratio = bounds.height / (workspace.view.height as f32) * scale_factor;
         }
+        let straighten_line = workspace.straighten_line.unwrap_or(StraightenLine::new(0.0, 0.0));
         Self {
             width: workspace.parameters.crop.width as f32,
             height: workspace.parameters.crop.height as f32,
             ratio,
-            display_grid: workspace.display_grid.into()
+            display_grid: workspace.display_grid.into(),
+            display_clipping: workspace.display_clipping.into(),
+            display_mask_overlay: workspace.display_mask_overlay.into(),
+            handle_radius: handles::handle_radius(&workspace.view),
+            display_straighten_line: workspace.straighten_line.is_some().into(),
+            straighten_start_x: straighten_line.start_x,
+            straighten_start_y: straighten_line.start_y,
+            straighten_end_x: straighten_line.end_x,
+            straighten_end_y: straighten_line.end_y,
+            perspective_vertical: workspace.parameters.lens_correction.perspective_vertical,
+            perspective_horizontal: workspace.parameters.lens_correction.perspective_horizontal,
+            distortion: workspace.parameters.lens_correction.distortion,
+            composition_guide: workspace.composition_guide.index(),
+            composition_orientation: workspace.composition_orientation,
+            red_scale: workspace.parameters.lens_correction.red_scale,
+            blue_scale: workspace.parameters.lens_correction.blue_scale,
+            defringe: workspace.parameters.lens_correction.defringe
         }
     }
 }
\ No newline at end of file
@@ -1,7 +1,10 @@
 use std::path::{Path, PathBuf};
 
 use iced::widget::shader::wgpu;
+use image::ImageEncoder;
 
+use crate::color_profile::ColorSpace;
+use crate::comparison_mode::ComparisonMode;
 use crate::workspace::workspace::Workspace;
 
 use super::pipeline::Pipeline;
@@ -12,8 +15,14 @@ use super::pipeline_factory::PipelineFactory;
 pub const EXPORT_SIZE: u32 = 8192;
 
 // TODO: This should be done in a separate thread...
-pub async fn export_image(workspace: &Workspace, export_directory: PathBuf) {
+pub async fn export_image(workspace: &Workspace, export_directory: PathBuf, color_space: ColorSpace) {
     if let Some(viewport_workspace) = ViewportWorkspace::try_new(&workspace) {
+        let viewport_workspace = ViewportWorkspace {
+            export_color_space: color_space,
+            comparison_mode: ComparisonMode::Off,
+            display_grid: false,
+            ..viewport_workspace
+        };
         let file_name = workspace.get_file_name();
         export_image_from_viewport(viewport_workspace, export_directory, file_name).await
     }
@@ -34,10 +43,11 @@ async fn export_image_from_viewport(viewport_workspace: ViewportWorkspace, expor
     let pipline_factory = PipelineFactory::new(
         viewport_workspace.get_image_width(),
         viewport_workspace.get_image_height(),
+        viewport_workspace.parameters.radial_masks.len(),
         &device,
         wgpu::TextureFormat::Rgba8UnormSrgb);
 
-    let pipeline = pipline_factory.create();
+    let mut pipeline = pipline_factory.create();
     pipeline.update(&queue, &viewport_workspace, &bounds, &bounds, 1.0);
 
     let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
@@ -69,6 +79,8 @@ async fn export_image_from_viewport(viewport_workspace: ViewportWorkspace, expor
 
     let width = viewport_workspace.parameters.crop.width as u32;
     let height = viewport_workspace.parameters.crop.height as u32;
+    let export_size = viewport_workspace.parameters.export_size;
+    let color_space = viewport_workspace.export_color_space;
     let buffer = pipeline.output_texture_buffer;
     let capturable = buffer.clone();
     buffer.slice(..)
@@ -83,7 +95,7 @@ async fn export_image_from_viewport(viewport_workspace: ViewportWorkspace, expor
                 let path = Path::join(&export_directory, "image.jpg")
                     .with_file_name(file_name)
                     .with_extension("jpg");
-                write_image(&view, &path, width, height);
+                write_image(&view, &path, width, height, export_size, color_space);
 
                 drop(mapped_range);
                 capturable.unmap();
@@ -150,10 +162,10 @@ fn render(encoder: &mut wgpu::CommandEncoder, device: &wgpu::Device, pipeline: &
         occlusion_query_set: None
     });
 
-    pipeline.render_pass(&mut pass);
+    pipeline.render_pass(&mut pass, 1);
 }
 
-fn write_image(data: &Vec<u32>, path: &PathBuf, width: u32, height: u32) {
+fn write_image(data: &Vec<u32>, path: &PathBuf, width: u32, height: u32, export_size: Option<(u32, u32)>, color_space: ColorSpace) {
     let mut rgb_data: Vec<u8> = Vec::with_capacity((width * height * 3) as usize);
 
     let mut x = 0;
@@ -175,7 +187,18 @@ fn write_image(data: &Vec<u32>, path: &PathBuf, width: u32, height: u32) {
         }
     }
 
-    let image: image::RgbImage = image::RgbImage::from_raw(width, height, rgb_data).unwrap();
-    
-    image.save_with_format(path, image::ImageFormat::Jpeg).unwrap();
+    // Pixel size presets are resampled to exactly the requested size
+    let (rgb_data, width, height) = match export_size {
+        Some((export_width, export_height)) if (export_width, export_height) != (width, height) => {
+            let image = image::RgbImage::from_raw(width, height, rgb_data).unwrap();
+            let resized = image::imageops::resize(&image, export_width, export_height, image::imageops::FilterType::Lanczos3);
+            (resized.into_raw(), export_width, export_height)
+        },
+        _ => (rgb_data, width, height)
+    };
+
+    let file = std::fs::File::create(path).unwrap();
+    let mut encoder = image::codecs::jpeg::JpegEncoder::new(std::io::BufWriter::new(file));
+    encoder.set_icc_profile(color_space.to_icc_profile()).unwrap();
+    encoder.write_image(&rgb_data, width, height, image::ExtendedColorType::Rgb8).unwrap();
 }
\ No newline at end of file
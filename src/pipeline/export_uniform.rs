@@ -0,0 +1,35 @@
+use cgmath::Matrix4;
+
+use crate::color_profile::{source_to_linear_srgb, ColorSpace, ParametricCurve, ToneCurve};
+
+use super::viewport::ViewportWorkspace;
+
+// Padding is needed to reach the 16 byte alignment of uniform structs
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+#[repr(C)]
+pub struct ExportUniform {
+    // The source image is converted into linear sRGB, which is converted into the export color space
+    source_color_transform: [[f32; 4]; 4],
+    color_space_transform: [[f32; 4]; 4],
+    transfer_function: u32,
+    gamma: f32,
+    padding: [f32; 2],
+}
+
+impl ExportUniform {
+    pub fn new(workspace: &ViewportWorkspace) -> Self {
+        let color_space: ColorSpace = workspace.export_color_space;
+        let (transfer_function, gamma) = match color_space.tone_curve() {
+            ToneCurve::Parametric(curve) if curve == ParametricCurve::srgb() => (0, 2.4),
+            ToneCurve::Parametric(curve) => (1, curve.g),
+            ToneCurve::Table(_) => (0, 2.4)
+        };
+        Self {
+            source_color_transform: Matrix4::from(source_to_linear_srgb(workspace.image.is_wide_gamut)).into(),
+            color_space_transform: Matrix4::from(color_space.conversion_from_linear_srgb()).into(),
+            transfer_function,
+            gamma,
+            padding: [0.0; 2]
+        }
+    }
+}
@@ -0,0 +1,259 @@
+use crate::color_profile::srgb_to_linear;
+use crate::types::{rgb_pixel_to_lab, LabPixel, RawImage, RgbPixel};
+use crate::workspace::parameters::Crop;
+
+// Slider values at their maximum map to these coefficients. The perspective is kept below 0.5, so the projection
+// can't flip within the image.
+const MAX_PERSPECTIVE: f32 = 0.4;
+const MAX_DISTORTION: f32 = 0.3;
+// Lateral chromatic aberration is a fraction of a percent of the image radius
+const MAX_CHROMATIC_ABERRATION: f32 = 0.003;
+// Fringes are detected from the contrast between pixels this far apart
+const DEFRINGE_DISTANCE: f32 = 2.0;
+// Hues of purple and green fringes in Oklab, in radians
+const PURPLE_FRINGE_HUE: f32 = -1.05;
+const GREEN_FRINGE_HUE: f32 = 2.44;
+
+/**
+ * Geometric corrections of the source image. Everything else (crop, masks, brushes) is placed on the corrected
+ * image, so only sampling the source image goes through the correction.
+ */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LensCorrection {
+    pub perspective_vertical: f32,
+    pub perspective_horizontal: f32,
+    pub distortion: f32,
+    // Red and blue channels are sampled this much further from the center than green
+    pub red_scale: f32,
+    pub blue_scale: f32,
+    pub defringe: f32
+}
+
+impl Default for LensCorrection {
+    fn default() -> Self {
+        Self {
+            perspective_vertical: 0.0,
+            perspective_horizontal: 0.0,
+            distortion: 0.0,
+            red_scale: 1.0,
+            blue_scale: 1.0,
+            defringe: 0.0
+        }
+    }
+}
+
+impl From<&Crop> for LensCorrection {
+    fn from(crop: &Crop) -> Self {
+        Self {
+            perspective_vertical: crop.perspective_vertical / 100.0 * MAX_PERSPECTIVE,
+            perspective_horizontal: crop.perspective_horizontal / 100.0 * MAX_PERSPECTIVE,
+            // Positive values correct barrel distortion, so the source is sampled closer to the center
+            distortion: -crop.distortion / 100.0 * MAX_DISTORTION,
+            // Positive values shrink the channel, removing red or blue fringes on the outer side of edges
+            red_scale: 1.0 + crop.chromatic_red_cyan / 100.0 * MAX_CHROMATIC_ABERRATION,
+            blue_scale: 1.0 + crop.chromatic_blue_yellow / 100.0 * MAX_CHROMATIC_ABERRATION,
+            defringe: crop.defringe / 100.0
+        }
+    }
+}
+
+impl LensCorrection {
+    /**
+     * Position in the source image shown at the given position of the corrected image. Same as `correct_lens` in the
+     * shader: coordinates are relative to the image center and normalized so the corners are at a distance of 1.
+     */
+    pub fn source_position(&self, x: f32, y: f32, image_width: usize, image_height: usize) -> (f32, f32) {
+        let half_width = image_width as f32 / 2.0;
+        let half_height = image_height as f32 / 2.0;
+        let half_diagonal = half_width.hypot(half_height).max(f32::EPSILON);
+        let normalized_x = (x - half_width) / half_diagonal;
+        let normalized_y = (y - half_height) / half_diagonal;
+
+        // Keystone correction as a projection, verticals converging towards the top are straightened by a positive value
+        let w = 1.0 - self.perspective_vertical * normalized_y - self.perspective_horizontal * normalized_x;
+        let projected_x = normalized_x / w;
+        let projected_y = normalized_y / w;
+
+        // Radial lens distortion
+        let radius_squared = projected_x * projected_x + projected_y * projected_y;
+        let factor = 1.0 + self.distortion * radius_squared;
+        (projected_x * factor * half_diagonal + half_width, projected_y * factor * half_diagonal + half_height)
+    }
+
+    /**
+     * Color shown at the given position of the corrected image, with the chromatic aberration corrected and fringes
+     * removed. Same as `get_pixel_color` in the shader, except that there's no color outside the source image.
+     */
+    pub fn sample(&self, image: &RawImage, x: f32, y: f32) -> Option<LabPixel> {
+        let (source_x, source_y) = self.source_position(x, y, image.width, image.height);
+        if !(0.0..image.width as f32).contains(&source_x) || !(0.0..image.height as f32).contains(&source_y) {
+            return None;
+        }
+
+        let center_x = image.width as f32 / 2.0;
+        let center_y = image.height as f32 / 2.0;
+        let scaled_pixel = |scale: f32| linear_pixel_at(image, (source_x - center_x) * scale + center_x, (source_y - center_y) * scale + center_y);
+        let rgb = RgbPixel {
+            red: scaled_pixel(self.red_scale).red,
+            green: linear_pixel_at(image, source_x, source_y).green,
+            blue: scaled_pixel(self.blue_scale).blue
+        };
+        Some(self.defringe(rgb_pixel_to_lab(rgb), image, source_x, source_y))
+    }
+
+    /**
+     * Same as `defringe` in the shader
+     */
+    fn defringe(&self, lab: LabPixel, image: &RawImage, source_x: f32, source_y: f32) -> LabPixel {
+        if self.defringe <= 0.0 {
+            return lab;
+        }
+
+        let luminances = [(-DEFRINGE_DISTANCE, 0.0), (DEFRINGE_DISTANCE, 0.0), (0.0, -DEFRINGE_DISTANCE), (0.0, DEFRINGE_DISTANCE)]
+            .map(|(offset_x, offset_y)| luminance(&linear_pixel_at(image, source_x + offset_x, source_y + offset_y)));
+        let contrast = luminances.iter().copied().fold(f32::MIN, f32::max) - luminances.iter().copied().fold(f32::MAX, f32::min);
+        let edge = smoothstep(0.1, 0.3, contrast);
+
+        let hue = lab.temperature.atan2(lab.tint);
+        let purple = 1.0 - smoothstep(0.3, 0.7, hue_distance(hue, PURPLE_FRINGE_HUE));
+        let green = 1.0 - smoothstep(0.3, 0.7, hue_distance(hue, GREEN_FRINGE_HUE));
+        let amount = self.defringe * edge * purple.max(green);
+        LabPixel {
+            lightness: lab.lightness,
+            tint: lab.tint * (1.0 - amount),
+            temperature: lab.temperature * (1.0 - amount)
+        }
+    }
+}
+
+/**
+ * Nearest pixel in linear sRGB, positions outside the image are clamped to its edge like the shader's sampler
+ */
+fn linear_pixel_at(image: &RawImage, x: f32, y: f32) -> RgbPixel {
+    let x = x.clamp(0.0, image.width.saturating_sub(1) as f32) as usize;
+    let y = y.clamp(0.0, image.height.saturating_sub(1) as f32) as usize;
+    image.rgb_pixel_at(x, y)
+        .map(|pixel| RgbPixel {
+            red: srgb_to_linear(pixel.red),
+            green: srgb_to_linear(pixel.green),
+            blue: srgb_to_linear(pixel.blue)
+        })
+        .unwrap_or(RgbPixel { red: 0.0, green: 0.0, blue: 0.0 })
+}
+
+fn luminance(rgb: &RgbPixel) -> f32 {
+    0.2126 * rgb.red + 0.7152 * rgb.green + 0.0722 * rgb.blue
+}
+
+fn hue_distance(hue: f32, target_hue: f32) -> f32 {
+    let difference = (hue - target_hue).abs();
+    difference.min(2.0 * std::f32::consts::PI - difference)
+}
+
+fn smoothstep(edge0: f32, edge1: f32, value: f32) -> f32 {
+    let t = ((value - edge0) / (edge1 - edge0)).clamp(0.0, 1.0);
+    t * t * (3.0 - 2.0 * t)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::*;
+
+    fn create_crop(perspective_vertical: f32, perspective_horizontal: f32, distortion: f32) -> Crop {
+        Crop {
+            perspective_vertical,
+            perspective_horizontal,
+            distortion,
+            ..Crop::default()
+        }
+    }
+
+    #[rstest]
+    fn test_default_crop_has_no_correction() {
+        // Act
+        let actual = LensCorrection::from(&Crop::default());
+
+        // Assert
+        assert_eq!(actual, LensCorrection::default());
+    }
+
+    #[rstest]
+    #[case(0.0, 0.0)]
+    #[case(37.0, 12.0)]
+    #[case(200.0, 100.0)]
+    fn test_source_position_without_correction(#[case] x: f32, #[case] y: f32) {
+        // Arrange
+        let lens_correction = LensCorrection::default();
+
+        // Act
+        let actual = lens_correction.source_position(x, y, 200, 100);
+
+        // Assert
+        assert!((actual.0 - x).abs() < 1e-3 && (actual.1 - y).abs() < 1e-3, "Was: {:?}", actual);
+    }
+
+    #[rstest]
+    #[case(0.0, 0.0, 50.0)]
+    #[case(50.0, 0.0, 0.0)]
+    #[case(-50.0, 0.0, 0.0)]
+    #[case(0.0, 50.0, 0.0)]
+    fn test_center_is_fixed(#[case] perspective_vertical: f32, #[case] perspective_horizontal: f32, #[case] distortion: f32) {
+        // Arrange
+        let lens_correction = LensCorrection::from(&create_crop(perspective_vertical, perspective_horizontal, distortion));
+
+        // Act
+        let actual = lens_correction.source_position(100.0, 50.0, 200, 100);
+
+        // Assert
+        assert!((actual.0 - 100.0).abs() < 1e-3 && (actual.1 - 50.0).abs() < 1e-3, "Was: {:?}", actual);
+    }
+
+    #[rstest]
+    fn test_vertical_perspective_narrows_top() {
+        // Arrange
+        let lens_correction = LensCorrection::from(&create_crop(50.0, 0.0, 0.0));
+
+        // Act
+        let top = lens_correction.source_position(200.0, 0.0, 200, 100);
+        let bottom = lens_correction.source_position(200.0, 100.0, 200, 100);
+
+        // Assert
+        assert!(top.0 < 200.0 && bottom.0 > 200.0, "Was: {:?}, {:?}", top, bottom);
+    }
+
+    #[rstest]
+    #[case(50.0, true)]
+    #[case(-50.0, false)]
+    fn test_distortion(#[case] distortion: f32, #[case] is_sampled_inwards: bool) {
+        // Arrange
+        let lens_correction = LensCorrection::from(&create_crop(0.0, 0.0, distortion));
+
+        // Act
+        let actual = lens_correction.source_position(200.0, 100.0, 200, 100);
+
+        // Assert
+        assert_eq!(actual.0 < 200.0 && actual.1 < 100.0, is_sampled_inwards, "Was: {:?}", actual);
+    }
+
+    #[rstest]
+    fn test_sample_corrects_chromatic_aberration() {
+        // Arrange
+        // Black with white on the right, so red scaled outwards from the center is sampled from the white side
+        let pixels = (0..100 * 10)
+            .flat_map(|index| if index % 100 < 60 { [0, 0, 0, 255] } else { [255, 255, 255, 255] })
+            .collect();
+        let image = RawImage { width: 100, height: 10, pixels, is_wide_gamut: false };
+        let lens_correction = LensCorrection {
+            red_scale: 1.5,
+            ..LensCorrection::default()
+        };
+
+        // Act
+        let actual = lens_correction.sample(&image, 58.0, 5.0).unwrap();
+
+        // Assert
+        let without_correction = LensCorrection::default().sample(&image, 58.0, 5.0).unwrap();
+        assert!(actual.tint > without_correction.tint + 0.1, "Was: {:?}, {:?}", actual, without_correction);
+    }
+}
@@ -1,20 +1,35 @@
+use crate::workspace::parameters::BaseParameters;
+
 use super::viewport::ViewportParameters;
 
-#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+#[derive(Default, Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
 #[repr(C)]
 pub struct ParameterUniform {
-    exposure: f32,
-    contrast: f32,
-    shadows: f32,
-    midtones: f32,
-    highlights: f32,
-    tint: f32,
-    temperature: f32,
-    saturation: f32,
+    pub exposure: f32,
+    pub contrast: f32,
+    pub shadows: f32,
+    pub midtones: f32,
+    pub highlights: f32,
+    pub tint: f32,
+    pub temperature: f32,
+    pub saturation: f32,
 }
 
 impl ParameterUniform {
     pub fn new(parameters: &ViewportParameters) -> Self {
+        Self::from_base_parameters(&BaseParameters {
+            exposure: parameters.exposure,
+            contrast: parameters.contrast,
+            tint: parameters.tint,
+            temperature: parameters.temperature,
+            saturation: parameters.saturation,
+            shadows: parameters.shadows,
+            midtones: parameters.midtones,
+            highlights: parameters.highlights
+        })
+    }
+
+    pub fn from_base_parameters(parameters: &BaseParameters) -> Self {
         Self {
             exposure: parameters.exposure,
             contrast: (parameters.contrast * 0.5 + 100.0) / 100.0,
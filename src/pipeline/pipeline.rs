@@ -2,18 +2,61 @@ use crate::pipeline::camera_uniform;
 
 use iced::widget::shader::wgpu::{self, RenderPass};
 
-use super::{crop_uniform, parameter_uniform, radial_parameter, transform::Rectangle, viewport::ViewportWorkspace};
+use crate::workspace::parameters::BrushMask;
+
+use super::{brush_parameter, pipeline_factory::PipelineFactory, brush_raster, crop_uniform, export_uniform, parameter_uniform, radial_parameter, split_uniform, transform::Rectangle, viewport::ViewportWorkspace};
+
+/**
+ * Buffers of the uniform bind group, in binding order.
+ */
+pub struct UniformBuffers {
+    pub camera: wgpu::Buffer,
+    pub parameter: wgpu::Buffer,
+    pub crop: wgpu::Buffer,
+    pub radial_parameters: wgpu::Buffer,
+    pub export: wgpu::Buffer,
+    pub split: wgpu::Buffer,
+    pub brush_parameters: wgpu::Buffer,
+}
+
+impl UniformBuffers {
+    pub fn to_bindings(&self) -> [&wgpu::Buffer; 7] {
+        [
+            &self.camera,
+            &self.parameter,
+            &self.crop,
+            &self.radial_parameters,
+            &self.export,
+            &self.split,
+            &self.brush_parameters
+        ]
+    }
+}
+
+pub struct PipelineTextures {
+    pub diffuse: wgpu::Texture,
+    pub brush_mask: wgpu::Texture,
+    pub output: wgpu::Texture,
+}
+
+pub struct PipelineBindGroups {
+    pub uniform: wgpu::BindGroup,
+    // Kept to bind a reallocated uniform buffer again
+    pub uniform_layout: wgpu::BindGroupLayout,
+    pub diffuse: wgpu::BindGroup,
+}
 
 pub struct Pipeline {
     pipeline: wgpu::RenderPipeline,
     vertex_buffer: wgpu::Buffer,
-    camera_buffer: wgpu::Buffer,
-    parameter_buffer: wgpu::Buffer,
-    crop_buffer: wgpu::Buffer,
-    radial_parameters_buffer: wgpu::Buffer,
-    uniform_bind_group: wgpu::BindGroup,
+    uniform_buffers: UniformBuffers,
+    // Number of masks that fit in the radial parameters buffer
+    radial_mask_capacity: usize,
+    bind_groups: PipelineBindGroups,
     diffuse_texture: wgpu::Texture,
-    diffuse_bind_group: wgpu::BindGroup,
+    brush_mask_texture: wgpu::Texture,
+    // Brush masks currently in the texture, so a layer is only rasterized again when its strokes change
+    rasterized_brush_masks: Vec<BrushMask>,
     pub output_texture: wgpu::Texture,
     pub output_texture_buffer: std::sync::Arc<wgpu::Buffer>,
 }
@@ -22,33 +65,28 @@ impl Pipeline {
     pub fn new(
             pipeline: wgpu::RenderPipeline,
             vertex_buffer: wgpu::Buffer,
-            camera_buffer: wgpu::Buffer,
-            parameter_buffer: wgpu::Buffer,
-            crop_buffer: wgpu::Buffer,
-            radial_parameters_buffer: wgpu::Buffer,
-            uniform_bind_group: wgpu::BindGroup,
-            diffuse_texture: wgpu::Texture,
-            diffuse_bind_group: wgpu::BindGroup,
-            output_texture: wgpu::Texture,
+            uniform_buffers: UniformBuffers,
+            radial_mask_capacity: usize,
+            bind_groups: PipelineBindGroups,
+            textures: PipelineTextures,
             output_texture_buffer: wgpu::Buffer) -> Self {
         let output_texture_buffer = std::sync::Arc::new(output_texture_buffer);
         Self {
             pipeline,
             vertex_buffer,
-            camera_buffer,
-            parameter_buffer,
-            crop_buffer,
-            radial_parameters_buffer,
-            uniform_bind_group,
-            diffuse_texture,
-            diffuse_bind_group,
-            output_texture,
+            uniform_buffers,
+            radial_mask_capacity,
+            bind_groups,
+            diffuse_texture: textures.diffuse,
+            brush_mask_texture: textures.brush_mask,
+            rasterized_brush_masks: Vec::new(),
+            output_texture: textures.output,
             output_texture_buffer,
         }
     }
 
     pub fn update(
-            &self,
+            &mut self,
             queue: &wgpu::Queue,
             workspace: &ViewportWorkspace,
             bounds: &Rectangle,
@@ -61,11 +99,18 @@ impl Pipeline {
         let parameter_uniform = parameter_uniform::ParameterUniform::new(&workspace.parameters);
         let crop_uniform = crop_uniform::CropUniform::new(&workspace, &viewport, scale_factor);
         let radial_parameters = radial_parameter::RadialParameters::new(&workspace.parameters, workspace.view_mode);
+        let export_uniform = export_uniform::ExportUniform::new(&workspace);
+        let split_uniform = split_uniform::SplitUniform::new(workspace, bounds);
+        let brush_parameters = brush_parameter::BrushParameters::new(workspace);
 
-        queue.write_buffer(&self.camera_buffer, 0, bytemuck::bytes_of(&camera_uniform));
-        queue.write_buffer(&self.parameter_buffer, 0, bytemuck::bytes_of(&parameter_uniform));
-        queue.write_buffer(&self.crop_buffer, 0, bytemuck::bytes_of(&crop_uniform));
-        queue.write_buffer(&self.radial_parameters_buffer, 0, bytemuck::bytes_of(&radial_parameters));
+        queue.write_buffer(&self.uniform_buffers.camera, 0, bytemuck::bytes_of(&camera_uniform));
+        queue.write_buffer(&self.uniform_buffers.parameter, 0, bytemuck::bytes_of(&parameter_uniform));
+        queue.write_buffer(&self.uniform_buffers.crop, 0, bytemuck::bytes_of(&crop_uniform));
+        queue.write_buffer(&self.uniform_buffers.radial_parameters, 0, &radial_parameters.to_bytes());
+        queue.write_buffer(&self.uniform_buffers.export, 0, bytemuck::bytes_of(&export_uniform));
+        queue.write_buffer(&self.uniform_buffers.split, 0, bytemuck::bytes_of(&split_uniform));
+        queue.write_buffer(&self.uniform_buffers.brush_parameters, 0, bytemuck::bytes_of(&brush_parameters));
+        self.update_brush_masks(queue, &workspace.parameters.brush_masks);
         queue.write_texture(
             wgpu::ImageCopyTexture {
                 texture: &self.diffuse_texture,
@@ -86,14 +131,68 @@ impl Pipeline {
             }
         );
     }
+
+    pub fn can_fit_radial_masks(&self, mask_count: usize) -> bool {
+        mask_count <= self.radial_mask_capacity
+    }
+
+    /**
+     * Reallocates the mask buffer to fit the masks, leaving the rest of the pipeline as it is.
+     */
+    pub fn resize_radial_masks(&mut self, pipeline_factory: &PipelineFactory, mask_count: usize) {
+        self.radial_mask_capacity = radial_parameter::RadialParameters::buffer_capacity(mask_count);
+        self.uniform_buffers.radial_parameters = pipeline_factory.create_radial_parameters_buffer(self.radial_mask_capacity);
+        self.bind_groups.uniform = pipeline_factory.create_uniform_bind_group(&self.bind_groups.uniform_layout, &self.uniform_buffers);
+    }
+
+    fn update_brush_masks(&mut self, queue: &wgpu::Queue, brush_masks: &[BrushMask]) {
+        let width = self.brush_mask_texture.width() as usize;
+        let height = self.brush_mask_texture.height() as usize;
+
+        for (index, brush_mask) in brush_masks.iter().take(brush_raster::MAX_BRUSH_MASKS).enumerate() {
+            if self.rasterized_brush_masks.get(index) == Some(brush_mask) {
+                continue;
+            }
+
+            let mask: Vec<u8> = brush_raster::rasterize(brush_mask, width, height).iter()
+                .map(|alpha| (alpha * 255.0).round() as u8)
+                .collect();
+            queue.write_texture(
+                wgpu::ImageCopyTexture {
+                    texture: &self.brush_mask_texture,
+                    mip_level: 0,
+                    origin: wgpu::Origin3d { x: 0, y: 0, z: index as u32 },
+                    aspect: wgpu::TextureAspect::All,
+                },
+                &mask,
+                wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(width as u32),
+                    rows_per_image: Some(height as u32)
+                },
+                wgpu::Extent3d {
+                    width: width as u32,
+                    height: height as u32,
+                    depth_or_array_layers: 1
+                }
+            );
+
+            if index < self.rasterized_brush_masks.len() {
+                self.rasterized_brush_masks[index] = brush_mask.clone();
+            } else {
+                self.rasterized_brush_masks.push(brush_mask.clone());
+            }
+        }
+        self.rasterized_brush_masks.truncate(brush_masks.len());
+    }
 }
 
 impl<'a> Pipeline {
-    pub fn render_pass(&'a self, pass: &mut RenderPass<'a>) {
+    pub fn render_pass(&'a self, pass: &mut RenderPass<'a>, instance_count: u32) {
         pass.set_pipeline(&self.pipeline);
-        pass.set_bind_group(0, &self.uniform_bind_group, &[]);
-        pass.set_bind_group(1, &self.diffuse_bind_group, &[]);
+        pass.set_bind_group(0, &self.bind_groups.uniform, &[]);
+        pass.set_bind_group(1, &self.bind_groups.diffuse, &[]);
         pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
-        pass.draw(0..6, 0..1);
+        pass.draw(0..6, 0..instance_count);
     }
 }
\ No newline at end of file
@@ -7,15 +7,20 @@ use std::mem::size_of;
 use crate::pipeline::pipeline;
 use crate::pipeline::vertex;
 
+use super::brush_parameter::BrushParameters;
+use super::brush_raster;
 use super::camera_uniform::CameraUniform;
 use super::crop_uniform::CropUniform;
 use super::export_image;
+use super::export_uniform::ExportUniform;
 use super::parameter_uniform::ParameterUniform;
 use super::radial_parameter::RadialParameters;
+use super::split_uniform::SplitUniform;
 
 pub struct PipelineFactory<'a> {
     image_width: usize,
     image_height: usize,
+    radial_mask_count: usize,
     device: &'a wgpu::Device,
     format: wgpu::TextureFormat
 }
@@ -24,51 +29,73 @@ impl<'a> PipelineFactory<'a> {
     pub fn new(
             image_width: usize,
             image_height: usize,
+            radial_mask_count: usize,
             device: &'a wgpu::Device,
             format: wgpu::TextureFormat) -> Self {
-        Self { image_width, image_height, device, format }
+        Self { image_width, image_height, radial_mask_count, device, format }
     }
 
     pub fn create(&self) -> pipeline::Pipeline {
         let vertex_buffer = self.create_vertex_buffer("vertex_buffer");
 
-        let camera_buffer = self.create_uniform_buffer(size_of::<CameraUniform>(), "camera_buffer");
-        let parameter_buffer = self.create_uniform_buffer(size_of::<ParameterUniform>(), "parameter_buffer");
-        let crop_buffer = self.create_uniform_buffer(size_of::<CropUniform>(), "crop_buffer");
-        let radial_parameters_buffer = self.create_uniform_buffer(size_of::<RadialParameters>(), "radial_parameters_buffer");
+        let radial_mask_capacity = RadialParameters::buffer_capacity(self.radial_mask_count);
+        let uniform_buffers = pipeline::UniformBuffers {
+            camera: self.create_uniform_buffer(size_of::<CameraUniform>(), "camera_buffer"),
+            parameter: self.create_uniform_buffer(size_of::<ParameterUniform>(), "parameter_buffer"),
+            crop: self.create_uniform_buffer(size_of::<CropUniform>(), "crop_buffer"),
+            radial_parameters: self.create_radial_parameters_buffer(radial_mask_capacity),
+            export: self.create_uniform_buffer(size_of::<ExportUniform>(), "export_buffer"),
+            split: self.create_uniform_buffer(size_of::<SplitUniform>(), "split_buffer"),
+            brush_parameters: self.create_uniform_buffer(size_of::<BrushParameters>(), "brush_parameters_buffer")
+        };
         let output_texture_buffer = self.create_storage_buffer((4 * export_image::EXPORT_SIZE * export_image::EXPORT_SIZE) as usize, "output_texture_buffer");
 
-        let buffers = &[
-            &camera_buffer,
-            &parameter_buffer,
-            &crop_buffer,
-            &radial_parameters_buffer
+        let read_only_storage = wgpu::BufferBindingType::Storage { read_only: true };
+        let binding_types = &[
+            wgpu::BufferBindingType::Uniform,
+            wgpu::BufferBindingType::Uniform,
+            wgpu::BufferBindingType::Uniform,
+            read_only_storage,
+            wgpu::BufferBindingType::Uniform,
+            wgpu::BufferBindingType::Uniform,
+            wgpu::BufferBindingType::Uniform
         ];
-        let uniform_bind_group_layout = self.create_bind_group_layout(4, "uniform_bind_group_layout");
-        let uniform_bind_group = self.create_bind_group(&uniform_bind_group_layout, buffers, "uniform_bind_group");
+        let uniform_bind_group_layout = self.create_bind_group_layout(binding_types, "uniform_bind_group_layout");
+        let uniform_bind_group = self.create_uniform_bind_group(&uniform_bind_group_layout, &uniform_buffers);
 
-        let diffuse_texture = self.create_image_texture("diffuse_texture"); 
-        let output_texture = self.create_storage_texture("output_texture"); 
+        let textures = pipeline::PipelineTextures {
+            diffuse: self.create_image_texture("diffuse_texture"),
+            brush_mask: self.create_brush_mask_texture("brush_mask_texture"),
+            output: self.create_storage_texture("output_texture")
+        };
         let texture_bind_group_layout = self.create_texture_bind_group_layout("texture_bind_group_layout");
-        let diffuse_bind_group = self.create_diffuse_bind_group(&diffuse_texture, &output_texture, &texture_bind_group_layout, "diffuse_bind_group");
+        let diffuse_bind_group = self.create_diffuse_bind_group(&textures.diffuse, &textures.output, &textures.brush_mask, &texture_bind_group_layout, "diffuse_bind_group");
 
         let pipeline = self.create_render_pipeline(&uniform_bind_group_layout, &texture_bind_group_layout);
         
         pipeline::Pipeline::new(
             pipeline,
             vertex_buffer,
-            camera_buffer,
-            parameter_buffer,
-            crop_buffer,
-            radial_parameters_buffer,
-            uniform_bind_group,
-            diffuse_texture,
-            diffuse_bind_group,
-            output_texture,
+            uniform_buffers,
+            radial_mask_capacity,
+            pipeline::PipelineBindGroups {
+                uniform: uniform_bind_group,
+                uniform_layout: uniform_bind_group_layout,
+                diffuse: diffuse_bind_group
+            },
+            textures,
             output_texture_buffer
         )
     }
 
+    pub fn create_radial_parameters_buffer(&self, radial_mask_capacity: usize) -> wgpu::Buffer {
+        self.create_mask_buffer(RadialParameters::buffer_size(radial_mask_capacity), "radial_parameters_buffer")
+    }
+
+    pub fn create_uniform_bind_group(&self, uniform_bind_group_layout: &wgpu::BindGroupLayout, uniform_buffers: &pipeline::UniformBuffers) -> wgpu::BindGroup {
+        self.create_bind_group(uniform_bind_group_layout, &uniform_buffers.to_bindings(), "uniform_bind_group")
+    }
+
     fn create_vertex_buffer(&self, label: &str) -> wgpu::Buffer {
         self.device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
             label: Some(label),
@@ -116,6 +143,26 @@ impl<'a> PipelineFactory<'a> {
         )
     }
 
+    fn create_brush_mask_texture(&self, label: &str) -> wgpu::Texture {
+        let (width, height) = brush_raster::brush_mask_size(self.image_width, self.image_height, brush_raster::MAX_BRUSH_MASK_SIZE);
+        self.device.create_texture(
+            &wgpu::TextureDescriptor {
+                label: Some(label),
+                size: wgpu::Extent3d {
+                    width: width as u32,
+                    height: height as u32,
+                    depth_or_array_layers: brush_raster::MAX_BRUSH_MASKS as u32
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format: wgpu::TextureFormat::R8Unorm,
+                usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+                view_formats: &[],
+            }
+        )
+    }
+
     fn create_uniform_buffer(&self, size: usize, label: &str) -> wgpu::Buffer {
         self.device.create_buffer(&wgpu::BufferDescriptor {
             label: Some(label),
@@ -125,6 +172,15 @@ impl<'a> PipelineFactory<'a> {
         })
     }
 
+    fn create_mask_buffer(&self, size: usize, label: &str) -> wgpu::Buffer {
+        self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some(label),
+            size: size as u64,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        })
+    }
+
     fn create_storage_buffer(&self, size: usize, label: &str) -> wgpu::Buffer {
         self.device.create_buffer(&wgpu::BufferDescriptor {
             label: Some(label),
@@ -134,14 +190,20 @@ impl<'a> PipelineFactory<'a> {
         })
     }
 
-    fn create_bind_group_layout(&self, count: usize, label: &str) -> wgpu::BindGroupLayout {
-        let entries: Vec<wgpu::BindGroupLayoutEntry> = (0..count)
-            .map(|index| {
+    fn create_bind_group_layout(&self, binding_types: &[wgpu::BufferBindingType], label: &str) -> wgpu::BindGroupLayout {
+        let entries: Vec<wgpu::BindGroupLayoutEntry> = binding_types.iter()
+            .enumerate()
+            .map(|(index, binding_type)| {
+                // Storage buffers are only read by the fragment shader, as not every backend supports them in the vertex stage
+                let visibility = match binding_type {
+                    wgpu::BufferBindingType::Uniform => wgpu::ShaderStages::VERTEX_FRAGMENT,
+                    _ => wgpu::ShaderStages::FRAGMENT
+                };
                 wgpu::BindGroupLayoutEntry {
                     binding: index as u32,
-                    visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
+                    visibility,
                     ty: wgpu::BindingType::Buffer {
-                        ty: wgpu::BufferBindingType::Uniform,
+                        ty: *binding_type,
                         has_dynamic_offset: false,
                         min_binding_size: None,
                     },
@@ -209,6 +271,18 @@ impl<'a> PipelineFactory<'a> {
                         view_dimension: wgpu::TextureViewDimension::D2
                     },
                     count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 3,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type:  wgpu::TextureSampleType::Float {
+                            filterable: true
+                        },
+                        view_dimension: wgpu::TextureViewDimension::D2Array,
+                        multisampled: false
+                    },
+                    count: None,
                 }
             ],
         })
@@ -218,6 +292,7 @@ impl<'a> PipelineFactory<'a> {
             &self,
             diffuse_texture: &wgpu::Texture,
             output_texture: &wgpu::Texture,
+            brush_mask_texture: &wgpu::Texture,
             texture_bind_group_layout: &wgpu::BindGroupLayout,
             label: &str) -> wgpu::BindGroup {
         let diffuse_texture_view = diffuse_texture.create_view(&wgpu::TextureViewDescriptor::default());
@@ -232,6 +307,10 @@ impl<'a> PipelineFactory<'a> {
         });
 
         let output_texture_view = output_texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let brush_mask_texture_view = brush_mask_texture.create_view(&wgpu::TextureViewDescriptor {
+            dimension: Some(wgpu::TextureViewDimension::D2Array),
+            ..Default::default()
+        });
 
         self.device.create_bind_group(
             &wgpu::BindGroupDescriptor {
@@ -249,6 +328,10 @@ impl<'a> PipelineFactory<'a> {
                         wgpu::BindGroupEntry {
                             binding: 2,
                             resource: wgpu::BindingResource::TextureView(&output_texture_view)
+                        },
+                        wgpu::BindGroupEntry {
+                            binding: 3,
+                            resource: wgpu::BindingResource::TextureView(&brush_mask_texture_view)
                         }
                     ]
                 }
@@ -1,47 +1,164 @@
+use std::mem::size_of;
+
 use crate::view_mode::ViewMode;
 
+use crate::workspace::parameters::{ColorRange, LuminanceRange};
+
+use super::parameter_uniform::ParameterUniform;
 use super::viewport::ViewportParameters;
 
+// Color tolerance slider maps to this Oklab distance at its maximum
+const MAX_COLOR_TOLERANCE: f32 = 0.3;
+
+// Edits that would leave more masks than this are refused, rather than the extra masks being dropped when rendering
+pub const MAX_RADIAL_MASKS: usize = 1024;
+
+// Smallest buffer allocated, so that adding the first few masks doesn't grow the buffer every time
+const MIN_BUFFER_CAPACITY: usize = 16;
+
 #[derive(Default, Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
 #[repr(C)]
 pub struct RadialParameter {
-    center_x: f32,
-    center_y: f32,
-    width: f32,
-    height: f32,
-    angle: f32,
-    feather: f32,
-    exposure: f32,
-    draw_boundary: u32,
+    pub center_x: f32,
+    pub center_y: f32,
+    pub width: f32,
+    pub height: f32,
+    pub angle: f32,
+    pub feather: f32,
+    pub draw_boundary: u32,
+    pub luminance_low: f32,
+    pub luminance_high: f32,
+    pub luminance_falloff: f32,
+    pub color_tolerance: f32,
+    pub color_lightness: f32,
+    pub color_tint: f32,
+    pub color_temperature: f32,
+    pub is_inverted: u32,
+    pub is_hidden: u32,
+    pub adjustments: ParameterUniform,
+    // Image area where the geometric mask is non-zero
+    pub bounds_min_x: f32,
+    pub bounds_min_y: f32,
+    pub bounds_max_x: f32,
+    pub bounds_max_y: f32,
 }
 
-#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
-#[repr(C)]
+/**
+ * Masks are stored in a storage buffer as the count followed by the entries.
+ */
 pub struct RadialParameters {
-    entries: [RadialParameter; 128],
-    count: u32
+    count: u32,
+    entries: Vec<RadialParameter>
 }
 
 impl RadialParameters {
     pub fn new(parameters: &ViewportParameters, view_mode: ViewMode) -> RadialParameters {
-        let mut entries = [RadialParameter::default(); 128];
-        for (index, radial_mask) in parameters.radial_masks.iter().take(entries.len()).enumerate() {
-            entries[index].center_x = radial_mask.center_x as f32;
-            entries[index].center_y = radial_mask.center_y as f32;
-            entries[index].width = radial_mask.width as f32;
-            if radial_mask.is_linear {
-                entries[index].height = f32::INFINITY;
-            } else {
-                entries[index].height = radial_mask.height as f32;
-            }
-            entries[index].angle = radial_mask.angle_degrees / 180.0 * std::f32::consts::PI;
-            entries[index].feather = (radial_mask.feather + 100.0) / 200.0;
-            entries[index].exposure = radial_mask.brightness;
-            entries[index].draw_boundary = Self::should_draw_boundary(index, view_mode);
-        }
+        let entries: Vec<RadialParameter> = Self::entries(parameters).into_iter()
+            .take(MAX_RADIAL_MASKS)
+            .enumerate()
+            .map(|(index, entry)| RadialParameter {
+                draw_boundary: Self::should_draw_boundary(index, view_mode),
+                ..entry
+            })
+            .collect();
         RadialParameters {
-            entries,
-            count: parameters.radial_masks.len() as u32
+            count: entries.len() as u32,
+            entries
+        }
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::from(bytemuck::bytes_of(&self.count));
+        bytes.extend_from_slice(bytemuck::cast_slice(&self.entries));
+        bytes
+    }
+
+    /**
+     * Number of masks the buffer is allocated for. It grows in powers of two, so the buffer is reallocated rarely.
+     */
+    pub fn buffer_capacity(mask_count: usize) -> usize {
+        mask_count.clamp(MIN_BUFFER_CAPACITY, MAX_RADIAL_MASKS).next_power_of_two()
+    }
+
+    pub fn buffer_size(capacity: usize) -> usize {
+        size_of::<u32>() + capacity * size_of::<RadialParameter>()
+    }
+
+    pub fn entries(parameters: &ViewportParameters) -> Vec<RadialParameter> {
+        parameters.radial_masks.iter()
+            .map(|radial_mask| {
+                let (luminance_low, luminance_high, luminance_falloff) = Self::luminance_range(radial_mask.luminance_range.as_ref());
+                let (color_lightness, color_tint, color_temperature, color_tolerance) = Self::color_range(radial_mask.color_range.as_ref());
+                let center_x = radial_mask.center_x as f32;
+                let center_y = radial_mask.center_y as f32;
+                // An infinite size covers the whole image
+                let width = if radial_mask.is_global {
+                    f32::INFINITY
+                } else {
+                    radial_mask.width as f32
+                };
+                let height = if radial_mask.is_linear || radial_mask.is_global {
+                    f32::INFINITY
+                } else {
+                    radial_mask.height as f32
+                };
+                let angle = radial_mask.angle_degrees / 180.0 * std::f32::consts::PI;
+                let (bounds_min_x, bounds_min_y, bounds_max_x, bounds_max_y) = Self::bounds(center_x, center_y, width, height, angle);
+                RadialParameter {
+                    center_x,
+                    center_y,
+                    width,
+                    height,
+                    angle,
+                    feather: (radial_mask.feather + 100.0) / 200.0,
+                    draw_boundary: 0,
+                    luminance_low,
+                    luminance_high,
+                    luminance_falloff,
+                    color_tolerance,
+                    color_lightness,
+                    color_tint,
+                    color_temperature,
+                    is_inverted: radial_mask.is_inverted as u32,
+                    is_hidden: radial_mask.is_hidden as u32,
+                    adjustments: ParameterUniform::from_base_parameters(&radial_mask.base_parameters()),
+                    bounds_min_x,
+                    bounds_min_y,
+                    bounds_max_x,
+                    bounds_max_y
+                }
+            })
+            .collect()
+    }
+
+    /**
+     * Axis aligned bounding box of the rotated ellipse. Linear and global masks extend over the whole image.
+     */
+    fn bounds(center_x: f32, center_y: f32, width: f32, height: f32, angle: f32) -> (f32, f32, f32, f32) {
+        if width.is_infinite() || height.is_infinite() {
+            return (f32::NEG_INFINITY, f32::NEG_INFINITY, f32::INFINITY, f32::INFINITY);
+        }
+
+        let (sin, cos) = angle.sin_cos();
+        let half_width = (width * cos).hypot(height * sin);
+        let half_height = (width * sin).hypot(height * cos);
+        (center_x - half_width, center_y - half_height, center_x + half_width, center_y + half_height)
+    }
+
+    /**
+     * Without a range, the range is unbounded so that every pixel is selected
+     */
+    fn luminance_range(luminance_range: Option<&LuminanceRange>) -> (f32, f32, f32) {
+        match luminance_range {
+            Some(range) => (range.low / 100.0, range.high / 100.0, range.falloff / 100.0),
+            None => (f32::NEG_INFINITY, f32::INFINITY, 0.0)
+        }
+    }
+
+    fn color_range(color_range: Option<&ColorRange>) -> (f32, f32, f32, f32) {
+        match color_range {
+            Some(range) => (range.lightness, range.tint, range.temperature, range.tolerance / 100.0 * MAX_COLOR_TOLERANCE),
+            None => (0.0, 0.0, 0.0, f32::INFINITY)
         }
     }
 
@@ -57,4 +174,46 @@ impl RadialParameters {
             _ => 0
         }
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::*;
+
+    #[rstest]
+    #[case(0, 16)]
+    #[case(16, 16)]
+    #[case(17, 32)]
+    #[case(100, 128)]
+    #[case(5000, MAX_RADIAL_MASKS)]
+    fn test_buffer_capacity(#[case] mask_count: usize, #[case] expected: usize) {
+        // Act
+        let actual = RadialParameters::buffer_capacity(mask_count);
+
+        // Assert
+        assert_eq!(actual, expected);
+    }
+
+    #[rstest]
+    #[case(0.0, (90.0, 180.0, 110.0, 220.0))]
+    #[case(90.0, (80.0, 190.0, 120.0, 210.0))]
+    fn test_bounds(#[case] angle_degrees: f32, #[case] expected: (f32, f32, f32, f32)) {
+        // Act
+        let actual = RadialParameters::bounds(100.0, 200.0, 10.0, 20.0, angle_degrees.to_radians());
+
+        // Assert
+        assert!((actual.0 - expected.0).abs() < 1e-3, "Was: {:?}", actual);
+        assert!((actual.1 - expected.1).abs() < 1e-3, "Was: {:?}", actual);
+        assert!((actual.2 - expected.2).abs() < 1e-3, "Was: {:?}", actual);
+        assert!((actual.3 - expected.3).abs() < 1e-3, "Was: {:?}", actual);
+    }
+
+    #[rstest]
+    fn test_bounds_infinite_mask() {
+        // Act
+        let actual = RadialParameters::bounds(100.0, 200.0, 10.0, f32::INFINITY, 0.3);
+
+        // Assert
+        assert_eq!(actual, (f32::NEG_INFINITY, f32::NEG_INFINITY, f32::INFINITY, f32::INFINITY));
+    }
+}
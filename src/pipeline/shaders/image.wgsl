@@ -5,6 +5,7 @@ struct CameraUniform {
     view_to_crop: mat4x4<f32>,
     base_to_image_area: mat4x4<f32>,
     base_to_export_area: mat4x4<f32>,
+    edited_to_original_window: mat4x4<f32>,
 };
 @group(0) @binding(0)
 var<uniform> camera: CameraUniform;
@@ -26,7 +27,23 @@ struct CropUniform {
     width: f32,
     height: f32,
     ratio: f32,
-    display_grid: i32
+    display_grid: i32,
+    display_clipping: i32,
+    display_mask_overlay: i32,
+    handle_radius: f32,
+    display_straighten_line: i32,
+    straighten_start_x: f32,
+    straighten_start_y: f32,
+    straighten_end_x: f32,
+    straighten_end_y: f32,
+    perspective_vertical: f32,
+    perspective_horizontal: f32,
+    distortion: f32,
+    composition_guide: i32,
+    composition_orientation: u32,
+    red_scale: f32,
+    blue_scale: f32,
+    defringe: f32
 };
 @group(0) @binding(2)
 var<uniform> crop: CropUniform;
@@ -38,15 +55,60 @@ struct RadialParameter {
     height: f32,
     angle: f32,
     feather: f32,
-    exposure: f32,
     display_boundary: u32,
+    luminance_low: f32,
+    luminance_high: f32,
+    luminance_falloff: f32,
+    color_tolerance: f32,
+    color_lightness: f32,
+    color_tint: f32,
+    color_temperature: f32,
+    is_inverted: u32,
+    is_hidden: u32,
+    adjustments: ParameterUniform,
+    bounds_min_x: f32,
+    bounds_min_y: f32,
+    bounds_max_x: f32,
+    bounds_max_y: f32,
 }
 struct RadialParameters {
-    entries: array<RadialParameter, 128>,
-    count: u32
+    count: u32,
+    entries: array<RadialParameter>
 }
 @group(0) @binding(3)
-var<uniform> radial_parameters: RadialParameters;
+var<storage, read> radial_parameters: RadialParameters;
+
+struct ExportUniform {
+    // The source image is converted into linear sRGB, which is converted into the export color space
+    source_color_transform: mat4x4<f32>,
+    color_space_transform: mat4x4<f32>,
+    transfer_function: u32, // 0: sRGB, 1: gamma
+    gamma: f32,
+};
+@group(0) @binding(4)
+var<uniform> export_settings: ExportUniform;
+
+struct SplitUniform {
+    mode: u32, // 0: off, 1: vertical split, 2: horizontal split, 3: side by side
+    position: f32,
+    line_width: f32,
+    line_height: f32,
+    // Adjustments of a snapshot shown instead of the original image
+    compared_parameters: ParameterUniform,
+    has_compared_parameters: u32,
+};
+@group(0) @binding(5)
+var<uniform> split: SplitUniform;
+
+struct BrushParameters {
+    entries: array<ParameterUniform, 8>,
+    count: u32,
+    cursor_x: f32,
+    cursor_y: f32,
+    cursor_radius: f32,
+}
+@group(0) @binding(6)
+var<uniform> brush_parameters: BrushParameters;
 
 struct Vertex {
     @location(0) uv: vec2<f32>
@@ -58,13 +120,20 @@ struct VertexOutput {
     @location(1) crop_coords: vec2<f32>,
     @location(2) image_coords: vec2<f32>,
     @location(3) export_coords: vec2<f32>,
+    @location(4) base_coords: vec2<f32>,
+    @location(5) @interpolate(flat) instance: u32,
 };
 
 @vertex
-fn vs_main(vertex: Vertex) -> VertexOutput {
+fn vs_main(vertex: Vertex, @builtin(instance_index) instance: u32) -> VertexOutput {
     var out: VertexOutput;
     let base: vec4<f32> = vec4<f32>(vertex.uv, 0.0, 1.0);
-    let render_position = base * camera.base_to_viewport_window * camera.window_to_render;
+    var base_to_window: mat4x4<f32> = camera.base_to_viewport_window;
+    if (instance == 1u) {
+        // Side by side comparison, the second instance is the original image
+        base_to_window = base_to_window * camera.edited_to_original_window;
+    }
+    let render_position = base * base_to_window * camera.window_to_render;
     let view_coords = base * camera.base_to_cropped_base;
     let crop_coords = view_coords * camera.view_to_crop * camera.base_to_export_area;
     let image_coords = base * camera.base_to_image_area;
@@ -75,6 +144,8 @@ fn vs_main(vertex: Vertex) -> VertexOutput {
     out.crop_coords = crop_coords.xy / crop_coords.w;
     out.image_coords = image_coords.xy / image_coords.w;
     out.export_coords = export_coords.xy / export_coords.w;
+    out.base_coords = vertex.uv;
+    out.instance = instance;
     return out;
 }
 
@@ -84,20 +155,31 @@ var t_diffuse: texture_2d<f32>;
 var s_diffuse: sampler;
 @group(1) @binding(2)
 var t_output: texture_storage_2d<rgba8unorm, write>;
+@group(1) @binding(3)
+var t_brush_masks: texture_2d_array<f32>;
 
 @fragment
 fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
     if (all(in.view_coords >= vec2(0.0) && in.view_coords <= vec2(1.0))) {
-        let lab_actual: vec3<f32> = get_pixel_color(in);
-        let lab_crop: vec3<f32> = draw_crop_area(in, lab_actual);
-        let lab_mask: vec3<f32> = draw_mask_boundaries(in, lab_crop);
-        let lab_final: vec3<f32> = draw_grid(in, lab_mask);
-
-        let rgb_actual: vec3<f32> = lab_to_rgb(lab_actual);
+        let lab_source: vec3<f32> = get_pixel_color(in);
+        let lab_edited: vec3<f32> = apply_parameters(lab_source, in);
+        let lab_actual: vec3<f32> = select(lab_edited, get_compared_color(lab_source), show_original(in));
+        let lab_clipping: vec3<f32> = draw_clipping(lab_actual);
+        let lab_crop: vec3<f32> = draw_crop_area(in, lab_clipping);
+        let lab_overlay: vec3<f32> = draw_mask_overlays(in, lab_source, lab_crop);
+        let lab_mask: vec3<f32> = draw_mask_boundaries(in, lab_overlay);
+        let lab_handles: vec3<f32> = draw_mask_handles(in, lab_mask);
+        let lab_brush: vec3<f32> = draw_brush_cursor(in, lab_handles);
+        let lab_grid: vec3<f32> = draw_grid(in, lab_brush);
+        let lab_crop_handles: vec3<f32> = draw_crop_handles(in, lab_grid);
+        let lab_straighten: vec3<f32> = draw_straighten_line(in, lab_crop_handles);
+        let lab_final: vec3<f32> = draw_split_line(in, lab_straighten);
+
+        let rgb_edited: vec3<f32> = lab_to_rgb(lab_edited);
         let rgb_final: vec3<f32> = lab_to_rgb(lab_final);
 
-        let gamma_corrected: vec3<f32> = pow(rgb_actual, vec3(1.0/2.2));
-        textureStore(t_output, vec2<i32>(in.export_coords.xy), vec4<f32>(gamma_corrected, 1.0));
+        let rgb_export: vec3<f32> = encode_export_color(rgb_edited);
+        textureStore(t_output, vec2<i32>(in.export_coords.xy), vec4<f32>(rgb_export, 1.0));
 
         return vec4<f32>(rgb_final, 1.0);
     } else {
@@ -107,32 +189,128 @@ fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
 }
 
 fn get_pixel_color(vertex: VertexOutput) -> vec3<f32> {
-    let texture_sample: vec4<f32> = textureSample(t_diffuse, s_diffuse, vertex.view_coords);
-    let rgb: vec3<f32> = texture_sample.xyz;
-    let lab: vec3<f32> = rgb_to_lab(rgb);
-    return apply_parameters(lab, vertex);
+    let source_coords: vec2<f32> = correct_lens(vertex.view_coords);
+    let texture_sample: vec3<f32> = source_to_linear_srgb(textureSample(t_diffuse, s_diffuse, source_coords).xyz);
+    let red: f32 = sample_channel(source_coords, crop.red_scale).x;
+    let blue: f32 = sample_channel(source_coords, crop.blue_scale).z;
+    // Corrections can pull in area outside the source, which is shown like the area outside the image
+    let is_inside = all(source_coords >= vec2(0.0) && source_coords <= vec2(1.0));
+    let rgb: vec3<f32> = select(vec3(1.0), vec3(red, texture_sample.y, blue), is_inside);
+    return defringe(rgb_to_lab(rgb), source_coords);
+}
+
+/**
+ * Lateral chromatic aberration scales a channel radially, so it's sampled scaled around the image center
+ */
+fn sample_channel(source_coords: vec2<f32>, scale: f32) -> vec3<f32> {
+    let coords = (source_coords - 0.5) * scale + 0.5;
+    return source_to_linear_srgb(textureSampleLevel(t_diffuse, s_diffuse, coords, 0.0).xyz);
+}
+
+/**
+ * Wide gamut images are stored with Rec. 2020 primaries, their wider colors end up outside 0-1
+ */
+fn source_to_linear_srgb(rgb: vec3<f32>) -> vec3<f32> {
+    return (export_settings.source_color_transform * vec4<f32>(rgb, 1.0)).xyz;
+}
+
+/**
+ * Desaturates purple and green pixels next to high contrast edges, where fringes show up
+ */
+fn defringe(lab: vec3<f32>, source_coords: vec2<f32>) -> vec3<f32> {
+    if (crop.defringe <= 0.0) {
+        return lab;
+    }
+
+    let texel = 2.0 / vec2<f32>(textureDimensions(t_diffuse));
+    let left = luminance(sample_channel(source_coords - vec2(texel.x, 0.0), 1.0));
+    let right = luminance(sample_channel(source_coords + vec2(texel.x, 0.0), 1.0));
+    let top = luminance(sample_channel(source_coords - vec2(0.0, texel.y), 1.0));
+    let bottom = luminance(sample_channel(source_coords + vec2(0.0, texel.y), 1.0));
+    let contrast = max(max(left, right), max(top, bottom)) - min(min(left, right), min(top, bottom));
+    let edge = smoothstep(0.1, 0.3, contrast);
+
+    // Purple fringes have a hue around -60 degrees and green ones around 140 degrees
+    let hue = atan2(lab.z, lab.y);
+    let purple = 1.0 - smoothstep(0.3, 0.7, hue_distance(hue, -1.05));
+    let green = 1.0 - smoothstep(0.3, 0.7, hue_distance(hue, 2.44));
+    let amount = crop.defringe * edge * max(purple, green);
+    return vec3<f32>(lab.x, lab.yz * (1.0 - amount));
+}
+
+fn luminance(rgb: vec3<f32>) -> f32 {
+    return dot(rgb, vec3(0.2126, 0.7152, 0.0722));
+}
+
+fn hue_distance(hue: f32, target_hue: f32) -> f32 {
+    let difference = abs(hue - target_hue);
+    return min(difference, 2.0 * 3.14159265 - difference);
+}
+
+/**
+ * Same as LensCorrection::source_position, but in texture coordinates
+ */
+fn correct_lens(view_coords: vec2<f32>) -> vec2<f32> {
+    let size = vec2<f32>(textureDimensions(t_diffuse));
+    let half_diagonal = length(size) / 2.0;
+    let normalized = (view_coords - 0.5) * size / half_diagonal;
+
+    let w = 1.0 - crop.perspective_vertical * normalized.y - crop.perspective_horizontal * normalized.x;
+    let projected = normalized / w;
+
+    let distorted = projected * (1.0 + crop.distortion * dot(projected, projected));
+    return distorted * half_diagonal / size + 0.5;
+}
+
+fn get_compared_color(lab_source: vec3<f32>) -> vec3<f32> {
+    if (split.has_compared_parameters == 1u) {
+        return apply_adjustments(lab_source, split.compared_parameters);
+    }
+    return lab_source;
+}
+
+fn show_original(vertex: VertexOutput) -> bool {
+    switch (split.mode) {
+        case 1u: {
+            return vertex.base_coords.x < split.position;
+        }
+        case 2u: {
+            return vertex.base_coords.y < split.position;
+        }
+        case 3u: {
+            return vertex.instance == 1u;
+        }
+        default: {
+            return false;
+        }
+    }
 }
 
 fn apply_parameters(lab: vec3<f32>, vertex: VertexOutput) -> vec3<f32> {
     let globally_applied: vec3<f32> = apply_global_parameters(lab);
-    let masked: vec3<f32> = apply_all_radial_parameters(globally_applied, vertex);
-    return masked;
+    let masked: vec3<f32> = apply_all_radial_parameters(globally_applied, lab, vertex);
+    let brushed: vec3<f32> = apply_all_brush_parameters(masked, vertex);
+    return brushed;
 }
 
 fn apply_global_parameters(lab: vec3<f32>) -> vec3<f32> {
+    return apply_adjustments(lab, parameters);
+}
+
+fn apply_adjustments(lab: vec3<f32>, adjustments: ParameterUniform) -> vec3<f32> {
     var applied: vec3<f32> = lab;
 
     // Color adjustment
-    applied += vec3<f32>(0.0, parameters.tint, parameters.temperature);
-    applied *= vec3<f32>(1.0, parameters.saturation, parameters.saturation);
+    applied += vec3<f32>(0.0, adjustments.tint, adjustments.temperature);
+    applied *= vec3<f32>(1.0, adjustments.saturation, adjustments.saturation);
 
     applied = to_lightness_adjustment_space(applied);
 
     // Lightness adjustment
-    var exposure_value = calculate_exposure_value(applied);
+    var exposure_value = calculate_exposure_value(applied, adjustments);
     applied = apply_exposure(applied, exposure_value);
     applied -= vec3<f32>(0.5, 0.0, 0.0);
-    applied *= vec3<f32>(parameters.contrast, 1.0, 1.0);
+    applied *= vec3<f32>(adjustments.contrast, 1.0, 1.0);
     applied += vec3<f32>(0.5, 0.0, 0.0);
 
     applied = from_lightness_adjustment_space(applied);
@@ -147,7 +325,7 @@ fn from_lightness_adjustment_space(lab: vec3<f32>) -> vec3<f32> {
     return lab * vec3<f32>(1.0, lab.x + 0.1, lab.x + 0.1);
 }
 
-fn calculate_exposure_value(lab: vec3<f32>) -> f32 {
+fn calculate_exposure_value(lab: vec3<f32>, adjustments: ParameterUniform) -> f32 {
     // Shadows range: [0.0, 0.75]
     // Midtones range: [0.0, 1.0]
     // Highlights range: [0.25, 1.0]
@@ -155,43 +333,62 @@ fn calculate_exposure_value(lab: vec3<f32>) -> f32 {
     var midtones_modifier: f32 = cubic_hermite(abs(lab.x - 0.5) / 0.5);
     var highlights_modifier: f32 = cubic_hermite(1.0 - (lab.x - 0.25) / 0.75) * 0.5;
 
-    return shadows_modifier * parameters.shadows +
-        midtones_modifier * parameters.midtones +
-        highlights_modifier * parameters.highlights +
-        parameters.exposure;
+    return shadows_modifier * adjustments.shadows +
+        midtones_modifier * adjustments.midtones +
+        highlights_modifier * adjustments.highlights +
+        adjustments.exposure;
 }
 
 fn apply_exposure(lab: vec3<f32>, exposure: f32) -> vec3<f32> {
     return lab * vec3<f32>((exposure * 0.01) + 1.0, 1.0, 1.0);
 }
 
-fn apply_all_radial_parameters(lab: vec3<f32>, vertex: VertexOutput) -> vec3<f32> {
+fn apply_all_radial_parameters(lab: vec3<f32>, lab_source: vec3<f32>, vertex: VertexOutput) -> vec3<f32> {
     var applied: vec3<f32> = lab;
 
     for (var index = 0u; index < radial_parameters.count; index++) {
-        applied = apply_radial_parameters(index, applied, vertex);
+        applied = apply_radial_parameters(index, applied, lab_source, vertex);
     }
 
     return applied;
 }
 
-fn apply_radial_parameters(index: u32, lab: vec3<f32>, vertex: VertexOutput) -> vec3<f32> {
+fn apply_radial_parameters(index: u32, lab: vec3<f32>, lab_source: vec3<f32>, vertex: VertexOutput) -> vec3<f32> {
     let radial_parameter = radial_parameters.entries[index];
+    if (radial_parameter.is_hidden != 0u) {
+        return lab;
+    }
 
-    let alpha = calculate_alpha(vertex, radial_parameter);
-
+    let alpha = calculate_mask_alpha(vertex, lab_source, radial_parameter);
     if (alpha > 0.0) {
-        var applied: vec3<f32> = lab;
-        applied = to_lightness_adjustment_space(applied);
-        applied = apply_exposure(applied, radial_parameter.exposure);
-        applied = from_lightness_adjustment_space(applied);
-
+        let applied: vec3<f32> = apply_adjustments(lab, radial_parameter.adjustments);
         return lab * (1.0 - alpha) + applied * alpha;
     } else {
         return lab;
     }
 }
 
+fn calculate_mask_alpha(vertex: VertexOutput, lab_source: vec3<f32>, radial_parameter: RadialParameter) -> f32 {
+    // Geometric and range masks are intersected
+    var alpha = 0.0;
+    if (in_mask_bounds(vertex, radial_parameter)) {
+        alpha = calculate_alpha(vertex, radial_parameter);
+    }
+    if (alpha > 0.0) {
+        alpha *= calculate_range_alpha(lab_source, radial_parameter);
+    }
+    if (radial_parameter.is_inverted != 0u) {
+        alpha = 1.0 - alpha;
+    }
+    return alpha;
+}
+
+fn in_mask_bounds(vertex: VertexOutput, radial_parameter: RadialParameter) -> bool {
+    let position = vertex.image_coords;
+    return position.x >= radial_parameter.bounds_min_x && position.x <= radial_parameter.bounds_max_x &&
+        position.y >= radial_parameter.bounds_min_y && position.y <= radial_parameter.bounds_max_y;
+}
+
 fn calculate_alpha(vertex: VertexOutput, radial_parameter: RadialParameter) -> f32 {
     let angle_matrix = mat2x2<f32>(
         cos(radial_parameter.angle), -sin(radial_parameter.angle),
@@ -223,6 +420,47 @@ fn calculate_alpha(vertex: VertexOutput, radial_parameter: RadialParameter) -> f
     }
 }
 
+fn apply_all_brush_parameters(lab: vec3<f32>, vertex: VertexOutput) -> vec3<f32> {
+    var applied: vec3<f32> = lab;
+
+    for (var index = 0u; index < brush_parameters.count; index++) {
+        applied = apply_brush_parameters(index, applied, vertex);
+    }
+
+    return applied;
+}
+
+fn apply_brush_parameters(index: u32, lab: vec3<f32>, vertex: VertexOutput) -> vec3<f32> {
+    // Brush masks cover the full image, so they share texture coordinates with the image
+    let alpha = textureSampleLevel(t_brush_masks, s_diffuse, vertex.view_coords, index, 0.0).x;
+
+    if (alpha > 0.0) {
+        let applied: vec3<f32> = apply_adjustments(lab, brush_parameters.entries[index]);
+        return lab * (1.0 - alpha) + applied * alpha;
+    } else {
+        return lab;
+    }
+}
+
+fn calculate_range_alpha(lab_source: vec3<f32>, radial_parameter: RadialParameter) -> f32 {
+    return calculate_luminance_alpha(lab_source.x, radial_parameter) * calculate_color_alpha(lab_source, radial_parameter);
+}
+
+fn calculate_luminance_alpha(lightness: f32, radial_parameter: RadialParameter) -> f32 {
+    let distance = max(max(radial_parameter.luminance_low - lightness, lightness - radial_parameter.luminance_high), 0.0);
+    if (radial_parameter.luminance_falloff <= 0.0) {
+        return select(0.0, 1.0, distance <= 0.0);
+    } else {
+        return cubic_hermite(distance / radial_parameter.luminance_falloff);
+    }
+}
+
+fn calculate_color_alpha(lab_source: vec3<f32>, radial_parameter: RadialParameter) -> f32 {
+    let color = vec3<f32>(radial_parameter.color_lightness, radial_parameter.color_tint, radial_parameter.color_temperature);
+    let distance = length(lab_source - color);
+    return cubic_hermite(distance / radial_parameter.color_tolerance);
+}
+
 fn cubic_hermite(x: f32) -> f32 {
     if (x > 1.0) {
         return 0.0;
@@ -233,6 +471,31 @@ fn cubic_hermite(x: f32) -> f32 {
     }
 }
 
+fn draw_split_line(vertex: VertexOutput, lab: vec3<f32>) -> vec3<f32> {
+    if (split.mode == 1u && abs(vertex.base_coords.x - split.position) <= split.line_width) {
+        return draw_line_at_pixel(lab);
+    } else if (split.mode == 2u && abs(vertex.base_coords.y - split.position) <= split.line_height) {
+        return draw_line_at_pixel(lab);
+    } else {
+        return lab;
+    }
+}
+
+fn draw_clipping(lab: vec3<f32>) -> vec3<f32> {
+    if (crop.display_clipping == 0) {
+        return lab;
+    }
+
+    let rgb: vec3<f32> = lab_to_rgb(lab);
+    if (any(rgb >= vec3(1.0))) {
+        return rgb_to_lab(vec3<f32>(1.0, 0.0, 0.0));
+    } else if (any(rgb <= vec3(0.0))) {
+        return rgb_to_lab(vec3<f32>(0.0, 0.0, 1.0));
+    } else {
+        return lab;
+    }
+}
+
 fn draw_crop_area(vertex: VertexOutput, lab: vec3<f32>) -> vec3<f32> {
     if (in_crop_area(vertex)) {
         return lab;
@@ -263,6 +526,26 @@ fn in_crop_border(vertex: VertexOutput) -> bool {
     }
 }
 
+fn draw_mask_overlays(vertex: VertexOutput, lab_source: vec3<f32>, lab: vec3<f32>) -> vec3<f32> {
+    if (crop.display_mask_overlay == 0) {
+        return lab;
+    }
+
+    var applied: vec3<f32> = lab;
+
+    // Only the mask being edited has its boundary displayed
+    for (var index = 0u; index < radial_parameters.count; index++) {
+        let radial_parameter: RadialParameter = radial_parameters.entries[index];
+        if (radial_parameter.display_boundary != 0) {
+            let alpha = calculate_mask_alpha(vertex, lab_source, radial_parameter);
+            let overlay = rgb_to_lab(vec3<f32>(1.0, 0.0, 0.0));
+            applied = mix(applied, overlay, alpha * 0.5);
+        }
+    }
+
+    return applied;
+}
+
 fn draw_mask_boundaries(vertex: VertexOutput, lab: vec3<f32>) -> vec3<f32> {
     var applied: vec3<f32> = lab;
 
@@ -301,16 +584,264 @@ fn draw_mask_boundary(index: u32, vertex: VertexOutput, lab: vec3<f32>) -> vec3<
     }
 }
 
+fn draw_mask_handles(vertex: VertexOutput, lab: vec3<f32>) -> vec3<f32> {
+    var applied: vec3<f32> = lab;
+
+    for (var index = 0u; index < radial_parameters.count; index++) {
+        applied = draw_mask_handle(index, vertex, applied);
+    }
+
+    return applied;
+}
+
+/**
+ * Handles are placed like in the workspace handles module, so that what is drawn is what can be grabbed
+ */
+fn draw_mask_handle(index: u32, vertex: VertexOutput, lab: vec3<f32>) -> vec3<f32> {
+    let radial_parameter: RadialParameter = radial_parameters.entries[index];
+
+    // Global masks don't have handles
+    if (radial_parameter.display_boundary == 0 || is_infinite(radial_parameter.width)) {
+        return lab;
+    }
+
+    let angle_matrix = mat2x2<f32>(
+        cos(radial_parameter.angle), -sin(radial_parameter.angle),
+        sin(radial_parameter.angle), cos(radial_parameter.angle)
+    );
+
+    let relative_position = vertex.image_coords - vec2<f32>(radial_parameter.center_x, radial_parameter.center_y);
+    let angled_position = relative_position * angle_matrix;
+
+    let width = radial_parameter.width;
+    let is_linear = is_infinite(radial_parameter.height);
+    let rotation_distance = select(radial_parameter.height, width, is_linear) + crop.handle_radius * 4.0;
+
+    var in_handle = in_handle_disc(angled_position, vec2<f32>(0.0, 0.0)) ||
+        in_handle_disc(angled_position, vec2<f32>(width, 0.0)) ||
+        in_handle_disc(angled_position, vec2<f32>(-width * (1.0 - radial_parameter.feather), 0.0)) ||
+        in_handle_disc(angled_position, vec2<f32>(0.0, -rotation_distance));
+    if (!is_linear) {
+        in_handle = in_handle || in_handle_disc(angled_position, vec2<f32>(0.0, radial_parameter.height));
+    }
+
+    if (in_handle) {
+        return draw_line_at_pixel(lab);
+    } else {
+        return lab;
+    }
+}
+
+// Larger than any finite f32
+fn is_infinite(value: f32) -> bool {
+    return value > 3.4e38;
+}
+
+fn in_handle_disc(position: vec2<f32>, handle_position: vec2<f32>) -> bool {
+    let distance = length(position - handle_position);
+    return distance <= crop.handle_radius && distance >= crop.handle_radius * 0.6;
+}
+
+fn draw_crop_handles(vertex: VertexOutput, lab: vec3<f32>) -> vec3<f32> {
+    if (crop.display_grid == 0) {
+        return lab;
+    }
+
+    // Corners and edge midpoints, with the squares kept inside the crop
+    let size = vec2<f32>(crop.width, crop.height);
+    let radius = min(crop.handle_radius, min(crop.width, crop.height) / 4.0);
+    for (var x = 0u; x <= 2u; x++) {
+        for (var y = 0u; y <= 2u; y++) {
+            if (x == 1u && y == 1u) {
+                continue;
+            }
+            let anchor = vec2<f32>(f32(x), f32(y)) / 2.0 * size;
+            let handle_position = clamp(anchor, vec2(radius), size - radius);
+            if (all(abs(vertex.crop_coords - handle_position) <= vec2(radius))) {
+                return draw_line_at_pixel(lab);
+            }
+        }
+    }
+
+    return lab;
+}
+
+fn draw_straighten_line(vertex: VertexOutput, lab: vec3<f32>) -> vec3<f32> {
+    if (crop.display_straighten_line == 0) {
+        return lab;
+    }
+
+    let start = vec2<f32>(crop.straighten_start_x, crop.straighten_start_y);
+    let end = vec2<f32>(crop.straighten_end_x, crop.straighten_end_y);
+    let line = end - start;
+    let along = clamp(dot(vertex.image_coords - start, line) / max(dot(line, line), 1e-6), 0.0, 1.0);
+    let distance = length(vertex.image_coords - (start + line * along));
+
+    if (distance <= crop.handle_radius * 0.2) {
+        return draw_line_at_pixel(lab);
+    } else {
+        return lab;
+    }
+}
+
+fn draw_brush_cursor(vertex: VertexOutput, lab: vec3<f32>) -> vec3<f32> {
+    if (brush_parameters.cursor_radius <= 0.0) {
+        return lab;
+    }
+
+    let cursor = vec2<f32>(brush_parameters.cursor_x, brush_parameters.cursor_y);
+    let distance = length(vertex.image_coords - cursor);
+    if (abs(distance - brush_parameters.cursor_radius) <= 1.5 / crop.ratio) {
+        return draw_line_at_pixel(lab);
+    } else {
+        return lab;
+    }
+}
+
 fn draw_grid(vertex: VertexOutput, lab: vec3<f32>) -> vec3<f32> {
     if (crop.display_grid == 0) {
         return lab;
-    } else if (in_big_grid(vertex) || in_small_grid(vertex)) {
+    } else if (in_composition_guide(vertex)) {
         return draw_line_at_pixel(lab);
     } else {
         return lab;
     }
 }
 
+/**
+ * Guides are drawn in screen pixels, so lines keep their width regardless of zoom. Indices match CompositionGuide.
+ */
+fn in_composition_guide(vertex: VertexOutput) -> bool {
+    let size = vec2<f32>(crop.width, crop.height) * crop.ratio;
+    var position = vertex.crop_coords * crop.ratio;
+    if ((crop.composition_orientation & 1u) != 0u) {
+        position.x = size.x - position.x;
+    }
+    if ((crop.composition_orientation & 2u) != 0u) {
+        position.y = size.y - position.y;
+    }
+
+    switch crop.composition_guide {
+        case 1: {
+            return in_grid_lines(position, size, 1.0 / 3.0);
+        }
+        case 2: {
+            return in_grid_lines(position, size, 0.381966);
+        }
+        case 3: {
+            return in_golden_spiral(position, size);
+        }
+        case 4: {
+            return in_line(position, vec2(0.0), size) || in_line(position, vec2(size.x, 0.0), vec2(0.0, size.y));
+        }
+        case 5: {
+            return in_golden_triangles(position, size);
+        }
+        case 6: {
+            return in_center_cross(position, size);
+        }
+        default: {
+            return in_big_grid(vertex) || in_small_grid(vertex);
+        }
+    }
+}
+
+/**
+ * Two lines on each axis, at the fraction from both edges
+ */
+fn in_grid_lines(position: vec2<f32>, size: vec2<f32>, fraction: f32) -> bool {
+    return in_pixel(position.x, size.x * fraction) ||
+        in_pixel(position.x, size.x * (1.0 - fraction)) ||
+        in_pixel(position.y, size.y * fraction) ||
+        in_pixel(position.y, size.y * (1.0 - fraction));
+}
+
+fn in_line(position: vec2<f32>, start: vec2<f32>, end: vec2<f32>) -> bool {
+    let direction = end - start;
+    let t = clamp(dot(position - start, direction) / max(dot(direction, direction), 1e-6), 0.0, 1.0);
+    return distance(position, start + t * direction) <= 0.75;
+}
+
+/**
+ * Quarter arcs through successively smaller golden rectangles, cut off from the left, top, right and bottom in turn.
+ * The rectangles follow the crop, so the arcs are elliptical when the crop isn't a golden rectangle.
+ */
+fn in_golden_spiral(position: vec2<f32>, size: vec2<f32>) -> bool {
+    var rectangle_min = vec2(0.0);
+    var rectangle_max = size;
+    for (var i = 0; i < 12; i++) {
+        let rectangle_size = rectangle_max - rectangle_min;
+        if (rectangle_size.x < 1.0 || rectangle_size.y < 1.0) {
+            break;
+        }
+
+        var part_min = rectangle_min;
+        var part_max = rectangle_max;
+        var center = vec2(0.0);
+        switch i % 4 {
+            case 0: {
+                part_max.x = rectangle_min.x + rectangle_size.x * 0.618034;
+                center = vec2(part_max.x, part_max.y);
+                rectangle_min.x = part_max.x;
+            }
+            case 1: {
+                part_max.y = rectangle_min.y + rectangle_size.y * 0.618034;
+                center = vec2(part_min.x, part_max.y);
+                rectangle_min.y = part_max.y;
+            }
+            case 2: {
+                part_min.x = rectangle_max.x - rectangle_size.x * 0.618034;
+                center = vec2(part_min.x, part_min.y);
+                rectangle_max.x = part_min.x;
+            }
+            default: {
+                part_min.y = rectangle_max.y - rectangle_size.y * 0.618034;
+                center = vec2(part_max.x, part_min.y);
+                rectangle_max.y = part_min.y;
+            }
+        }
+
+        let in_part = all(position >= part_min - 1.0) && all(position <= part_max + 1.0);
+        if (in_part && in_ellipse_arc(position, center, part_max - part_min)) {
+            return true;
+        }
+    }
+    return false;
+}
+
+/**
+ * Approximates the distance to the ellipse by the implicit function divided by its gradient
+ */
+fn in_ellipse_arc(position: vec2<f32>, center: vec2<f32>, radii: vec2<f32>) -> bool {
+    let normalized = (position - center) / max(radii, vec2(1e-6));
+    let normalized_length = max(length(normalized), 1e-6);
+    let gradient = length(normalized / max(radii, vec2(1e-6))) / normalized_length;
+    return abs(normalized_length - 1.0) / max(gradient, 1e-6) <= 0.75;
+}
+
+/**
+ * A diagonal with lines from the two other corners meeting it at a right angle
+ */
+fn in_golden_triangles(position: vec2<f32>, size: vec2<f32>) -> bool {
+    let start = vec2(0.0);
+    let end = size;
+    let direction = (end - start) / max(dot(end - start, end - start), 1e-6);
+    let top_right = vec2(size.x, 0.0);
+    let bottom_left = vec2(0.0, size.y);
+    let top_right_foot = start + dot(top_right - start, direction) * (end - start);
+    let bottom_left_foot = start + dot(bottom_left - start, direction) * (end - start);
+    return in_line(position, start, end) ||
+        in_line(position, top_right, top_right_foot) ||
+        in_line(position, bottom_left, bottom_left_foot);
+}
+
+fn in_center_cross(position: vec2<f32>, size: vec2<f32>) -> bool {
+    let center = size / 2.0;
+    let arm = min(size.x, size.y) / 12.0;
+    return in_line(position, center - vec2(arm, 0.0), center + vec2(arm, 0.0)) ||
+        in_line(position, center - vec2(0.0, arm), center + vec2(0.0, arm));
+}
+
 fn in_big_grid(vertex: VertexOutput) -> bool {
     let position = vertex.crop_coords * crop.ratio;
     let width = crop.width * crop.ratio;
@@ -369,6 +900,22 @@ fn draw_line_at_pixel(lab: vec3<f32>) -> vec3<f32> {
     }
 }
 
+fn encode_export_color(rgb: vec3<f32>) -> vec3<f32> {
+    let transformed: vec4<f32> = export_settings.color_space_transform * vec4<f32>(rgb, 1.0);
+    let linear: vec3<f32> = clamp(transformed.xyz, vec3(0.0), vec3(1.0));
+    if (export_settings.transfer_function == 0u) {
+        return linear_to_srgb(linear);
+    } else {
+        return pow(linear, vec3(1.0 / export_settings.gamma));
+    }
+}
+
+fn linear_to_srgb(rgb: vec3<f32>) -> vec3<f32> {
+    let low: vec3<f32> = rgb * 12.92;
+    let high: vec3<f32> = 1.055 * pow(rgb, vec3(1.0 / 2.4)) - 0.055;
+    return select(high, low, rgb <= vec3(0.0031308));
+}
+
 /**
  * Using Oklab color space
  * Conversions based on: https://bottosson.github.io/posts/oklab/
@@ -379,7 +926,8 @@ fn rgb_to_lab(rgb: vec3<f32>) -> vec3<f32> {
         0.2119034982 * rgb.x + 0.6806995451 * rgb.y + 0.1073969566 * rgb.z,
         0.0883024619 * rgb.x + 0.2817188376 * rgb.y + 0.6299787005 * rgb.z
     );
-    let lms_root: vec3<f32> = pow(lms, vec3(1.0 / 3.0));
+    // Colors outside sRGB can have negative components
+    let lms_root: vec3<f32> = sign(lms) * pow(abs(lms), vec3(1.0 / 3.0));
     return vec3<f32>(
         0.2104542553 * lms_root.x + 0.7936177850 * lms_root.y - 0.0040720468 * lms_root.z,
         1.9779984951 * lms_root.x - 2.4285922050 * lms_root.y + 0.4505937099 * lms_root.z,
@@ -393,7 +941,7 @@ fn lab_to_rgb(lab: vec3<f32>) -> vec3<f32> {
         lab.x - 0.1055613458 * lab.y - 0.0638541728 * lab.z,
         lab.x - 0.0894841775 * lab.y - 1.2914855480 * lab.z
     );
-    let lms: vec3<f32> = pow(lms_root, vec3(3.0));
+    let lms: vec3<f32> = lms_root * lms_root * lms_root;
     return vec3<f32>(
          4.0767416621 * lms.x - 3.3077115913 * lms.y + 0.2309699292 * lms.z,
         -1.2684380046 * lms.x + 2.6097574011 * lms.y - 0.3413193965 * lms.z,
@@ -0,0 +1,41 @@
+use crate::comparison_mode::ComparisonMode;
+
+use super::camera_uniform::create_viewport_area;
+use super::parameter_uniform::ParameterUniform;
+use super::{transform::Rectangle, viewport::ViewportWorkspace};
+
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+#[repr(C)]
+pub struct SplitUniform {
+    mode: u32,
+    position: f32,
+    line_width: f32,
+    line_height: f32,
+    compared_parameters: ParameterUniform,
+    has_compared_parameters: u32,
+    padding: [f32; 3]
+}
+
+impl SplitUniform {
+    pub fn new(workspace: &ViewportWorkspace, bounds: &Rectangle) -> Self {
+        let mode = match workspace.comparison_mode {
+            ComparisonMode::Off => 0,
+            ComparisonMode::SplitVertical => 1,
+            ComparisonMode::SplitHorizontal => 2,
+            ComparisonMode::SideBySide => 3
+        };
+        // Line size is given in the [0, 1] space of the displayed view
+        let viewport_area = create_viewport_area(bounds, &workspace.view);
+        Self {
+            mode,
+            position: workspace.split_position,
+            line_width: 1.0 / viewport_area.width,
+            line_height: 1.0 / viewport_area.height,
+            compared_parameters: workspace.compared_parameters.as_ref()
+                .map(ParameterUniform::from_base_parameters)
+                .unwrap_or_default(),
+            has_compared_parameters: workspace.compared_parameters.is_some() as u32,
+            padding: [0.0; 3]
+        }
+    }
+}
@@ -1,18 +1,22 @@
 use std::sync::Arc;
 
+use crate::color_profile::ColorSpace;
+use crate::comparison_mode::ComparisonMode;
+use crate::composition_guide::CompositionGuide;
 use crate::types::RawImage;
 use crate::pipeline::pipeline;
 use crate::pipeline::camera_uniform;
 use crate::view_mode::ViewMode;
 use crate::workspace::parameters::Crop;
-use crate::workspace::parameters::CropPreset;
-use crate::workspace::parameters::{Parameters, RadialMask};
+use crate::workspace::parameters::{BaseParameters, BrushMask, Parameters, RadialMask};
 use crate::workspace::workspace::Workspace;
+use crate::workspace::straighten::StraightenLine;
 
 use iced::mouse;
 use iced::widget::shader;
 use iced::widget::shader::wgpu;
 
+use super::lens_correction::LensCorrection;
 use super::pipeline_factory::PipelineFactory;
 use super::transform::Rectangle;
 
@@ -23,6 +27,8 @@ static mut IMAGE_MOUSE_X: i32 = 0;
 static mut IMAGE_MOUSE_Y: i32 = 0;
 static mut RELATIVE_MOUSE_X: i32 = 0;
 static mut RELATIVE_MOUSE_Y: i32 = 0;
+static mut VIEW_MOUSE_X: f32 = 0.0;
+static mut VIEW_MOUSE_Y: f32 = 0.0;
 
 pub fn get_image_mouse_x() -> i32 {
     unsafe {
@@ -48,6 +54,18 @@ pub fn get_relative_mouse_y() -> i32 {
     }
 }
 
+pub fn get_view_mouse_x() -> f32 {
+    unsafe {
+        VIEW_MOUSE_X
+    }
+}
+
+pub fn get_view_mouse_y() -> f32 {
+    unsafe {
+        VIEW_MOUSE_Y
+    }
+}
+
 fn update_image_mouse(mouse_x: i32, mouse_y: i32) {
     unsafe {
         IMAGE_MOUSE_X = mouse_x;
@@ -62,7 +80,14 @@ fn update_relative_mouse(mouse_x: i32, mouse_y: i32) {
     }
 }
 
-#[derive(Default, Debug, Clone)]
+fn update_view_mouse(mouse_x: f32, mouse_y: f32) {
+    unsafe {
+        VIEW_MOUSE_X = mouse_x;
+        VIEW_MOUSE_Y = mouse_y;
+    }
+}
+
+#[derive(Default, Debug, Clone, PartialEq)]
 pub struct ViewportCrop {
     pub center_x: i32,
     pub center_y: i32,
@@ -70,29 +95,25 @@ pub struct ViewportCrop {
     pub height: i32,
     pub angle_degrees: f32,
     pub scale: f32,
+    pub flip_horizontal: bool,
+    pub flip_vertical: bool,
 }
 
-fn width_height_from_crop(crop: &Crop) -> (f32, f32) {
-    match crop.preset {
-        CropPreset::Original => {
-            if crop.rotation % 2 == 0 {
-                (crop.source_image_width as f32, crop.source_image_height as f32)
-            } else {
-                (crop.source_image_height as f32, crop.source_image_width as f32)
-            }
-        },
-        CropPreset::Ratio(width, height) => {
-            let crop_width = crop.source_image_width as f32;
-            let crop_height = crop_width * (height as f32) / (width as f32);
-            (crop_width, crop_height)
-        }
+impl ViewportCrop {
+    /**
+     * Width and height where a flipped axis is negative, which mirrors the crop when used in a transform
+     */
+    pub fn signed_size(&self) -> (f32, f32) {
+        let width = if self.flip_horizontal { -self.width } else { self.width };
+        let height = if self.flip_vertical { -self.height } else { self.height };
+        (width as f32, height as f32)
     }
 }
 
 impl From<Crop> for ViewportCrop {
     fn from(crop: Crop) -> Self {
         let scale = f32::powf(2.0, crop.scale);
-        let (width, height) = width_height_from_crop(&crop);
+        let (width, height) = crop.unscaled_size();
 
         Self {
             center_x: crop.center_x,
@@ -100,12 +121,14 @@ impl From<Crop> for ViewportCrop {
             width: (width * scale) as i32,
             height: (height * scale) as i32,
             angle_degrees: crop.get_full_angle(),
-            scale
+            scale,
+            flip_horizontal: crop.flip_horizontal,
+            flip_vertical: crop.flip_vertical
         }
     }
 }
 
-#[derive(Default, Debug, Clone)]
+#[derive(Default, Debug, Clone, PartialEq)]
 pub struct ViewportParameters {
     pub exposure: f32,
     pub contrast: f32,
@@ -116,11 +139,19 @@ pub struct ViewportParameters {
     pub temperature: f32,
     pub saturation: f32,
     pub radial_masks: Vec<RadialMask>,
-    pub crop: ViewportCrop
+    pub brush_masks: Vec<BrushMask>,
+    pub crop: ViewportCrop,
+    pub lens_correction: LensCorrection,
+    pub export_size: Option<(u32, u32)>
 }
 
 impl From<Parameters> for ViewportParameters {
     fn from(parameters: Parameters) -> ViewportParameters {
+        let lens_correction = parameters.crop.as_ref()
+            .map(LensCorrection::from)
+            .unwrap_or_default();
+        let export_size = parameters.crop.as_ref()
+            .and_then(|crop| crop.preset.export_size());
         let crop = match parameters.crop {
             Some(crop) => crop.into(),
             _ => ViewportCrop::default()
@@ -136,7 +167,10 @@ impl From<Parameters> for ViewportParameters {
             temperature: base_parameters.temperature,
             saturation: base_parameters.saturation,
             radial_masks: parameters.radial_masks.clone(),
-            crop: crop
+            brush_masks: parameters.brush_masks.clone(),
+            crop: crop,
+            lens_correction,
+            export_size
         }
     }
 }
@@ -148,7 +182,17 @@ pub struct ViewportWorkspace {
     pub parameters: ViewportParameters,
     pub view: ViewportCrop,
     pub display_grid: bool,
-    pub view_mode: ViewMode
+    pub composition_guide: CompositionGuide,
+    pub composition_orientation: u32,
+    pub display_clipping: bool,
+    pub display_mask_overlay: bool,
+    pub comparison_mode: ComparisonMode,
+    pub compared_parameters: Option<BaseParameters>,
+    pub split_position: f32,
+    pub view_mode: ViewMode,
+    pub brush_radius: f32,
+    pub straighten_line: Option<StraightenLine>,
+    pub export_color_space: ColorSpace
 }
 
 impl ViewportWorkspace {
@@ -158,14 +202,34 @@ impl ViewportWorkspace {
             let parameters = workspace.parameters_to_display();
             let view = workspace.current_view();
             let display_grid = workspace.is_crop_mode();
+            let composition_guide = workspace.get_composition_guide();
+            let composition_orientation = workspace.get_composition_orientation();
+            let display_clipping = workspace.get_display_clipping();
+            let display_mask_overlay = workspace.get_display_mask_overlay();
+            let comparison_mode = workspace.get_comparison_mode();
+            let compared_parameters = workspace.get_compared_base_parameters();
+            let split_position = workspace.get_split_position();
             let view_mode = workspace.get_view_mode();
+            let brush_radius = workspace.get_brush_settings().radius();
+            let straighten_line = workspace.current_straighten_line();
+            let export_color_space = ColorSpace::default();
             Some(Self {
                 image,
                 photo_id,
                 parameters,
                 view,
                 display_grid,
-                view_mode
+                composition_guide,
+                composition_orientation,
+                display_clipping,
+                display_mask_overlay,
+                comparison_mode,
+                compared_parameters,
+                split_position,
+                view_mode,
+                brush_radius,
+                straighten_line,
+                export_color_space
             })
         } else {
             None
@@ -203,7 +267,9 @@ impl Viewport {
     fn update_mouse(&self, bounds: &iced::Rectangle) {
         match self.cursor {
             mouse::Cursor::Available(point) => {
-                let bounds_rectangle = Self::bounds_to_rectangle(bounds);
+                let bounds_rectangle = camera_uniform::edited_bounds(
+                    &Self::bounds_to_rectangle(bounds),
+                    self.workspace.comparison_mode);
                 let image_point: iced::Point = camera_uniform::point_to_image_position(
                     &point,
                     &bounds_rectangle,
@@ -218,6 +284,11 @@ impl Viewport {
                         ..self.workspace.view.clone()
                     });
                 update_relative_mouse(relative_point.x as i32, relative_point.y as i32);
+                let view_point: iced::Point = camera_uniform::point_to_view_position(
+                    &point,
+                    &bounds_rectangle,
+                    &self.workspace.view);
+                update_view_mouse(view_point.x, view_point.y);
             },
             mouse::Cursor::Unavailable => {} // Do nothing
         }
@@ -232,10 +303,11 @@ impl Viewport {
         }
     }
 
-    fn create_pipeline(&self, device: &wgpu::Device, format: wgpu::TextureFormat) -> pipeline::Pipeline {
+    fn pipeline_factory<'a>(&self, device: &'a wgpu::Device, format: wgpu::TextureFormat) -> PipelineFactory<'a> {
         let image_width = self.workspace.get_image_width();
         let image_height = self.workspace.get_image_height();
-        PipelineFactory::new(image_width, image_height, device, format).create()
+        let radial_mask_count = self.workspace.parameters.radial_masks.len();
+        PipelineFactory::new(image_width, image_height, radial_mask_count, device, format)
     }
 
     fn bounds_to_rectangle(bounds: &iced::Rectangle) -> Rectangle {
@@ -291,7 +363,7 @@ impl shader::Primitive for Viewport {
         let needs_update: bool = self.needs_update(&storage);
 
         if needs_update {
-            storage.store(self.create_pipeline(device, format));
+            storage.store(self.pipeline_factory(device, format).create());
             storage.store(ImageIndex { photo_id: self.workspace.photo_id });
         }
 
@@ -299,6 +371,12 @@ impl shader::Primitive for Viewport {
 
         let pipeline = storage.get_mut::<pipeline::Pipeline>().unwrap();
 
+        // The mask buffer is sized to the masks, so it's reallocated once they no longer fit
+        let radial_mask_count = self.workspace.parameters.radial_masks.len();
+        if !pipeline.can_fit_radial_masks(radial_mask_count) {
+            pipeline.resize_radial_masks(&self.pipeline_factory(device, format), radial_mask_count);
+        }
+
         let bounds_rectangle = Self::bounds_to_rectangle(bounds);
         let viewport_rectangle = Self::viewport_to_rectangle(viewport);
 
@@ -330,6 +408,6 @@ impl shader::Primitive for Viewport {
         pass.set_scissor_rect(clip_bounds.x, clip_bounds.y, clip_bounds.width, clip_bounds.height);
 
         let pipeline = storage.get::<pipeline::Pipeline>().unwrap();
-        pipeline.render_pass(&mut pass);
+        pipeline.render_pass(&mut pass, self.workspace.comparison_mode.instance_count());
     }
 }
\ No newline at end of file
@@ -2,4 +2,11 @@ pub mod album_repository_factory;
 pub mod album_repository;
 pub mod settings_repository_factory;
 pub mod settings_repository;
-pub mod parameter_name;
\ No newline at end of file
+pub mod crop_preset_repository_factory;
+pub mod crop_preset_repository;
+pub mod preset_repository_factory;
+pub mod preset_repository;
+pub mod snapshot_repository_factory;
+pub mod snapshot_repository;
+pub mod parameter_name;
+pub mod json_column;
\ No newline at end of file
@@ -13,7 +13,9 @@ pub struct AlbumPhotoDto {
     pub id: i32,
     pub file_name: String,
     pub parameters: String,
-    pub thumbnail: Option<RawImage>
+    pub thumbnail: Option<RawImage>,
+    // Undo steps as JSON and the index of the current step
+    pub history: Option<(String, usize)>
 }
 
 impl AlbumRepository {
@@ -30,23 +32,30 @@ impl AlbumRepository {
                     photo.parameters,
                     thumbnail.data,
                     thumbnail.width,
-                    thumbnail.height
+                    thumbnail.height,
+                    history.steps,
+                    history.step_index
                 FROM photo
                 LEFT OUTER JOIN thumbnail
-                ON photo.id = thumbnail.photo_id"
+                ON photo.id = thumbnail.photo_id
+                LEFT OUTER JOIN history
+                ON photo.id = history.photo_id"
         )?;
 
         let rows = statement.query_map([], |row| {
             let thumbnail_data: Option<Vec<u8>> = row.get(3)?;
             let thumbnail_width: Option<usize> = row.get(4)?;
             let thumbnail_height: Option<usize> = row.get(5)?;
+            let history_steps: Option<String> = row.get(6)?;
+            let history_step_index: Option<usize> = row.get(7)?;
 
             let thumbnail = match (thumbnail_data, thumbnail_width, thumbnail_height) {
                 (Some(data), Some(width), Some(height)) => {
                     Some(RawImage {
                         pixels: data,
                         width: width,
-                        height: height
+                        height: height,
+                        is_wide_gamut: false
                     })
                 },
                 _ => None
@@ -56,7 +65,8 @@ impl AlbumRepository {
                 id: row.get(0)?,
                 file_name: row.get(1)?,
                 parameters: row.get(2)?,
-                thumbnail: thumbnail
+                thumbnail: thumbnail,
+                history: history_steps.zip(history_step_index)
             })
         })?;
         
@@ -75,6 +85,17 @@ impl AlbumRepository {
         Ok(())
     }
 
+    pub fn save_photo_history(&self, photo_id: i32, steps: String, step_index: usize) -> Result<()> {
+        let connection = self.connection.lock().unwrap();
+        connection.execute(
+            "INSERT OR REPLACE INTO history (photo_id, steps, step_index)
+                VALUES (?1, ?2, ?3)",
+            (photo_id, &steps, step_index)
+        )?;
+
+        Ok(())
+    }
+
     pub fn add_photo(&self, path: &PathBuf) -> Result<()> {
         let connection = self.connection.lock().unwrap();
         connection.execute(
@@ -119,6 +140,11 @@ impl AlbumRepository {
             WHERE photo_id = ?1",
             [photo_id]
         )?;
+        connection.execute(
+            "DELETE FROM history
+            WHERE photo_id = ?1",
+            [photo_id]
+        )?;
         connection.execute(
             "DELETE FROM photo
             WHERE id = ?1",
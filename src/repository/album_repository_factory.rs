@@ -16,6 +16,7 @@ impl AlbumRepositoryFactory {
     pub fn create(self) -> AlbumRepository {
         self.create_photo_table().unwrap();
         self.create_thumbnail_table().unwrap();
+        self.create_history_table().unwrap();
         AlbumRepository::new(self.connection)
     }
 
@@ -47,4 +48,18 @@ impl AlbumRepositoryFactory {
 
         Ok(())
     }
+
+    fn create_history_table(&self) -> Result<()> {
+        let connection = self.connection.lock().unwrap();
+        connection.execute(
+            "CREATE TABLE IF NOT EXISTS history (
+                photo_id INTEGER UNIQUE REFERENCES photo(id),
+                steps TEXT NOT NULL,
+                step_index INTEGER NOT NULL
+            )",
+            ()
+        )?;
+
+        Ok(())
+    }
 }
\ No newline at end of file
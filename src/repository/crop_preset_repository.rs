@@ -0,0 +1,71 @@
+use std::sync::{Arc, Mutex};
+
+use rusqlite::{Connection, Result};
+
+use crate::workspace::parameters::CropPreset;
+
+pub struct CropPresetRepository {
+    connection: Arc<Mutex<Connection>>
+}
+
+impl CropPresetRepository {
+    pub fn new(connection: Arc<Mutex<Connection>>) -> Self {
+        Self { connection }
+    }
+
+    pub fn get_crop_presets(&self) -> Result<Vec<CropPreset>> {
+        let connection = self.connection.lock().unwrap();
+        let mut statement = connection.prepare(
+            "SELECT kind, width, height
+                FROM crop_preset
+                ORDER BY id"
+        )?;
+
+        let rows = statement.query_map([], |row| {
+            let kind: String = row.get(0)?;
+            let width: i32 = row.get(1)?;
+            let height: i32 = row.get(2)?;
+            Ok(match kind.as_str() {
+                "pixels" => CropPreset::Pixels(width, height),
+                _ => CropPreset::Ratio(width, height)
+            })
+        })?;
+
+        Ok(rows.map(|row| row.unwrap()).collect())
+    }
+
+    pub fn add_crop_preset(&self, crop_preset: CropPreset) -> Result<()> {
+        if let Some((kind, width, height)) = Self::to_row(crop_preset) {
+            let connection = self.connection.lock().unwrap();
+            connection.execute(
+                "INSERT OR IGNORE INTO crop_preset (kind, width, height)
+                    VALUES (?1, ?2, ?3)",
+                (kind, width, height))?;
+        }
+
+        Ok(())
+    }
+
+    pub fn delete_crop_preset(&self, crop_preset: CropPreset) -> Result<()> {
+        if let Some((kind, width, height)) = Self::to_row(crop_preset) {
+            let connection = self.connection.lock().unwrap();
+            connection.execute(
+                "DELETE FROM crop_preset
+                    WHERE kind = ?1 AND width = ?2 AND height = ?3",
+                (kind, width, height))?;
+        }
+
+        Ok(())
+    }
+
+    /**
+     * Only ratios and pixel sizes are user defined, the rest are built in
+     */
+    fn to_row(crop_preset: CropPreset) -> Option<(&'static str, i32, i32)> {
+        match crop_preset {
+            CropPreset::Ratio(width, height) => Some(("ratio", width, height)),
+            CropPreset::Pixels(width, height) => Some(("pixels", width, height)),
+            _ => None
+        }
+    }
+}
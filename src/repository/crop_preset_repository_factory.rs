@@ -0,0 +1,36 @@
+use std::sync::{Arc, Mutex};
+
+use rusqlite::{Connection, Result};
+
+use super::crop_preset_repository::CropPresetRepository;
+
+pub struct CropPresetRepositoryFactory {
+    connection: Arc<Mutex<Connection>>
+}
+
+impl CropPresetRepositoryFactory {
+    pub fn new(connection: Arc<Mutex<Connection>>) -> Self {
+        Self { connection }
+    }
+
+    pub fn create(self) -> CropPresetRepository {
+        self.create_crop_preset_table().unwrap();
+        CropPresetRepository::new(self.connection)
+    }
+
+    fn create_crop_preset_table(&self) -> Result<()> {
+        let connection = self.connection.lock().unwrap();
+        connection.execute(
+            "CREATE TABLE IF NOT EXISTS crop_preset (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                kind TEXT NOT NULL,
+                width INTEGER NOT NULL,
+                height INTEGER NOT NULL,
+                UNIQUE(kind, width, height)
+            )",
+            ()
+        )?;
+
+        Ok(())
+    }
+}
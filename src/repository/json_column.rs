@@ -0,0 +1,11 @@
+use rusqlite::{types::Type, Error, Result, Row};
+use serde::de::DeserializeOwned;
+
+/**
+ * Parses a column stored as JSON. Invalid JSON fails the row like any other value that can't be converted.
+ */
+pub fn get_json<T: DeserializeOwned>(row: &Row, index: usize) -> Result<T> {
+    let json: String = row.get(index)?;
+    serde_json::from_str(&json)
+        .map_err(|error| Error::FromSqlConversionFailure(index, Type::Text, Box::new(error)))
+}
@@ -2,7 +2,10 @@ use core::fmt;
 
 #[derive(Debug)]
 pub enum ParameterName {
-    ExportPath
+    ExportPath,
+    ExportColorSpace,
+    PasteGroups,
+    SyncGroups
 }
 
 impl fmt::Display for ParameterName {
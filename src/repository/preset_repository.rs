@@ -0,0 +1,100 @@
+use std::sync::{Arc, Mutex};
+
+use rusqlite::{Connection, Result};
+
+use crate::workspace::preset::Preset;
+
+use super::json_column::get_json;
+
+pub struct PresetRepository {
+    connection: Arc<Mutex<Connection>>
+}
+
+impl PresetRepository {
+    pub fn new(connection: Arc<Mutex<Connection>>) -> Self {
+        Self { connection }
+    }
+
+    pub fn get_presets(&self) -> Result<Vec<Preset>> {
+        let connection = self.connection.lock().unwrap();
+        let mut statement = connection.prepare(
+            "SELECT id, name, folder, groups, parameters
+                FROM preset
+                ORDER BY folder, name"
+        )?;
+
+        let rows = statement.query_map([], |row| {
+            Ok(Preset {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                folder: row.get(2)?,
+                groups: get_json(row, 3)?,
+                parameters: get_json(row, 4)?
+            })
+        })?;
+
+        // A preset that can't be read is left out, rather than applying default parameters in its place
+        Ok(rows
+            .filter_map(|row| row.inspect_err(|error| eprintln!("Skipping preset: {}", error)).ok())
+            .collect())
+    }
+
+    /**
+     * Returns the id of the new preset
+     */
+    pub fn add_preset(&self, preset: &Preset) -> Result<i32> {
+        let groups = serde_json::to_string(&preset.groups).unwrap();
+        let parameters = serde_json::to_string(&preset.parameters).unwrap();
+        let connection = self.connection.lock().unwrap();
+        connection.execute(
+            "INSERT INTO preset (name, folder, groups, parameters)
+                VALUES (?1, ?2, ?3, ?4)",
+            [&preset.name, &preset.folder, &groups, &parameters])?;
+
+        Ok(connection.last_insert_rowid() as i32)
+    }
+
+    pub fn delete_preset(&self, preset_id: i32) -> Result<()> {
+        let connection = self.connection.lock().unwrap();
+        connection.execute(
+            "DELETE FROM preset
+                WHERE id = ?1",
+            [preset_id])?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::*;
+
+    use crate::repository::preset_repository_factory::PresetRepositoryFactory;
+    use crate::workspace::{parameter_group::ParameterGroup, parameters::Parameters};
+
+    #[rstest]
+    fn test_get_presets_skips_unreadable_preset() {
+        // Arrange
+        let connection = Arc::new(Mutex::new(Connection::open_in_memory().unwrap()));
+        let preset_repository = PresetRepositoryFactory::new(connection.clone()).create();
+        let preset = Preset {
+            id: 0,
+            name: String::from("Readable"),
+            folder: String::new(),
+            groups: vec![ParameterGroup::Exposure],
+            parameters: Parameters::default()
+        };
+        let preset_id = preset_repository.add_preset(&preset).unwrap();
+        connection.lock().unwrap().execute(
+            "INSERT INTO preset (name, folder, groups, parameters)
+                VALUES ('Corrupt', '', '[]', '{\"exposure\": ')",
+            ()).unwrap();
+
+        // Act
+        let presets = preset_repository.get_presets().unwrap();
+
+        // Assert
+        assert_eq!(presets, vec![Preset { id: preset_id, ..preset }]);
+    }
+}
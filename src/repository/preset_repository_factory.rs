@@ -0,0 +1,36 @@
+use std::sync::{Arc, Mutex};
+
+use rusqlite::{Connection, Result};
+
+use super::preset_repository::PresetRepository;
+
+pub struct PresetRepositoryFactory {
+    connection: Arc<Mutex<Connection>>
+}
+
+impl PresetRepositoryFactory {
+    pub fn new(connection: Arc<Mutex<Connection>>) -> Self {
+        Self { connection }
+    }
+
+    pub fn create(self) -> PresetRepository {
+        self.create_preset_table().unwrap();
+        PresetRepository::new(self.connection)
+    }
+
+    fn create_preset_table(&self) -> Result<()> {
+        let connection = self.connection.lock().unwrap();
+        connection.execute(
+            "CREATE TABLE IF NOT EXISTS preset (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                name TEXT NOT NULL,
+                folder TEXT NOT NULL,
+                groups TEXT NOT NULL,
+                parameters TEXT NOT NULL
+            )",
+            ()
+        )?;
+
+        Ok(())
+    }
+}
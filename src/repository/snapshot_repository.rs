@@ -0,0 +1,108 @@
+use std::sync::{Arc, Mutex};
+
+use rusqlite::{Connection, Result};
+
+use crate::workspace::snapshot::Snapshot;
+
+use super::json_column::get_json;
+
+pub struct SnapshotRepository {
+    connection: Arc<Mutex<Connection>>
+}
+
+impl SnapshotRepository {
+    pub fn new(connection: Arc<Mutex<Connection>>) -> Self {
+        Self { connection }
+    }
+
+    pub fn get_snapshots(&self, photo_id: i32) -> Result<Vec<Snapshot>> {
+        let connection = self.connection.lock().unwrap();
+        let mut statement = connection.prepare(
+            "SELECT id, photo_id, name, parameters
+                FROM snapshot
+                WHERE photo_id = ?1
+                ORDER BY id"
+        )?;
+
+        let rows = statement.query_map([photo_id], |row| {
+            Ok(Snapshot {
+                id: row.get(0)?,
+                photo_id: row.get(1)?,
+                name: row.get(2)?,
+                parameters: get_json(row, 3)?
+            })
+        })?;
+
+        // Restoring a snapshot that can't be read would reset the photo, so it's left out
+        Ok(rows
+            .filter_map(|row| row.inspect_err(|error| eprintln!("Skipping snapshot: {}", error)).ok())
+            .collect())
+    }
+
+    /**
+     * Returns the id of the new snapshot
+     */
+    pub fn add_snapshot(&self, snapshot: &Snapshot) -> Result<i32> {
+        let parameters = serde_json::to_string(&snapshot.parameters).unwrap();
+        let connection = self.connection.lock().unwrap();
+        connection.execute(
+            "INSERT INTO snapshot (photo_id, name, parameters)
+                VALUES (?1, ?2, ?3)",
+            (snapshot.photo_id, &snapshot.name, &parameters))?;
+
+        Ok(connection.last_insert_rowid() as i32)
+    }
+
+    pub fn delete_snapshot(&self, snapshot_id: i32) -> Result<()> {
+        let connection = self.connection.lock().unwrap();
+        connection.execute(
+            "DELETE FROM snapshot
+                WHERE id = ?1",
+            [snapshot_id])?;
+
+        Ok(())
+    }
+
+    pub fn delete_photo_snapshots(&self, photo_id: i32) -> Result<()> {
+        let connection = self.connection.lock().unwrap();
+        connection.execute(
+            "DELETE FROM snapshot
+                WHERE photo_id = ?1",
+            [photo_id])?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::*;
+
+    use crate::repository::{album_repository_factory::AlbumRepositoryFactory, snapshot_repository_factory::SnapshotRepositoryFactory};
+    use crate::workspace::parameters::Parameters;
+
+    #[rstest]
+    fn test_get_snapshots_skips_unreadable_snapshot() {
+        // Arrange
+        let connection = Arc::new(Mutex::new(Connection::open_in_memory().unwrap()));
+        AlbumRepositoryFactory::new(connection.clone()).create();
+        connection.lock().unwrap().execute(
+            "INSERT INTO photo (id, file_name, parameters)
+                VALUES (1, 'photo.jpg', '{}')",
+            ()).unwrap();
+        let snapshot_repository = SnapshotRepositoryFactory::new(connection.clone()).create();
+        let snapshot = Snapshot::new(1, String::from("Readable"), Parameters::default());
+        let snapshot_id = snapshot_repository.add_snapshot(&snapshot).unwrap();
+        connection.lock().unwrap().execute(
+            "INSERT INTO snapshot (photo_id, name, parameters)
+                VALUES (1, 'Corrupt', '{\"exposure\": ')",
+            ()).unwrap();
+
+        // Act
+        let snapshots = snapshot_repository.get_snapshots(1).unwrap();
+
+        // Assert
+        assert_eq!(snapshots, vec![Snapshot { id: snapshot_id, ..snapshot }]);
+    }
+}
@@ -0,0 +1,35 @@
+use std::sync::{Arc, Mutex};
+
+use rusqlite::{Connection, Result};
+
+use super::snapshot_repository::SnapshotRepository;
+
+pub struct SnapshotRepositoryFactory {
+    connection: Arc<Mutex<Connection>>
+}
+
+impl SnapshotRepositoryFactory {
+    pub fn new(connection: Arc<Mutex<Connection>>) -> Self {
+        Self { connection }
+    }
+
+    pub fn create(self) -> SnapshotRepository {
+        self.create_snapshot_table().unwrap();
+        SnapshotRepository::new(self.connection)
+    }
+
+    fn create_snapshot_table(&self) -> Result<()> {
+        let connection = self.connection.lock().unwrap();
+        connection.execute(
+            "CREATE TABLE IF NOT EXISTS snapshot (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                photo_id INTEGER REFERENCES photo(id),
+                name TEXT NOT NULL,
+                parameters TEXT NOT NULL
+            )",
+            ()
+        )?;
+
+        Ok(())
+    }
+}
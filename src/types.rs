@@ -1,3 +1,5 @@
+use crate::color_profile::wide_gamut_to_srgb;
+
 #[derive(Clone, Debug)]
 pub struct RgbPixel {
     pub red: f32,
@@ -16,7 +18,9 @@ pub struct RgbImage {
 pub struct RawImage {
     pub width: usize,
     pub height: usize,
-    pub pixels: Vec<u8>
+    pub pixels: Vec<u8>,
+    // Stored with Rec. 2020 primaries instead of sRGB, see color_profile
+    pub is_wide_gamut: bool
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -27,14 +31,22 @@ pub struct LabPixel {
 }
 
 impl RawImage {
-    fn rgb_pixel_at(&self, x: usize, y: usize) -> Option<RgbPixel> {
+    /**
+     * The pixel in sRGB, wide gamut colors are outside 0-1
+     */
+    pub fn rgb_pixel_at(&self, x: usize, y: usize) -> Option<RgbPixel> {
 
         if x < self.width && y < self.height {
             let pixel_index: usize = (y * self.width + x) * 4; // Times 4 due to unused alpha channel
             let red: f32 = self.pixels[pixel_index + 0] as f32 / 255.0;
             let green: f32 = self.pixels[pixel_index + 1] as f32 / 255.0;
             let blue: f32 = self.pixels[pixel_index + 2] as f32 / 255.0;
-            Some(RgbPixel { red, green, blue })
+            let pixel = RgbPixel { red, green, blue };
+            if self.is_wide_gamut {
+                Some(wide_gamut_to_srgb(&pixel))
+            } else {
+                Some(pixel)
+            }
         } else {
             None
         }
@@ -53,9 +65,10 @@ pub fn rgb_pixel_to_lab(rgb_pixel: RgbPixel) -> LabPixel {
     let mut m: f32 = 0.2119034982 * rgb_pixel.red + 0.6806995451 * rgb_pixel.green + 0.1073969566 * rgb_pixel.blue;
     let mut s: f32 = 0.0883024619 * rgb_pixel.red + 0.2817188376 * rgb_pixel.green + 0.6299787005 * rgb_pixel.blue;
     
-    l = l.powf(1.0 / 3.0);
-    m = m.powf(1.0 / 3.0);
-    s = s.powf(1.0 / 3.0);
+    // Colors outside sRGB can have negative components
+    l = l.cbrt();
+    m = m.cbrt();
+    s = s.cbrt();
     
     LabPixel {
         lightness:   0.2104542553 * l + 0.7936177850 * m - 0.0040720468 * s,
@@ -1,19 +1,54 @@
+use crate::color_profile::ColorSpace;
 use crate::workspace::album::Album;
+use crate::workspace::histogram::Histogram;
+use crate::workspace::parameters::CropPreset;
+use crate::workspace::preset::Preset;
+use crate::workspace::snapshot::Snapshot;
 use crate::workspace::image_manager::ImageManager;
 use crate::workspace::workspace::Workspace;
 use crate::viewport::Viewport;
 
 use super::message::Message;
-use super::panes::bottom_pane::BottomPane;
+use super::panes::bottom_pane::{BottomPane, DisplayState};
+use super::panes::histogram_pane::HistogramPane;
+use super::panes::history_pane::HistoryPane;
 use super::panes::image_selection_pane::ImageSelectionPane;
+use super::panes::group_dialog::GroupDialog;
+pub use super::panes::group_dialog::{GroupDialogAction, GroupDialogInput};
+use super::panes::preset_pane::PresetPane;
 use super::panes::render_pane::RenderPane;
-use super::panes::toolbox_pane::ToolboxPane;
+use super::panes::snapshot_pane::SnapshotPane;
+use super::panes::toolbox_pane::{CropToolState, ToolboxPane};
+pub use super::panes::toolbox_pane::CropPresetInput;
+pub use super::panes::preset_pane::PresetInput;
 use super::panes::top_pane::TopPane;
 
+/**
+ * Application state shown in the window besides the album and workspace: export settings, presets, snapshots and the
+ * dialog being edited
+ */
+pub struct MainWindowState<'a> {
+    pub is_save_active: bool,
+    pub export_color_space: ColorSpace,
+    pub custom_crop_presets: &'a [CropPreset],
+    pub crop_preset_input: &'a CropPresetInput,
+    pub presets: &'a [Preset],
+    pub preset_input: &'a PresetInput,
+    pub snapshots: &'a [Snapshot],
+    pub snapshot_name: &'a str,
+    pub can_paste: bool,
+    pub group_dialog_input: Option<&'a GroupDialogInput>
+}
+
 pub struct MainWindow<'a> {
     bottom_pane: BottomPane,
+    group_dialog: Option<GroupDialog<'a>>,
+    histogram_pane: HistogramPane<'a>,
+    history_pane: HistoryPane,
     image_selection_pane: ImageSelectionPane<'a>,
+    preset_pane: PresetPane<'a>,
     render_pane: RenderPane<'a>,
+    snapshot_pane: SnapshotPane<'a>,
     toolbox_pane: ToolboxPane,
     top_pane: TopPane
 }
@@ -24,46 +59,93 @@ impl<'a> MainWindow<'a> {
             album: &'a Album,
             workspace: &'a Workspace,
             viewport: &'a Option<Viewport>,
-            is_save_active: bool) -> MainWindow<'a> {
+            histogram: Option<&'a Histogram>,
+            state: MainWindowState<'a>) -> MainWindow<'a> {
         let photo_id = album.get_photo_id();
         let album_images = &album.get_images();
         let image_index = album.get_image_index();
+        let selected_photo_ids = album.get_selected_photo_ids();
+        let can_sync = selected_photo_ids.len() > 1;
 
         let parameters = workspace.current_parameters();
-        let angle_degrees = workspace.current_angle_degrees();
-        let crop_scale = workspace.current_crop_scale();
         let mask_index = workspace.get_mask_index();
-        let parameters_visible = workspace.get_parameters_visible();
-        let can_reset_view = workspace.can_reset_view();
+        let brush_mask_index = workspace.get_brush_mask_index();
+        let brush_settings = workspace.get_brush_settings();
+        let crop_tool_state = CropToolState {
+            angle_degrees: workspace.current_angle_degrees(),
+            crop_scale: workspace.current_crop_scale(),
+            is_straightening: workspace.is_straightening(),
+            composition_guide: workspace.get_composition_guide(),
+            custom_crop_presets: state.custom_crop_presets.to_vec(),
+            crop_preset_input: state.crop_preset_input.clone()
+        };
+        let display_state = DisplayState {
+            parameters_visible: workspace.get_parameters_visible(),
+            display_clipping: workspace.get_display_clipping(),
+            display_mask_overlay: workspace.get_display_mask_overlay(),
+            comparison_mode: workspace.get_comparison_mode(),
+            can_reset_view: workspace.can_reset_view()
+        };
         let is_favorite = parameters.is_favorite;
 
         let toolbox_enabled = viewport.is_some();
         let is_filter_active = image_manager.get_is_filter_active();
 
-        let bottom_pane: BottomPane = BottomPane::new(photo_id, parameters_visible, is_favorite, can_reset_view);
-        let image_selection_pane: ImageSelectionPane<'a> = ImageSelectionPane::new(album_images, image_index);
+        let bottom_pane: BottomPane = BottomPane::new(photo_id, display_state, is_favorite, can_sync);
+        let histogram_pane: HistogramPane<'a> = HistogramPane::new(histogram);
+        let (history_labels, history_index) = workspace.get_history();
+        let history_pane: HistoryPane = HistoryPane::new(history_labels, history_index);
+        let image_selection_pane: ImageSelectionPane<'a> = ImageSelectionPane::new(album_images, image_index, selected_photo_ids);
+        let group_dialog: Option<GroupDialog<'a>> = state.group_dialog_input.map(GroupDialog::new);
+        let preset_pane: PresetPane<'a> = PresetPane::new(state.presets, state.preset_input, toolbox_enabled);
         let render_pane: RenderPane<'a> = RenderPane::new(&viewport);
-        let toolbox_pane: ToolboxPane = ToolboxPane::new(parameters, angle_degrees, crop_scale, mask_index, toolbox_enabled);
-        let top_pane: TopPane = TopPane::new(is_filter_active, is_save_active);
+        let snapshot_pane: SnapshotPane<'a> = SnapshotPane::new(state.snapshots, state.snapshot_name, workspace.get_compared_snapshot_id(), toolbox_enabled);
+        let toolbox_pane: ToolboxPane = ToolboxPane::new(parameters, crop_tool_state, mask_index, brush_mask_index, brush_settings, toolbox_enabled);
+        let top_pane: TopPane = TopPane::new(is_filter_active, state.is_save_active, state.export_color_space, state.can_paste);
 
         Self {
             bottom_pane,
+            group_dialog,
+            histogram_pane,
+            history_pane,
             image_selection_pane,
+            preset_pane,
             render_pane,
+            snapshot_pane,
             toolbox_pane,
             top_pane
         }
     }
 
     pub fn view(&self) -> iced::Element<'a, Message> {
-        let toolbox_pane = iced::widget::container(self.toolbox_pane.view().map(Message::ToolboxMessage))
+        let toolbox_pane = iced::widget::container(iced::widget::column![
+                self.histogram_pane.view(),
+                self.toolbox_pane.view().map(Message::ToolboxMessage)
+            ])
             .width(300);
-        iced::widget::row![
+        let window = iced::widget::row![
+                self.view_library(),
                 self.view_main_area(),
                 toolbox_pane
             ]
             .width(iced::Fill)
-            .height(iced::Fill)
+            .height(iced::Fill);
+        match &self.group_dialog {
+            Some(group_dialog) => iced::widget::stack![
+                    window,
+                    group_dialog.view().map(Message::GroupDialogMessage)
+                ]
+                .into(),
+            None => window.into()
+        }
+    }
+
+    fn view_library(&self) -> iced::Element<'a, Message> {
+        iced::widget::column![
+                self.preset_pane.view().map(Message::PresetMessage),
+                self.snapshot_pane.view().map(Message::SnapshotMessage),
+                self.history_pane.view().map(Message::HistoryMessage),
+            ]
             .into()
     }
 
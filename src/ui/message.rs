@@ -1,4 +1,6 @@
-use crate::workspace::{image_loader::ImageLoadResult, parameters::CropPreset};
+use crate::color_profile::ColorSpace;
+use crate::composition_guide::CompositionGuide;
+use crate::workspace::{histogram::HistogramResult, image_loader::ImageLoadResult, parameter_group::ParameterGroup, parameters::{CropPreset, Parameter}};
 
 #[derive(Debug, Clone, Copy)]
 pub enum MouseState {
@@ -10,10 +12,14 @@ pub enum MouseState {
 pub enum BottomPaneMessage {
     NextImage,
     PreviousImage,
+    SyncSelected,
     ToggleFavorite,
     DeleteImage(i32),
     ResetView,
-    ToggleParametersVisibility
+    ToggleParametersVisibility,
+    ToggleClipping,
+    ToggleMaskOverlay,
+    ToggleComparisonMode
 }
 
 #[derive(Debug, Clone)]
@@ -35,24 +41,57 @@ pub enum MainParameterMessage {
     SaturationChanged(f32),
     TemperatureChanged(f32),
     TintChanged(f32),
+    AutoTone,
 }
 
 #[derive(Debug, Clone)]
 pub enum MaskChangeMessage {
     DeleteMask,
+    DuplicateMask,
+    MoveMaskUp,
+    MoveMaskDown,
     ToggleMaskMode,
+    NameChanged(String),
     MaskToggleLinear(bool),
-    BrightnessChanged(f32),
+    MaskToggleInverted(bool),
+    MaskToggleHidden(bool),
+    MaskToggleGlobal(bool),
+    ParameterChanged(Parameter, f32),
     MaskAngleChanged(f32),
-    FeatherChanged(f32)
+    FeatherChanged(f32),
+    LuminanceRangeToggled(bool),
+    LuminanceLowChanged(f32),
+    LuminanceHighChanged(f32),
+    LuminanceFalloffChanged(f32),
+    ColorRangeToggled(bool),
+    ColorToleranceChanged(f32)
 }
 
 #[derive(Debug, Clone)]
 pub enum MaskMessage {
     AddMask,
+    AddLuminanceMask,
+    AddColorMask,
     MaskChanged(usize, MaskChangeMessage)
 }
 
+#[derive(Debug, Clone)]
+pub enum BrushMaskChangeMessage {
+    DeleteMask,
+    ToggleBrushMode,
+    ParameterChanged(Parameter, f32)
+}
+
+#[derive(Debug, Clone)]
+pub enum BrushMaskMessage {
+    AddBrushMask,
+    BrushMaskChanged(usize, BrushMaskChangeMessage),
+    BrushSizeChanged(f32),
+    BrushFeatherChanged(f32),
+    BrushFlowChanged(f32),
+    BrushEraseToggled(bool)
+}
+
 #[derive(Debug, Clone)]
 pub enum MiscMessage {
     AngleChanged(f32),
@@ -60,23 +99,76 @@ pub enum MiscMessage {
     ToggleCropMode,
     CropRotateLeft,
     CropRotateRight,
-    CropPresetChanged(CropPreset)
+    CropFlipHorizontal,
+    CropFlipVertical,
+    CropPresetChanged(CropPreset),
+    CropPresetWidthChanged(String),
+    CropPresetHeightChanged(String),
+    AddCropRatioPreset,
+    AddCropPixelsPreset,
+    DeleteCropPreset(CropPreset),
+    CompositionGuideChanged(CompositionGuide),
+    RotateCompositionGuide,
+    ToggleStraighten,
+    AutoStraighten,
+    PerspectiveVerticalChanged(f32),
+    PerspectiveHorizontalChanged(f32),
+    DistortionChanged(f32),
+    ChromaticRedCyanChanged(f32),
+    ChromaticBlueYellowChanged(f32),
+    DefringeChanged(f32)
 }
 
 #[derive(Debug, Clone)]
 pub enum ToolboxMessage {
     MainParameterMessage(MainParameterMessage),
     MaskMessage(MaskMessage),
+    BrushMaskMessage(BrushMaskMessage),
     MiscMessage(MiscMessage)
 }
 
+#[derive(Debug, Clone)]
+pub enum PresetMessage {
+    NameChanged(String),
+    FolderChanged(String),
+    GroupToggled(ParameterGroup, bool),
+    Save,
+    Apply(i32),
+    Delete(i32),
+    Export(i32),
+    Import
+}
+
+#[derive(Debug, Clone)]
+pub enum HistoryMessage {
+    JumpTo(usize)
+}
+
+#[derive(Debug, Clone)]
+pub enum SnapshotMessage {
+    NameChanged(String),
+    Save,
+    Restore(i32),
+    Compare(i32),
+    Delete(i32)
+}
+
+#[derive(Debug, Clone)]
+pub enum GroupDialogMessage {
+    GroupToggled(ParameterGroup, bool),
+    Confirm,
+    Cancel
+}
+
 #[derive(Debug, Clone)]
 pub enum TopPaneMessage {
     AddImages,
     SetExportPath,
+    ExportColorSpaceChanged(ColorSpace),
     Export,
     Undo,
     Redo,
+    PasteSpecial,
     ToggleFilter
 }
 
@@ -97,7 +189,8 @@ pub enum WelcomeMessage {
 
 #[derive(Debug, Clone)]
 pub enum TaskMessage {
-    NewImage(ImageLoadResult)
+    NewImage(ImageLoadResult),
+    NewHistogram(Box<HistogramResult>)
 }
 
 #[derive(Debug, Clone)]
@@ -106,16 +199,26 @@ pub enum KeyboardMessage {
     PreviousImage,
     CropRotateLeft,
     CropRotateRight,
+    CropFlipHorizontal,
+    CropFlipVertical,
     ToggleFavorite,
     ToggleCropMode,
+    ToggleStraighten,
+    ToggleClipping,
+    ToggleMaskOverlay,
+    ToggleComparisonMode,
+    CycleCompositionGuide,
+    RotateCompositionGuide,
     Undo,
     Redo,
     Copy,
     Paste,
+    PasteSpecial,
     IncreaseParameter,
     DecreaseParameter,
     IncreaseParameterLarge,
     DecreaseParameterLarge,
+    ModifiersChanged(iced::keyboard::Modifiers)
 }
 
 #[derive(Debug, Clone)]
@@ -126,6 +229,10 @@ pub enum Message {
 
     BottomPaneMessage(BottomPaneMessage),
     ImageSelectionMessage(ImageSelectionMessage),
+    PresetMessage(PresetMessage),
+    SnapshotMessage(SnapshotMessage),
+    HistoryMessage(HistoryMessage),
+    GroupDialogMessage(GroupDialogMessage),
     RenderMessage(RenderMessage),
     ToolboxMessage(ToolboxMessage),
     TopPaneMessage(TopPaneMessage),
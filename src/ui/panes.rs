@@ -1,6 +1,11 @@
 pub mod bottom_pane;
+pub mod group_dialog;
+pub mod histogram_pane;
+pub mod history_pane;
 pub mod image_selection_pane;
+pub mod preset_pane;
 pub mod render_pane;
+pub mod snapshot_pane;
 pub mod toolbox_pane;
 pub mod top_pane;
 pub mod welcome_pane;
\ No newline at end of file
@@ -1,19 +1,27 @@
+use crate::comparison_mode::ComparisonMode;
 use crate::ui::{message::BottomPaneMessage, utils::icon_button};
 
+/**
+ * How the workspace is displayed, toggled from the right side of the pane
+ */
+pub struct DisplayState {
+    pub parameters_visible: bool,
+    pub display_clipping: bool,
+    pub display_mask_overlay: bool,
+    pub comparison_mode: ComparisonMode,
+    pub can_reset_view: bool
+}
+
 pub struct BottomPane {
     photo_id: Option<i32>,
-    parameters_visible: bool,
+    display_state: DisplayState,
     is_favorite: bool,
-    can_reset_view: bool
+    can_sync: bool
 }
 
 impl<'a> BottomPane {
-    pub fn new(
-            photo_id: Option<i32>,
-            parameters_visible: bool,
-            is_favorite: bool,
-            can_reset_view: bool) -> Self {
-        Self { photo_id, parameters_visible, is_favorite, can_reset_view }
+    pub fn new(photo_id: Option<i32>, display_state: DisplayState, is_favorite: bool, can_sync: bool) -> Self {
+        Self { photo_id, display_state, is_favorite, can_sync }
     }
 
     pub fn view(&self) -> iced::Element<'a, BottomPaneMessage> {
@@ -27,6 +35,7 @@ impl<'a> BottomPane {
 
     fn view_left(&self) -> iced::Element<'a, BottomPaneMessage> {
         let row = iced::widget::row![
+                icon_button(iced_fonts::Nerd::Sync).on_press_maybe(self.can_sync.then_some(BottomPaneMessage::SyncSelected)),
             ];
         iced::widget::container(row)
             .align_left(iced::Fill)
@@ -47,7 +56,10 @@ impl<'a> BottomPane {
 
     fn view_right(&self) -> iced::Element<'a, BottomPaneMessage> {
         let row = iced::widget::row![
-                icon_button(iced_fonts::Nerd::TargetVariant).on_press_maybe(self.can_reset_view.then(|| BottomPaneMessage::ResetView)),
+                icon_button(iced_fonts::Nerd::TargetVariant).on_press_maybe(self.display_state.can_reset_view.then(|| BottomPaneMessage::ResetView)),
+                icon_button(self.make_comparison_icon()).on_press(BottomPaneMessage::ToggleComparisonMode),
+                icon_button(self.make_clipping_icon()).on_press(BottomPaneMessage::ToggleClipping),
+                icon_button(self.make_mask_overlay_icon()).on_press(BottomPaneMessage::ToggleMaskOverlay),
                 icon_button(self.make_parameters_visibility_icon()).on_press(BottomPaneMessage::ToggleParametersVisibility)
             ];
         iced::widget::container(row)
@@ -56,13 +68,38 @@ impl<'a> BottomPane {
     }
 
     fn make_parameters_visibility_icon(&self) -> iced_fonts::Nerd {
-        if self.parameters_visible {
+        if self.display_state.parameters_visible {
             iced_fonts::Nerd::EyeTwo
         } else {
             iced_fonts::Nerd::EyeOutline
         }
     }
 
+    fn make_comparison_icon(&self) -> iced_fonts::Nerd {
+        match self.display_state.comparison_mode {
+            ComparisonMode::Off => iced_fonts::Nerd::Compare,
+            ComparisonMode::SplitVertical => iced_fonts::Nerd::CompareHorizontal,
+            ComparisonMode::SplitHorizontal => iced_fonts::Nerd::CompareVertical,
+            ComparisonMode::SideBySide => iced_fonts::Nerd::SplitVertical
+        }
+    }
+
+    fn make_clipping_icon(&self) -> iced_fonts::Nerd {
+        if self.display_state.display_clipping {
+            iced_fonts::Nerd::AlertBox
+        } else {
+            iced_fonts::Nerd::AlertBoxOutline
+        }
+    }
+
+    fn make_mask_overlay_icon(&self) -> iced_fonts::Nerd {
+        if self.display_state.display_mask_overlay {
+            iced_fonts::Nerd::Layers
+        } else {
+            iced_fonts::Nerd::LayersOutline
+        }
+    }
+
     fn make_favorite_icon(&self) -> iced_fonts::Nerd {
         if self.is_favorite {
             iced_fonts::Nerd::HeartFill
@@ -0,0 +1,73 @@
+use crate::ui::message::GroupDialogMessage;
+use crate::workspace::parameter_group::ParameterGroup;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GroupDialogAction {
+    Paste,
+    Sync
+}
+
+/**
+ * What the open dialog does with the parameter groups checked in it
+ */
+#[derive(Debug, Clone)]
+pub struct GroupDialogInput {
+    pub action: GroupDialogAction,
+    pub groups: Vec<ParameterGroup>
+}
+
+impl GroupDialogInput {
+    pub fn toggle_group(&mut self, group: ParameterGroup, is_included: bool) {
+        self.groups.retain(|included| *included != group);
+        if is_included {
+            self.groups.push(group);
+        }
+    }
+}
+
+pub struct GroupDialog<'a> {
+    input: &'a GroupDialogInput
+}
+
+impl<'a> GroupDialog<'a> {
+    pub fn new(input: &'a GroupDialogInput) -> Self {
+        Self { input }
+    }
+
+    pub fn view(&self) -> iced::Element<'a, GroupDialogMessage> {
+        let (title, confirm) = match self.input.action {
+            GroupDialogAction::Paste => ("Paste special", "Paste"),
+            GroupDialogAction::Sync => ("Sync selected photos", "Sync")
+        };
+        let group_checkboxes = ParameterGroup::ALL.into_iter()
+            .map(|group| {
+                iced::widget::checkbox(group.to_string(), self.input.groups.contains(&group))
+                    .on_toggle(move |is_included| GroupDialogMessage::GroupToggled(group, is_included))
+                    .into()
+            })
+            .collect::<Vec<iced::Element<'a, GroupDialogMessage>>>();
+        let can_confirm = !self.input.groups.is_empty();
+        let column = iced::widget::column![
+                iced::widget::text(title),
+                iced::widget::column(group_checkboxes).spacing(2),
+                iced::widget::row![
+                        iced::widget::button(confirm).on_press_maybe(can_confirm.then_some(GroupDialogMessage::Confirm)),
+                        iced::widget::button("Cancel")
+                            .style(iced::widget::button::secondary)
+                            .on_press(GroupDialogMessage::Cancel),
+                    ]
+                    .spacing(10),
+            ]
+            .spacing(10)
+            .padding(20);
+
+        let dialog = iced::widget::container(column)
+            .style(iced::widget::container::bordered_box)
+            .width(250);
+
+        // The backdrop blocks the window below while the dialog is open
+        let backdrop = iced::widget::mouse_area(iced::widget::center(iced::widget::opaque(dialog)))
+            .on_press(GroupDialogMessage::Cancel);
+        iced::widget::opaque(backdrop)
+    }
+}
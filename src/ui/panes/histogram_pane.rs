@@ -0,0 +1,63 @@
+use crate::workspace::histogram::{Histogram, BIN_COUNT};
+
+const HISTOGRAM_HEIGHT: usize = 100;
+
+pub struct HistogramPane<'a> {
+    histogram: Option<&'a Histogram>
+}
+
+impl<'a> HistogramPane<'a> {
+    pub fn new(histogram: Option<&'a Histogram>) -> Self {
+        Self { histogram }
+    }
+
+    pub fn view<Message: 'a>(&self) -> iced::Element<'a, Message> {
+        match self.histogram {
+            Some(histogram) => {
+                let image_handle = iced::widget::image::Handle::from_rgba(
+                    BIN_COUNT as u32,
+                    HISTOGRAM_HEIGHT as u32,
+                    render_histogram(histogram));
+                iced::widget::image(image_handle)
+                    .width(iced::Fill)
+                    .height(HISTOGRAM_HEIGHT as u16)
+                    .content_fit(iced::ContentFit::Fill)
+                    .into()
+            },
+            None => {
+                iced::widget::Space::new(iced::Fill, HISTOGRAM_HEIGHT as u16)
+                    .into()
+            }
+        }
+    }
+}
+
+/**
+ * Luminance is drawn as a grey area with the color channels added on top, so overlapping channels mix into white
+ */
+fn render_histogram(histogram: &Histogram) -> Vec<u8> {
+    let max_count = histogram.max_count().max(1) as f32;
+    let to_height = |count: u32| (count as f32 / max_count * HISTOGRAM_HEIGHT as f32).round() as usize;
+
+    let mut pixels: Vec<u8> = vec![0; BIN_COUNT * HISTOGRAM_HEIGHT * 4];
+    for bin in 0..BIN_COUNT {
+        let luminance_height = to_height(histogram.luminance[bin]);
+        let red_height = to_height(histogram.red[bin]);
+        let green_height = to_height(histogram.green[bin]);
+        let blue_height = to_height(histogram.blue[bin]);
+
+        for height in 0..HISTOGRAM_HEIGHT {
+            let base: u32 = if height < luminance_height { 70 } else { 0 };
+            let red = base + if height < red_height { 150 } else { 0 };
+            let green = base + if height < green_height { 150 } else { 0 };
+            let blue = base + if height < blue_height { 150 } else { 0 };
+
+            let pixel_index = ((HISTOGRAM_HEIGHT - 1 - height) * BIN_COUNT + bin) * 4;
+            pixels[pixel_index] = red.min(255) as u8;
+            pixels[pixel_index + 1] = green.min(255) as u8;
+            pixels[pixel_index + 2] = blue.min(255) as u8;
+            pixels[pixel_index + 3] = if red + green + blue > 0 { 255 } else { 0 };
+        }
+    }
+    pixels
+}
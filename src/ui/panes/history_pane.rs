@@ -0,0 +1,56 @@
+use crate::ui::message::HistoryMessage;
+
+pub struct HistoryPane {
+    labels: Vec<String>,
+    step_index: usize
+}
+
+impl<'a> HistoryPane {
+    pub fn new(labels: Vec<String>, step_index: usize) -> Self {
+        Self { labels, step_index }
+    }
+
+    pub fn view(&self) -> iced::Element<'a, HistoryMessage> {
+        let steps = self.labels.iter().enumerate()
+            .map(|(index, label)| self.view_step(index, label))
+            .collect::<Vec<iced::Element<'a, HistoryMessage>>>();
+        let column = iced::widget::column![
+                iced::widget::text("History"),
+                iced::widget::scrollable(iced::widget::column(steps))
+                    .anchor_bottom()
+                    .height(150),
+            ]
+            .spacing(5)
+            .padding(10);
+
+        iced::widget::container(column)
+            .style(iced::widget::container::bordered_box)
+            .width(220)
+            .into()
+    }
+
+    /**
+     * Steps after the current one can be redone and are shown fainter
+     */
+    fn view_step(&self, index: usize, label: &str) -> iced::Element<'a, HistoryMessage> {
+        let is_redoable = index > self.step_index;
+        let style: fn(&iced::Theme, iced::widget::button::Status) -> iced::widget::button::Style = if index == self.step_index {
+            iced::widget::button::primary
+        } else {
+            iced::widget::button::text
+        };
+        let text = iced::widget::text(label.to_string())
+            .style(move |theme: &iced::Theme| {
+                if is_redoable {
+                    iced::widget::text::Style { color: Some(theme.extended_palette().background.strong.color) }
+                } else {
+                    iced::widget::text::Style::default()
+                }
+            });
+        iced::widget::button(text)
+            .style(style)
+            .width(iced::Fill)
+            .on_press(HistoryMessage::JumpTo(index))
+            .into()
+    }
+}
@@ -4,20 +4,22 @@ use crate::{ui::message::ImageSelectionMessage, workspace::album_image::AlbumIma
 
 pub struct ImageSelectionPane<'a> {
     album_images: &'a Vec<AlbumImage>,
-    selected_index: usize
+    selected_index: usize,
+    selected_photo_ids: Vec<i32>
 }
 
 impl <'a> ImageSelectionPane<'a> {
-    pub fn new(album_images: &'a Vec<AlbumImage>, selected_index: usize) -> Self {
+    pub fn new(album_images: &'a Vec<AlbumImage>, selected_index: usize, selected_photo_ids: Vec<i32>) -> Self {
         Self {
             album_images,
-            selected_index
+            selected_index,
+            selected_photo_ids
         }
     }
 
     pub fn view(&self) -> iced::Element<'a, ImageSelectionMessage> {
         let thumbnails = self.album_images.iter().enumerate()
-            .map(|(index, album_image)| self.view_thumbnail_image(index, &album_image))
+            .map(|(index, album_image)| self.view_thumbnail_image(index, album_image))
             .collect();
 
         let row = iced::widget::Row::from_vec(thumbnails);
@@ -35,13 +37,13 @@ impl <'a> ImageSelectionPane<'a> {
                     thumbnail.width as u32,
                     thumbnail.height as u32,
                     thumbnail.pixels.clone());
-                self.wrap_thumbnail_area(index, iced::widget::image(image_handle)
+                self.wrap_thumbnail_area(index, album_image.photo_id, iced::widget::image(image_handle)
                     .width(100)
                     .height(100)
                     .into())
             },
             None => {
-                self.wrap_thumbnail_area(index, iced::widget::text("...").center()
+                self.wrap_thumbnail_area(index, album_image.photo_id, iced::widget::text("...").center()
                     .width(100)
                     .height(100)
                     .into())
@@ -49,13 +51,16 @@ impl <'a> ImageSelectionPane<'a> {
         }
     }
 
-    fn wrap_thumbnail_area(&self, index: usize, element: iced::Element<'a, ImageSelectionMessage>) -> iced::Element<'a, ImageSelectionMessage> {
-        let is_selected_thumbnail = self.selected_index == index;
+    fn wrap_thumbnail_area(&self, index: usize, photo_id: i32, element: iced::Element<'a, ImageSelectionMessage>) -> iced::Element<'a, ImageSelectionMessage> {
+        let is_current_thumbnail = self.selected_index == index;
+        let is_selected_thumbnail = self.selected_photo_ids.contains(&photo_id);
         let container = iced::widget::container(element)
             .style(move |theme| {
                 if is_selected_thumbnail {
+                    // Other selected photos are marked with a fainter color than the current one
                     let color = theme.palette().primary;
-                    let background = iced::Background::Color(color);
+                    let alpha = if is_current_thumbnail { 1.0 } else { 0.4 };
+                    let background = iced::Background::Color(color.scale_alpha(alpha));
                     Style {
                         background: Some(background),
                         ..Style::default()
@@ -0,0 +1,129 @@
+use itertools::Itertools;
+
+use crate::ui::{message::PresetMessage, utils::icon_button};
+use crate::workspace::{parameter_group::ParameterGroup, preset::Preset};
+
+/**
+ * Name, folder and groups of the preset being created, kept between frames
+ */
+#[derive(Debug, Clone)]
+pub struct PresetInput {
+    pub name: String,
+    pub folder: String,
+    pub groups: Vec<ParameterGroup>
+}
+
+impl Default for PresetInput {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            folder: String::new(),
+            groups: ParameterGroup::BASE.to_vec()
+        }
+    }
+}
+
+impl PresetInput {
+    pub fn toggle_group(&mut self, group: ParameterGroup, is_included: bool) {
+        self.groups.retain(|included| *included != group);
+        if is_included {
+            self.groups.push(group);
+        }
+    }
+
+    pub fn can_save(&self) -> bool {
+        !self.name.trim().is_empty() && !self.groups.is_empty()
+    }
+}
+
+pub struct PresetPane<'a> {
+    presets: &'a [Preset],
+    preset_input: &'a PresetInput,
+    enabled: bool
+}
+
+impl<'a> PresetPane<'a> {
+    pub fn new(presets: &'a [Preset], preset_input: &'a PresetInput, enabled: bool) -> Self {
+        Self { presets, preset_input, enabled }
+    }
+
+    pub fn view(&self) -> iced::Element<'a, PresetMessage> {
+        let column = iced::widget::column![
+                self.view_header(),
+                self.view_presets(),
+                iced::widget::horizontal_rule(2),
+                self.view_new_preset()
+            ]
+            .spacing(10)
+            .padding(10);
+
+        iced::widget::container(iced::widget::scrollable(column).height(iced::Fill))
+            .style(iced::widget::container::bordered_box)
+            .width(220)
+            .into()
+    }
+
+    fn view_header(&self) -> iced::Element<'a, PresetMessage> {
+        iced::widget::row![
+                iced::widget::text("Presets").width(iced::Fill),
+                icon_button(iced_fonts::Nerd::FileImport).on_press(PresetMessage::Import),
+            ]
+            .into()
+    }
+
+    /**
+     * Presets are sorted by folder, so consecutive presets share a folder
+     */
+    fn view_presets(&self) -> iced::Element<'a, PresetMessage> {
+        let folders = self.presets.iter()
+            .chunk_by(|preset| preset.folder.as_str())
+            .into_iter()
+            .map(|(folder, presets)| {
+                let folder_name = if folder.is_empty() { "Unsorted" } else { folder };
+                let rows = presets.map(|preset| self.view_preset(preset));
+                iced::widget::column![
+                        iced::widget::text(folder_name.to_string()).size(14),
+                        iced::widget::column(rows).padding([0, 10]),
+                    ]
+                    .into()
+            })
+            .collect::<Vec<iced::Element<'a, PresetMessage>>>();
+        iced::widget::column(folders)
+            .spacing(5)
+            .into()
+    }
+
+    fn view_preset(&self, preset: &Preset) -> iced::Element<'a, PresetMessage> {
+        let name = iced::widget::button(iced::widget::text(preset.name.clone()))
+            .style(iced::widget::button::text)
+            .width(iced::Fill)
+            .on_press_maybe(self.enabled.then_some(PresetMessage::Apply(preset.id)));
+        iced::widget::row![
+                name,
+                icon_button(iced_fonts::Nerd::FileExport).on_press(PresetMessage::Export(preset.id)),
+                icon_button(iced_fonts::Nerd::Trash).on_press(PresetMessage::Delete(preset.id)),
+            ]
+            .into()
+    }
+
+    fn view_new_preset(&self) -> iced::Element<'a, PresetMessage> {
+        let group_checkboxes = ParameterGroup::ALL.into_iter()
+            .map(|group| {
+                iced::widget::checkbox(group.to_string(), self.preset_input.groups.contains(&group))
+                    .on_toggle(move |is_included| PresetMessage::GroupToggled(group, is_included))
+                    .into()
+            })
+            .collect::<Vec<iced::Element<'a, PresetMessage>>>();
+        let can_save = self.enabled && self.preset_input.can_save();
+        iced::widget::column![
+                iced::widget::text_input("Name", &self.preset_input.name)
+                    .on_input(PresetMessage::NameChanged),
+                iced::widget::text_input("Folder", &self.preset_input.folder)
+                    .on_input(PresetMessage::FolderChanged),
+                iced::widget::column(group_checkboxes).spacing(2),
+                iced::widget::button("Save preset").on_press_maybe(can_save.then_some(PresetMessage::Save)),
+            ]
+            .spacing(5)
+            .into()
+    }
+}
@@ -0,0 +1,56 @@
+use crate::ui::{message::SnapshotMessage, utils::icon_button};
+use crate::workspace::snapshot::Snapshot;
+
+pub struct SnapshotPane<'a> {
+    snapshots: &'a [Snapshot],
+    snapshot_name: &'a str,
+    compared_snapshot_id: Option<i32>,
+    enabled: bool
+}
+
+impl<'a> SnapshotPane<'a> {
+    pub fn new(snapshots: &'a [Snapshot], snapshot_name: &'a str, compared_snapshot_id: Option<i32>, enabled: bool) -> Self {
+        Self { snapshots, snapshot_name, compared_snapshot_id, enabled }
+    }
+
+    pub fn view(&self) -> iced::Element<'a, SnapshotMessage> {
+        let rows = self.snapshots.iter()
+            .map(|snapshot| self.view_snapshot(snapshot))
+            .collect::<Vec<iced::Element<'a, SnapshotMessage>>>();
+        let can_save = self.enabled && !self.snapshot_name.trim().is_empty();
+        let column = iced::widget::column![
+                iced::widget::text("Snapshots"),
+                iced::widget::column(rows),
+                iced::widget::text("Comparing shows the global adjustments of the snapshot, not its masks or crop").size(12),
+                iced::widget::text_input("Name", self.snapshot_name)
+                    .on_input(SnapshotMessage::NameChanged)
+                    .on_submit_maybe(can_save.then_some(SnapshotMessage::Save)),
+                iced::widget::button("Save snapshot").on_press_maybe(can_save.then_some(SnapshotMessage::Save)),
+            ]
+            .spacing(5)
+            .padding(10);
+
+        iced::widget::container(column)
+            .style(iced::widget::container::bordered_box)
+            .width(220)
+            .into()
+    }
+
+    fn view_snapshot(&self, snapshot: &Snapshot) -> iced::Element<'a, SnapshotMessage> {
+        let name = iced::widget::button(iced::widget::text(snapshot.name.clone()))
+            .style(iced::widget::button::text)
+            .width(iced::Fill)
+            .on_press_maybe(self.enabled.then_some(SnapshotMessage::Restore(snapshot.id)));
+        let compare_icon = if self.compared_snapshot_id == Some(snapshot.id) {
+            iced_fonts::Nerd::CompareRemove
+        } else {
+            iced_fonts::Nerd::Compare
+        };
+        iced::widget::row![
+                name,
+                icon_button(compare_icon).on_press_maybe(self.enabled.then_some(SnapshotMessage::Compare(snapshot.id))),
+                icon_button(iced_fonts::Nerd::Trash).on_press(SnapshotMessage::Delete(snapshot.id)),
+            ]
+            .into()
+    }
+}
@@ -1,21 +1,67 @@
-use crate::{ui::{message::{MainParameterMessage, MaskChangeMessage, MaskMessage, MiscMessage, ToolboxMessage}, utils::{icon_button, slider_scaled}}, workspace::parameters::{CropPreset, Parameters, RadialMask}};
+use crate::{composition_guide::CompositionGuide, pipeline::{brush_raster::MAX_BRUSH_MASKS, radial_parameter::MAX_RADIAL_MASKS}, ui::{message::{BrushMaskChangeMessage, BrushMaskMessage, MainParameterMessage, MaskChangeMessage, MaskMessage, MiscMessage, ToolboxMessage}, utils::{icon_button, slider_scaled}}, workspace::{parameters::{BrushMask, ColorRange, CropPreset, LuminanceRange, MaskAdjustments, Parameter, Parameters, RadialMask}, workspace::BrushSettings}};
+
+// Built in presets, custom ones are listed after these
+const CROP_PRESETS: [CropPreset; 11] = [
+    CropPreset::Original,
+    CropPreset::Free,
+    CropPreset::Ratio(1, 1),
+    CropPreset::Ratio(5, 4),
+    CropPreset::Ratio(4, 3),
+    CropPreset::Ratio(3, 2),
+    CropPreset::Ratio(16, 9),
+    CropPreset::Ratio(4, 5),
+    CropPreset::Ratio(3, 4),
+    CropPreset::Ratio(2, 3),
+    CropPreset::Ratio(9, 16),
+];
+
+/**
+ * Width and height typed in for a new crop preset, kept as text while editing
+ */
+#[derive(Debug, Clone, Default)]
+pub struct CropPresetInput {
+    pub width: String,
+    pub height: String
+}
+
+impl CropPresetInput {
+    pub fn parse(&self) -> Option<(i32, i32)> {
+        let width: i32 = self.width.trim().parse().ok()?;
+        let height: i32 = self.height.trim().parse().ok()?;
+        (width > 0 && height > 0).then_some((width, height))
+    }
+}
+
+/**
+ * Values shown by the crop tools that aren't part of the parameters
+ */
+pub struct CropToolState {
+    pub angle_degrees: f32,
+    pub crop_scale: f32,
+    pub is_straightening: bool,
+    pub composition_guide: CompositionGuide,
+    pub custom_crop_presets: Vec<CropPreset>,
+    pub crop_preset_input: CropPresetInput
+}
 
 pub struct ToolboxPane {
     parameters: Parameters,
-    angle_degrees: f32,
-    crop_scale: f32,
+    crop_tool_state: CropToolState,
     mask_edit_index: Option<usize>,
+    brush_mask_edit_index: Option<usize>,
+    brush_settings: BrushSettings,
     enabled: bool
 }
 
 impl <'a> ToolboxPane {
     pub fn new(
             parameters: Parameters,
-            angle_degrees: f32,
-            crop_scale: f32,
+            crop_tool_state: CropToolState,
             mask_edit_index: Option<usize>,
+            brush_mask_edit_index: Option<usize>,
+            brush_settings: BrushSettings,
             enabled: bool) -> Self {
-        Self { parameters, angle_degrees, crop_scale, mask_edit_index, enabled }
+        Self { parameters, crop_tool_state, mask_edit_index, brush_mask_edit_index, brush_settings, enabled }
     }
 
     pub fn view(&self) -> iced::Element<'a, ToolboxMessage> {
@@ -33,6 +79,8 @@ impl <'a> ToolboxPane {
                     iced::widget::horizontal_rule(2),
                     self.view_all_mask_parameter_sliders().map(ToolboxMessage::MaskMessage),
                     iced::widget::horizontal_rule(2),
+                    self.view_all_brush_mask_sliders().map(ToolboxMessage::BrushMaskMessage),
+                    iced::widget::horizontal_rule(2),
                     self.view_misc_buttons().map(ToolboxMessage::MiscMessage)
                 ]
                 .spacing(15);
@@ -55,6 +103,9 @@ impl <'a> ToolboxPane {
     fn view_base_parameter_sliders(&self) -> iced::Element<'a, MainParameterMessage> {
         let base_parameters = &self.parameters.base_parameters;
 
+        let auto_button = iced::widget::container(
+                iced::widget::button("Auto").on_press(MainParameterMessage::AutoTone))
+            .align_right(iced::Fill);
         let main_group = iced::widget::column![
                 self.view_slider("Exposure", base_parameters.exposure, MainParameterMessage::ExposureChanged),
                 self.view_slider("Contrast", base_parameters.contrast, MainParameterMessage::ContrastChanged),
@@ -71,6 +122,7 @@ impl <'a> ToolboxPane {
             ];
 
         iced::widget::column![
+                auto_button,
                 main_group,
                 tones_group,
                 colors_group,
@@ -107,23 +159,59 @@ impl <'a> ToolboxPane {
         let mask_elements = iced::widget::Column::with_children(mask_sliders)
             .spacing(10);
 
+        let can_add_mask = self.parameters.radial_masks.len() < MAX_RADIAL_MASKS;
+        let add_buttons = iced::widget::row![
+                icon_button(iced_fonts::Nerd::PlusCircle).on_press_maybe(can_add_mask.then_some(MaskMessage::AddMask)),
+                iced::widget::button("Luminance").on_press_maybe(can_add_mask.then_some(MaskMessage::AddLuminanceMask)),
+                iced::widget::button("Color").on_press_maybe(can_add_mask.then_some(MaskMessage::AddColorMask)),
+            ]
+            .spacing(5);
+        let limit_text = (!can_add_mask).then(|| {
+            iced::widget::text(format!("Mask limit of {} reached", MAX_RADIAL_MASKS))
+                .style(iced::widget::text::danger)
+        });
+
         iced::widget::column![
                 iced::widget::text("Mask"),
                 mask_elements,
-                icon_button(iced_fonts::Nerd::PlusCircle).on_press(MaskMessage::AddMask),
+                add_buttons,
             ]
+            .push_maybe(limit_text)
             .into()
     }
 
     fn view_mask_parameter_sliders(&self, mask_index: usize, radial_mask: &RadialMask) -> iced::Element<'a, MaskChangeMessage> {
+        let visibility_icon = if radial_mask.is_hidden {
+            iced_fonts::Nerd::EyeOff
+        } else {
+            iced_fonts::Nerd::Eye
+        };
+        let is_last = mask_index + 1 >= self.parameters.radial_masks.len();
+        let is_at_limit = self.parameters.radial_masks.len() >= MAX_RADIAL_MASKS;
+        let header = iced::widget::row![
+                iced::widget::text_input(&format!("Mask {}", mask_index + 1), &radial_mask.name)
+                    .on_input(MaskChangeMessage::NameChanged),
+                icon_button(visibility_icon).on_press(MaskChangeMessage::MaskToggleHidden(!radial_mask.is_hidden)),
+                icon_button(iced_fonts::Nerd::ArrowUp).on_press_maybe((mask_index > 0).then_some(MaskChangeMessage::MoveMaskUp)),
+                icon_button(iced_fonts::Nerd::ArrowDown).on_press_maybe((!is_last).then_some(MaskChangeMessage::MoveMaskDown)),
+                icon_button(iced_fonts::Nerd::ContentDuplicate).on_press_maybe((!is_at_limit).then_some(MaskChangeMessage::DuplicateMask)),
+            ]
+            .spacing(5)
+            .align_y(iced::Center);
         let buttons = iced::widget::row![
                 icon_button(self.mask_edit_icon(mask_index)).on_press(MaskChangeMessage::ToggleMaskMode),
                 icon_button(iced_fonts::Nerd::Trash).on_press(MaskChangeMessage::DeleteMask),
                 iced::widget::checkbox("Linear", radial_mask.is_linear)
                     .on_toggle(MaskChangeMessage::MaskToggleLinear),
-            ];
+                iced::widget::checkbox("Global", radial_mask.is_global)
+                    .on_toggle(MaskChangeMessage::MaskToggleGlobal),
+                iced::widget::checkbox("Invert", radial_mask.is_inverted)
+                    .on_toggle(MaskChangeMessage::MaskToggleInverted),
+            ]
+            .spacing(5);
         iced::widget::column![
-                self.view_slider("Brightness", radial_mask.brightness, MaskChangeMessage::BrightnessChanged),
+                header,
+                self.view_mask_adjustment_sliders(radial_mask.brightness, &radial_mask.adjustments, MaskChangeMessage::ParameterChanged),
                 iced::widget::text("Angle"),
                 iced::widget::slider(
                     -180.0..=180.0,
@@ -134,43 +222,222 @@ impl <'a> ToolboxPane {
                         -100.0..=100.0,
                         radial_mask.feather,
                         MaskChangeMessage::FeatherChanged),
+                self.view_luminance_range(radial_mask.luminance_range.as_ref()),
+                self.view_color_range(radial_mask.color_range.as_ref()),
                 buttons,
             ]
             .into()
     }
 
+    /**
+     * Shared by radial and brush masks, the exposure is stored outside the other adjustments
+     */
+    fn view_mask_adjustment_sliders<T: Clone + 'a>(
+            &self,
+            exposure: f32,
+            adjustments: &MaskAdjustments,
+            message: impl Fn(Parameter, f32) -> T + Copy + 'a) -> iced::Element<'a, T> {
+        let main_group = iced::widget::column![
+                self.view_slider("Exposure", exposure, move |value| message(Parameter::Exposure, value)),
+                self.view_slider("Contrast", adjustments.contrast, move |value| message(Parameter::Contrast, value)),
+            ];
+        let tones_group = iced::widget::column![
+                self.view_slider("Shadows", adjustments.shadows, move |value| message(Parameter::Shadows, value)),
+                self.view_slider("Midtones", adjustments.midtones, move |value| message(Parameter::Midtones, value)),
+                self.view_slider("Highlights", adjustments.highlights, move |value| message(Parameter::Highlights, value)),
+            ];
+        let colors_group = iced::widget::column![
+                self.view_slider("Tint", adjustments.tint, move |value| message(Parameter::Tint, value)),
+                self.view_slider("Temperature", adjustments.temperature, move |value| message(Parameter::Temperature, value)),
+                self.view_slider("Saturation", adjustments.saturation, move |value| message(Parameter::Saturation, value)),
+            ];
+
+        iced::widget::column![
+                main_group,
+                tones_group,
+                colors_group,
+            ]
+            .spacing(10)
+            .into()
+    }
+
+    fn view_luminance_range(&self, luminance_range: Option<&LuminanceRange>) -> iced::Element<'a, MaskChangeMessage> {
+        let checkbox = iced::widget::checkbox("Luminance range", luminance_range.is_some())
+            .on_toggle(MaskChangeMessage::LuminanceRangeToggled);
+        match luminance_range {
+            Some(luminance_range) => {
+                iced::widget::column![
+                        checkbox,
+                        iced::widget::text(format!("Low {:.0}", luminance_range.low)),
+                        iced::widget::slider(0.0..=100.0, luminance_range.low, MaskChangeMessage::LuminanceLowChanged),
+                        iced::widget::text(format!("High {:.0}", luminance_range.high)),
+                        iced::widget::slider(0.0..=100.0, luminance_range.high, MaskChangeMessage::LuminanceHighChanged),
+                        iced::widget::text(format!("Falloff {:.0}", luminance_range.falloff)),
+                        iced::widget::slider(0.0..=100.0, luminance_range.falloff, MaskChangeMessage::LuminanceFalloffChanged),
+                    ]
+                    .into()
+            },
+            None => checkbox.into()
+        }
+    }
+
+    fn view_color_range(&self, color_range: Option<&ColorRange>) -> iced::Element<'a, MaskChangeMessage> {
+        let checkbox = iced::widget::checkbox("Color range", color_range.is_some())
+            .on_toggle(MaskChangeMessage::ColorRangeToggled);
+        match color_range {
+            Some(color_range) => {
+                iced::widget::column![
+                        checkbox,
+                        iced::widget::text("Right click the image while editing to pick a color").size(12),
+                        iced::widget::text(format!("Tolerance {:.0}", color_range.tolerance)),
+                        iced::widget::slider(1.0..=100.0, color_range.tolerance, MaskChangeMessage::ColorToleranceChanged),
+                    ]
+                    .into()
+            },
+            None => checkbox.into()
+        }
+    }
+
+    fn view_all_brush_mask_sliders(&self) -> iced::Element<'a, BrushMaskMessage> {
+        let mask_sliders = self.parameters.brush_masks.iter()
+            .enumerate()
+            .map(|(mask_index, brush_mask)| {
+                self.view_brush_mask_sliders(mask_index, brush_mask)
+                    .map(move |message| BrushMaskMessage::BrushMaskChanged(mask_index, message))
+            });
+
+        let mask_elements = iced::widget::Column::with_children(mask_sliders)
+            .spacing(10);
+
+        let can_add_mask = self.parameters.brush_masks.len() < MAX_BRUSH_MASKS;
+        let column = iced::widget::column![
+                iced::widget::text("Brush"),
+                mask_elements,
+                icon_button(iced_fonts::Nerd::PlusCircle).on_press_maybe(can_add_mask.then_some(BrushMaskMessage::AddBrushMask)),
+            ];
+
+        if self.brush_mask_edit_index.is_some() {
+            column.push(self.view_brush_settings()).into()
+        } else {
+            column.into()
+        }
+    }
+
+    fn view_brush_mask_sliders(&self, mask_index: usize, brush_mask: &BrushMask) -> iced::Element<'a, BrushMaskChangeMessage> {
+        let buttons = iced::widget::row![
+                icon_button(self.brush_mask_edit_icon(mask_index)).on_press(BrushMaskChangeMessage::ToggleBrushMode),
+                icon_button(iced_fonts::Nerd::Trash).on_press(BrushMaskChangeMessage::DeleteMask),
+            ];
+        iced::widget::column![
+                self.view_mask_adjustment_sliders(brush_mask.brightness, &brush_mask.adjustments, BrushMaskChangeMessage::ParameterChanged),
+                buttons,
+            ]
+            .into()
+    }
+
+    fn view_brush_settings(&self) -> iced::Element<'a, BrushMaskMessage> {
+        let brush_settings = &self.brush_settings;
+        iced::widget::column![
+                iced::widget::text(format!("Size {:.0}", brush_settings.size)),
+                iced::widget::slider(1.0..=100.0, brush_settings.size, BrushMaskMessage::BrushSizeChanged),
+                iced::widget::text(format!("Feather {:.0}", brush_settings.feather)),
+                iced::widget::slider(0.0..=100.0, brush_settings.feather, BrushMaskMessage::BrushFeatherChanged),
+                iced::widget::text(format!("Flow {:.0}", brush_settings.flow)),
+                iced::widget::slider(1.0..=100.0, brush_settings.flow, BrushMaskMessage::BrushFlowChanged),
+                iced::widget::checkbox("Erase", brush_settings.is_erase)
+                    .on_toggle(BrushMaskMessage::BrushEraseToggled),
+            ]
+            .into()
+    }
+
     fn view_misc_buttons(&self) -> iced::Element<'a, MiscMessage> {
         iced::widget::column![
                 iced::widget::text("Crop"),
                 self.view_crop_buttons(),
+                self.view_crop_preset_input(),
+                self.view_composition_guide(),
                 iced::widget::text("Angle"),
-                slider_scaled(-3600.0..=3600.0, self.angle_degrees, 40.0, MiscMessage::AngleChanged),
+                slider_scaled(-3600.0..=3600.0, self.crop_tool_state.angle_degrees, 40.0, MiscMessage::AngleChanged),
                 iced::widget::text("Scale"),
-                slider_scaled(-500.0..=0.0, self.crop_scale, 100.0, MiscMessage::CropScaleChanged)
+                slider_scaled(-500.0..=0.0, self.crop_tool_state.crop_scale, 100.0, MiscMessage::CropScaleChanged),
+                self.view_lens_correction_sliders()
+            ]
+            .into()
+    }
+
+    fn view_lens_correction_sliders(&self) -> iced::Element<'a, MiscMessage> {
+        let (perspective_vertical, perspective_horizontal, distortion) = self.parameters.crop.as_ref()
+            .map_or((0.0, 0.0, 0.0), |crop| (crop.perspective_vertical, crop.perspective_horizontal, crop.distortion));
+        iced::widget::column![
+                iced::widget::text(format!("Vertical perspective {:.0}", perspective_vertical)),
+                iced::widget::slider(-100.0..=100.0, perspective_vertical, MiscMessage::PerspectiveVerticalChanged),
+                iced::widget::text(format!("Horizontal perspective {:.0}", perspective_horizontal)),
+                iced::widget::slider(-100.0..=100.0, perspective_horizontal, MiscMessage::PerspectiveHorizontalChanged),
+                iced::widget::text(format!("Distortion {:.0}", distortion)),
+                iced::widget::slider(-100.0..=100.0, distortion, MiscMessage::DistortionChanged),
+                self.view_chromatic_aberration_sliders()
+            ]
+            .into()
+    }
+
+    fn view_chromatic_aberration_sliders(&self) -> iced::Element<'a, MiscMessage> {
+        let (red_cyan, blue_yellow, defringe) = self.parameters.crop.as_ref()
+            .map_or((0.0, 0.0, 0.0), |crop| (crop.chromatic_red_cyan, crop.chromatic_blue_yellow, crop.defringe));
+        iced::widget::column![
+                iced::widget::text(format!("Red/cyan fringe {:.0}", red_cyan)),
+                iced::widget::slider(-100.0..=100.0, red_cyan, MiscMessage::ChromaticRedCyanChanged),
+                iced::widget::text(format!("Blue/yellow fringe {:.0}", blue_yellow)),
+                iced::widget::slider(-100.0..=100.0, blue_yellow, MiscMessage::ChromaticBlueYellowChanged),
+                iced::widget::text(format!("Defringe {:.0}", defringe)),
+                iced::widget::slider(0.0..=100.0, defringe, MiscMessage::DefringeChanged),
             ]
             .into()
     }
 
     fn view_crop_buttons(&self) -> iced::Element<'a, MiscMessage> {
-        let crop_presets = [
-            CropPreset::Original,
-            CropPreset::Ratio(1, 1),
-            CropPreset::Ratio(5, 4),
-            CropPreset::Ratio(4, 3),
-            CropPreset::Ratio(3, 2),
-            CropPreset::Ratio(16, 9),
-            CropPreset::Ratio(4, 5),
-            CropPreset::Ratio(3, 4),
-            CropPreset::Ratio(2, 3),
-            CropPreset::Ratio(9, 16),
-        ];
+        let crop_presets: Vec<CropPreset> = CROP_PRESETS.into_iter()
+            .chain(self.crop_tool_state.custom_crop_presets.iter().copied().filter(|preset| !CROP_PRESETS.contains(preset)))
+            .collect();
 
         let crop_preset = self.parameters.crop.as_ref().map(|crop| crop.preset);
         iced::widget::row![
                 icon_button(self.crop_icon()).on_press(MiscMessage::ToggleCropMode),
                 icon_button(iced_fonts::Nerd::RotateLeftVariant).on_press(MiscMessage::CropRotateLeft),
                 icon_button(iced_fonts::Nerd::RotateRightVariant).on_press(MiscMessage::CropRotateRight),
+                icon_button(iced_fonts::Nerd::FlipHorizontal).on_press(MiscMessage::CropFlipHorizontal),
+                icon_button(iced_fonts::Nerd::FlipVertical).on_press(MiscMessage::CropFlipVertical),
                 iced::widget::pick_list(crop_presets, crop_preset, MiscMessage::CropPresetChanged),
+                icon_button(self.straighten_icon()).on_press(MiscMessage::ToggleStraighten),
+                icon_button(iced_fonts::Nerd::AutoFix).on_press(MiscMessage::AutoStraighten),
+            ]
+            .into()
+    }
+
+    fn view_crop_preset_input(&self) -> iced::Element<'a, MiscMessage> {
+        let size = self.crop_tool_state.crop_preset_input.parse();
+        let selected_custom_preset = self.parameters.crop.as_ref()
+            .map(|crop| crop.preset)
+            .filter(|preset| self.crop_tool_state.custom_crop_presets.contains(preset));
+        iced::widget::row![
+                iced::widget::text_input("W", &self.crop_tool_state.crop_preset_input.width)
+                    .on_input(MiscMessage::CropPresetWidthChanged)
+                    .width(70),
+                iced::widget::text_input("H", &self.crop_tool_state.crop_preset_input.height)
+                    .on_input(MiscMessage::CropPresetHeightChanged)
+                    .width(70),
+                iced::widget::button("Ratio").on_press_maybe(size.map(|_| MiscMessage::AddCropRatioPreset)),
+                iced::widget::button("Pixels").on_press_maybe(size.map(|_| MiscMessage::AddCropPixelsPreset)),
+                icon_button(iced_fonts::Nerd::Trash).on_press_maybe(selected_custom_preset.map(MiscMessage::DeleteCropPreset)),
+            ]
+            .spacing(5)
+            .into()
+    }
+
+    fn view_composition_guide(&self) -> iced::Element<'a, MiscMessage> {
+        let can_rotate = self.crop_tool_state.composition_guide.has_orientation();
+        iced::widget::row![
+                iced::widget::pick_list(CompositionGuide::ALL, Some(self.crop_tool_state.composition_guide), MiscMessage::CompositionGuideChanged),
+                icon_button(iced_fonts::Nerd::RotateOrbit).on_press_maybe(can_rotate.then_some(MiscMessage::RotateCompositionGuide)),
             ]
             .into()
     }
@@ -183,7 +450,23 @@ impl <'a> ToolboxPane {
         }
     }
 
+    fn brush_mask_edit_icon(&self, mask_index: usize) -> iced_fonts::Nerd {
+        if self.brush_mask_edit_index == Some(mask_index) {
+            iced_fonts::Nerd::Brush
+        } else {
+            iced_fonts::Nerd::BrushOutline
+        }
+    }
+
     fn crop_icon(&self) -> iced_fonts::Nerd {
         iced_fonts::Nerd::CropOne
     }
+
+    fn straighten_icon(&self) -> iced_fonts::Nerd {
+        if self.crop_tool_state.is_straightening {
+            iced_fonts::Nerd::RulerSquare
+        } else {
+            iced_fonts::Nerd::Ruler
+        }
+    }
 }
\ No newline at end of file
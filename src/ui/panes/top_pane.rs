@@ -1,15 +1,19 @@
-use crate::ui::{message::TopPaneMessage, utils::icon_button};
+use crate::{color_profile::ColorSpace, ui::{message::TopPaneMessage, utils::icon_button}};
 
 pub struct TopPane {
     is_filter_active: bool,
     is_save_active: bool,
+    export_color_space: ColorSpace,
+    can_paste: bool
 }
 
 impl<'a> TopPane {
-    pub fn new(is_filter_active: bool, is_save_active: bool) -> Self {
+    pub fn new(is_filter_active: bool, is_save_active: bool, export_color_space: ColorSpace, can_paste: bool) -> Self {
         Self {
             is_filter_active,
-            is_save_active
+            is_save_active,
+            export_color_space,
+            can_paste
         }
     }
 
@@ -27,6 +31,7 @@ impl<'a> TopPane {
                 icon_button(iced_fonts::Nerd::FileImagePlus).on_press(TopPaneMessage::AddImages),
                 icon_button(iced_fonts::Nerd::ContentSaveSettings).on_press(TopPaneMessage::SetExportPath),
                 icon_button(iced_fonts::Nerd::ContentSaveOutline).on_press_maybe(self.is_save_active.then(|| TopPaneMessage::Export)),
+                iced::widget::pick_list(ColorSpace::ALL, Some(self.export_color_space), TopPaneMessage::ExportColorSpaceChanged),
             ];
         iced::widget::container(row)
             .align_left(iced::Fill)
@@ -44,6 +49,7 @@ impl<'a> TopPane {
 
     fn view_right(&self) -> iced::Element<'a, TopPaneMessage> {
         let row = iced::widget::row![
+                icon_button(iced_fonts::Nerd::ContentPaste).on_press_maybe(self.can_paste.then_some(TopPaneMessage::PasteSpecial)),
                 icon_button(iced_fonts::Nerd::UndoOne).on_press(TopPaneMessage::Undo),
                 icon_button(iced_fonts::Nerd::RedoOne).on_press(TopPaneMessage::Redo),
             ];
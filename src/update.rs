@@ -1,13 +1,13 @@
 use iced::Task;
 
-use crate::{pipeline::viewport::Viewport, repository::{parameter_name::ParameterName}, ui::message::TaskMessage, update_event::{AlbumEvent, ImageManagerEvent, MouseEvent, UpdateEvent, WorkspaceEvent}, workspace::{image_loader, workspace::Workspace}, Main, Message, MouseState, ViewMode};
+use crate::{color_profile::ColorSpace, pipeline::{radial_parameter::MAX_RADIAL_MASKS, viewport::Viewport}, repository::{parameter_name::ParameterName}, ui::{main_window::{GroupDialogAction, GroupDialogInput}, message::TaskMessage}, update_event::{AlbumEvent, CropPresetEvent, ImageManagerEvent, GroupDialogEvent, PresetEvent, MouseEvent, SnapshotEvent, UpdateEvent, WorkspaceEvent}, workspace::{image_loader, parameter_group::ParameterGroup, parameters::{CropPreset, Parameter}, preset::Preset, snapshot::Snapshot, workspace::Workspace}, Main, Message, MouseState, ViewMode};
 
 use std::{path::PathBuf, usize};
 
 impl Main {
     pub fn update(&mut self, message: Message) -> iced::Task<Message> {
         let update_event = UpdateEvent::from(message);
-        match update_event {
+        let task = match update_event {
             UpdateEvent::OnStart => {
                 self.batch_image_load()
             },
@@ -19,6 +19,10 @@ impl Main {
                 self.set_export_path_dialog();
                 iced::Task::none()
             },
+            UpdateEvent::SetExportColorSpace(color_space) => {
+                self.set_export_color_space(color_space);
+                iced::Task::none()
+            },
             UpdateEvent::ImageManagerEvent(image_manager_event) => {
                 self.update_image_manager(image_manager_event)
             },
@@ -27,7 +31,40 @@ impl Main {
             },
             UpdateEvent::WorkspaceEvent(workspace_event) => {
                 self.update_workspace(workspace_event)
+            },
+            UpdateEvent::CropPresetEvent(crop_preset_event) => {
+                self.update_crop_presets(crop_preset_event);
+                iced::Task::none()
+            },
+            UpdateEvent::PresetEvent(preset_event) => {
+                self.update_presets(preset_event);
+                iced::Task::none()
+            },
+            UpdateEvent::SnapshotEvent(snapshot_event) => {
+                self.update_snapshots(snapshot_event);
+                iced::Task::none()
+            },
+            UpdateEvent::GroupDialogEvent(group_dialog_event) => {
+                self.update_group_dialog(group_dialog_event);
+                iced::Task::none()
+            },
+            UpdateEvent::ModifiersChanged(modifiers) => {
+                self.keyboard_modifiers = modifiers;
+                iced::Task::none()
+            },
+            UpdateEvent::NewHistogram(histogram_result) => {
+                self.histogram_cache.set(*histogram_result);
+                iced::Task::none()
             }
+        };
+        iced::Task::batch([task, self.update_histogram()])
+    }
+
+    fn update_histogram(&mut self) -> iced::Task<Message> {
+        match self.histogram_cache.update(self.workspace.as_ref()) {
+            Some(computation) => iced::Task::perform(computation, |histogram_result| TaskMessage::NewHistogram(Box::new(histogram_result)))
+                .map(Message::TaskMessage),
+            None => iced::Task::none()
         }
     }
 
@@ -49,6 +86,7 @@ impl Main {
                 iced::Task::none()
             },
             ImageManagerEvent::DeleteImage(photo_id) => {
+                self.snapshot_repository.delete_photo_snapshots(photo_id).ok();
                 self.image_manager.delete_image(photo_id);
                 iced::Task::none()
             },
@@ -63,6 +101,7 @@ impl Main {
             .and_then(|photo_id| self.image_manager.get_workspace_image(photo_id))
             .map(|workspace_image| Workspace::update(workspace_image, &self.workspace));
         self.viewport = self.workspace.as_ref().and_then(Viewport::try_new);
+        self.load_snapshots();
         tasks
     }
 
@@ -74,19 +113,33 @@ impl Main {
             AlbumEvent::PreviousImage => {
                 self.album.previous_image();
             },
-            AlbumEvent::SetImage(index) => {
-                self.album.set_image_index(index);
+            AlbumEvent::SelectImage(index) => {
+                if self.keyboard_modifiers.shift() {
+                    self.album.select_image_range(index);
+                } else if self.keyboard_modifiers.control() {
+                    self.album.toggle_image_selection(index);
+                } else {
+                    self.album.set_image_index(index);
+                }
             }
         };
+        // Extending the selection keeps the current photo open
+        if self.workspace.as_ref().map(Workspace::get_photo_id) == self.album.get_photo_id() {
+            return iced::Task::none();
+        }
         self.workspace = self.album.get_photo_id()
             .and_then(|photo_id| self.image_manager.get_workspace_image(photo_id))
             .map(Workspace::new);
         self.viewport = self.workspace.as_ref().and_then(Viewport::try_new);
+        self.load_snapshots();
         self.batch_image_load()
     }
 
     fn update_workspace(&mut self, workspace_event: WorkspaceEvent) -> iced::Task<Message> {
         if let Some(workspace) = &mut self.workspace {
+            let selected_photo_ids = self.album.get_selected_photo_ids();
+            let previous_base_parameters = (selected_photo_ids.len() > 1 && workspace_event.is_base_parameter_change())
+                .then(|| workspace.current_parameters().base_parameters);
             match workspace_event {
                 WorkspaceEvent::ToggleCropMode => {
                     workspace.toggle_view_mode(ViewMode::Crop);
@@ -94,6 +147,9 @@ impl Main {
                 WorkspaceEvent::ToggleMaskMode(mask_index) => {
                     workspace.toggle_view_mode(ViewMode::Mask(mask_index));
                 },
+                WorkspaceEvent::ToggleBrushMode(mask_index) => {
+                    workspace.toggle_view_mode(ViewMode::Brush(mask_index));
+                },
                 WorkspaceEvent::ExposureChanged(exposure) => {
                     workspace.set_exposure(exposure);
                 },
@@ -118,17 +174,44 @@ impl Main {
                 WorkspaceEvent::SaturationChanged(saturation) => {
                     workspace.set_saturation(saturation);
                 },
+                WorkspaceEvent::AutoTone => {
+                    workspace.auto_tone();
+                },
                 WorkspaceEvent::AddMask => {
                     workspace.add_mask();
                 },
+                WorkspaceEvent::AddLuminanceMask => {
+                    workspace.add_luminance_mask();
+                },
+                WorkspaceEvent::AddColorMask => {
+                    workspace.add_color_mask();
+                },
                 WorkspaceEvent::DeleteMask(index) => {
                     workspace.delete_mask(index);
                 },
+                WorkspaceEvent::DuplicateMask(index) => {
+                    workspace.duplicate_mask(index);
+                },
+                WorkspaceEvent::MoveMask(index, is_up) => {
+                    workspace.move_mask(index, is_up);
+                },
+                WorkspaceEvent::MaskNameChanged(index, name) => {
+                    workspace.set_mask_name(index, name);
+                },
                 WorkspaceEvent::MaskToggleLinear(index, is_linear) => {
                     workspace.set_mask_is_linear(index, is_linear);
                 },
-                WorkspaceEvent::MaskBrightnessChanged(index, brightness) => {
-                    workspace.set_mask_brightness(index, brightness);
+                WorkspaceEvent::MaskToggleGlobal(index, is_global) => {
+                    workspace.set_mask_is_global(index, is_global);
+                },
+                WorkspaceEvent::MaskToggleInverted(index, is_inverted) => {
+                    workspace.set_mask_is_inverted(index, is_inverted);
+                },
+                WorkspaceEvent::MaskToggleHidden(index, is_hidden) => {
+                    workspace.set_mask_is_hidden(index, is_hidden);
+                },
+                WorkspaceEvent::MaskParameterChanged(index, parameter, value) => {
+                    workspace.set_mask_parameter(index, parameter, value);
                 },
                 WorkspaceEvent::MaskAngleChanged(index, angle) => {
                     workspace.set_mask_angle_degrees(index, angle);
@@ -136,6 +219,45 @@ impl Main {
                 WorkspaceEvent::MaskFeatherChanged(index, angle) => {
                     workspace.set_mask_feather(index, angle);
                 },
+                WorkspaceEvent::MaskLuminanceRangeToggled(index, is_enabled) => {
+                    workspace.set_mask_luminance_range_enabled(index, is_enabled);
+                },
+                WorkspaceEvent::MaskLuminanceLowChanged(index, low) => {
+                    workspace.set_mask_luminance_low(index, low);
+                },
+                WorkspaceEvent::MaskLuminanceHighChanged(index, high) => {
+                    workspace.set_mask_luminance_high(index, high);
+                },
+                WorkspaceEvent::MaskLuminanceFalloffChanged(index, falloff) => {
+                    workspace.set_mask_luminance_falloff(index, falloff);
+                },
+                WorkspaceEvent::MaskColorRangeToggled(index, is_enabled) => {
+                    workspace.set_mask_color_range_enabled(index, is_enabled);
+                },
+                WorkspaceEvent::MaskColorToleranceChanged(index, tolerance) => {
+                    workspace.set_mask_color_tolerance(index, tolerance);
+                },
+                WorkspaceEvent::AddBrushMask => {
+                    workspace.add_brush_mask();
+                },
+                WorkspaceEvent::DeleteBrushMask(index) => {
+                    workspace.delete_brush_mask(index);
+                },
+                WorkspaceEvent::BrushMaskParameterChanged(index, parameter, value) => {
+                    workspace.set_brush_mask_parameter(index, parameter, value);
+                },
+                WorkspaceEvent::BrushSizeChanged(size) => {
+                    workspace.set_brush_size(size);
+                },
+                WorkspaceEvent::BrushFeatherChanged(feather) => {
+                    workspace.set_brush_feather(feather);
+                },
+                WorkspaceEvent::BrushFlowChanged(flow) => {
+                    workspace.set_brush_flow(flow);
+                },
+                WorkspaceEvent::BrushEraseToggled(is_erase) => {
+                    workspace.set_brush_erase(is_erase);
+                },
                 WorkspaceEvent::AngleChanged(angle_degrees) => {
                     workspace.set_crop_angle(angle_degrees);
                 },
@@ -148,18 +270,66 @@ impl Main {
                 WorkspaceEvent::CropRotateRight => {
                     workspace.crop_rotate_right();
                 },
+                WorkspaceEvent::CropFlipHorizontal => {
+                    workspace.crop_flip_horizontal();
+                },
+                WorkspaceEvent::CropFlipVertical => {
+                    workspace.crop_flip_vertical();
+                },
                 WorkspaceEvent::CropPresetChanged(crop_preset) => {
                     workspace.set_crop_preset(crop_preset);
                 },
+                WorkspaceEvent::ToggleStraighten => {
+                    workspace.toggle_straighten();
+                },
+                WorkspaceEvent::AutoStraighten => {
+                    workspace.auto_straighten();
+                },
+                WorkspaceEvent::PerspectiveVerticalChanged(value) => {
+                    workspace.set_perspective_vertical(value);
+                },
+                WorkspaceEvent::PerspectiveHorizontalChanged(value) => {
+                    workspace.set_perspective_horizontal(value);
+                },
+                WorkspaceEvent::DistortionChanged(value) => {
+                    workspace.set_distortion(value);
+                },
+                WorkspaceEvent::ChromaticRedCyanChanged(value) => {
+                    workspace.set_chromatic_red_cyan(value);
+                },
+                WorkspaceEvent::ChromaticBlueYellowChanged(value) => {
+                    workspace.set_chromatic_blue_yellow(value);
+                },
+                WorkspaceEvent::DefringeChanged(value) => {
+                    workspace.set_defringe(value);
+                },
                 WorkspaceEvent::ToggleParametersVisibility => {
                     workspace.toggle_parameters_visibility();
                 },
+                WorkspaceEvent::ToggleClipping => {
+                    workspace.toggle_clipping();
+                },
+                WorkspaceEvent::ToggleMaskOverlay => {
+                    workspace.toggle_mask_overlay();
+                },
+                WorkspaceEvent::ToggleComparisonMode => {
+                    workspace.toggle_comparison_mode();
+                },
+                WorkspaceEvent::CompositionGuideChanged(composition_guide) => {
+                    workspace.set_composition_guide(composition_guide);
+                },
+                WorkspaceEvent::CycleCompositionGuide => {
+                    workspace.cycle_composition_guide();
+                },
+                WorkspaceEvent::RotateCompositionGuide => {
+                    workspace.rotate_composition_guide();
+                },
                 WorkspaceEvent::ToggleFavorite => {
                     workspace.toggle_favorite();
                 },
                 WorkspaceEvent::ExportImage => {
                     if let Some(export_directory) = self.settings_repository.get_parameter_value(ParameterName::ExportPath).unwrap() {
-                        workspace.export_image(PathBuf::from(export_directory));
+                        workspace.export_image(PathBuf::from(export_directory), self.export_color_space);
                     }
                 },
                 WorkspaceEvent::Undo => {
@@ -168,12 +338,17 @@ impl Main {
                 WorkspaceEvent::Redo => {
                     workspace.redo();
                 },
+                WorkspaceEvent::JumpToHistoryStep(step_index) => {
+                    workspace.jump_to_history_step(step_index);
+                },
                 WorkspaceEvent::Copy => {
                     self.clipboard_parameters = Some(workspace.copy_parameters());
                 },
                 WorkspaceEvent::Paste => {
                     if let Some(parameters) = self.clipboard_parameters.as_ref() {
-                        workspace.paste_parameters(parameters);
+                        if !workspace.paste_parameters(parameters, &self.paste_groups) {
+                            Self::mask_limit_dialog(1);
+                        }
                     }
                 },
                 WorkspaceEvent::DecreaseParameter => {
@@ -195,11 +370,284 @@ impl Main {
                     Self::update_mouse_on_image(workspace, mouse_event);
                 },
             }
+            if let Some(previous_base_parameters) = previous_base_parameters {
+                let photo_id = workspace.get_photo_id();
+                let other_photo_ids: Vec<i32> = selected_photo_ids.into_iter()
+                    .filter(|selected_photo_id| *selected_photo_id != photo_id)
+                    .collect();
+                let base_parameters = workspace.current_parameters().base_parameters;
+                for parameter in Parameter::BASE {
+                    let delta = base_parameters.get(parameter).zip(previous_base_parameters.get(parameter))
+                        .map(|(value, previous_value)| value - previous_value)
+                        .unwrap_or(0.0);
+                    if delta != 0.0 {
+                        self.image_manager.adjust_parameter(&other_photo_ids, parameter, delta);
+                    }
+                }
+            }
             self.viewport = Viewport::try_new(workspace);
         };
         iced::Task::none()
     }
 
+    fn update_crop_presets(&mut self, crop_preset_event: CropPresetEvent) {
+        match crop_preset_event {
+            CropPresetEvent::WidthChanged(width) => {
+                self.crop_preset_input.width = width;
+            },
+            CropPresetEvent::HeightChanged(height) => {
+                self.crop_preset_input.height = height;
+            },
+            CropPresetEvent::AddRatio => {
+                if let Some((width, height)) = self.crop_preset_input.parse() {
+                    self.add_crop_preset(CropPreset::Ratio(width, height));
+                }
+            },
+            CropPresetEvent::AddPixels => {
+                if let Some((width, height)) = self.crop_preset_input.parse() {
+                    self.add_crop_preset(CropPreset::Pixels(width, height));
+                }
+            },
+            CropPresetEvent::Delete(crop_preset) => {
+                if self.crop_preset_repository.delete_crop_preset(crop_preset).is_ok() {
+                    self.crop_presets.retain(|preset| *preset != crop_preset);
+                }
+            }
+        }
+    }
+
+    /**
+     * A new preset is applied right away, as that's almost always why it was added
+     */
+    fn add_crop_preset(&mut self, crop_preset: CropPreset) {
+        if self.crop_preset_repository.add_crop_preset(crop_preset).is_ok() && !self.crop_presets.contains(&crop_preset) {
+            self.crop_presets.push(crop_preset);
+        }
+        if let Some(workspace) = &mut self.workspace {
+            workspace.set_crop_preset(crop_preset);
+            self.viewport = Viewport::try_new(workspace);
+        }
+    }
+
+    fn update_presets(&mut self, preset_event: PresetEvent) {
+        match preset_event {
+            PresetEvent::NameChanged(name) => {
+                self.preset_input.name = name;
+            },
+            PresetEvent::FolderChanged(folder) => {
+                self.preset_input.folder = folder;
+            },
+            PresetEvent::GroupToggled(group, is_included) => {
+                self.preset_input.toggle_group(group, is_included);
+            },
+            PresetEvent::Save => {
+                if let Some(workspace) = &self.workspace {
+                    let preset = Preset::new(
+                        self.preset_input.name.trim().to_string(),
+                        self.preset_input.folder.trim().to_string(),
+                        self.preset_input.groups.clone(),
+                        &workspace.current_parameters());
+                    self.add_preset(preset);
+                    self.preset_input.name.clear();
+                }
+            },
+            PresetEvent::Apply(preset_id) => {
+                let preset = self.presets.iter().find(|preset| preset.id == preset_id);
+                if let Some((workspace, preset)) = self.workspace.as_mut().zip(preset) {
+                    if !workspace.apply_preset(preset) {
+                        Self::mask_limit_dialog(1);
+                    }
+                    self.viewport = Viewport::try_new(workspace);
+                }
+            },
+            PresetEvent::Delete(preset_id) => {
+                if self.preset_repository.delete_preset(preset_id).is_ok() {
+                    self.presets.retain(|preset| preset.id != preset_id);
+                }
+            },
+            PresetEvent::Export(preset_id) => {
+                if let Some(preset) = self.presets.iter().find(|preset| preset.id == preset_id) {
+                    Self::export_preset_dialog(preset);
+                }
+            },
+            PresetEvent::Import => {
+                self.import_presets_dialog();
+            }
+        }
+    }
+
+    fn update_snapshots(&mut self, snapshot_event: SnapshotEvent) {
+        let snapshot = |snapshot_id: i32| self.snapshots.iter()
+            .find(|snapshot| snapshot.id == snapshot_id)
+            .cloned();
+        match snapshot_event {
+            SnapshotEvent::NameChanged(name) => {
+                self.snapshot_name = name;
+            },
+            SnapshotEvent::Save => {
+                if let Some(workspace) = &self.workspace {
+                    let name = self.snapshot_name.trim().to_string();
+                    let snapshot = Snapshot::new(workspace.get_photo_id(), name, workspace.current_parameters());
+                    if let Ok(snapshot_id) = self.snapshot_repository.add_snapshot(&snapshot) {
+                        self.snapshots.push(Snapshot { id: snapshot_id, ..snapshot });
+                        self.snapshot_name.clear();
+                    }
+                }
+            },
+            SnapshotEvent::Restore(snapshot_id) => {
+                if let Some((workspace, snapshot)) = self.workspace.as_mut().zip(snapshot(snapshot_id)) {
+                    if !workspace.restore_snapshot(&snapshot) {
+                        Self::mask_limit_dialog(1);
+                    }
+                    self.viewport = Viewport::try_new(workspace);
+                }
+            },
+            SnapshotEvent::Compare(snapshot_id) => {
+                if let Some((workspace, snapshot)) = self.workspace.as_mut().zip(snapshot(snapshot_id)) {
+                    workspace.toggle_compared_snapshot(&snapshot);
+                    self.viewport = Viewport::try_new(workspace);
+                }
+            },
+            SnapshotEvent::Delete(snapshot_id) => {
+                if self.snapshot_repository.delete_snapshot(snapshot_id).is_ok() {
+                    self.snapshots.retain(|snapshot| snapshot.id != snapshot_id);
+                    if let Some(workspace) = &mut self.workspace {
+                        workspace.forget_snapshot(snapshot_id);
+                        self.viewport = Viewport::try_new(workspace);
+                    }
+                }
+            }
+        }
+    }
+
+    fn load_snapshots(&mut self) {
+        self.snapshots = self.album.get_photo_id()
+            .and_then(|photo_id| self.snapshot_repository.get_snapshots(photo_id).ok())
+            .unwrap_or_default();
+    }
+
+    fn update_group_dialog(&mut self, group_dialog_event: GroupDialogEvent) {
+        match group_dialog_event {
+            GroupDialogEvent::Open(action) => {
+                let groups = match action {
+                    GroupDialogAction::Paste => self.clipboard_parameters.is_some().then(|| self.paste_groups.clone()),
+                    GroupDialogAction::Sync => (self.album.get_selected_photo_ids().len() > 1).then(|| self.sync_groups.clone())
+                };
+                self.group_dialog_input = groups.map(|groups| GroupDialogInput { action, groups });
+            },
+            GroupDialogEvent::GroupToggled(group, is_included) => {
+                if let Some(input) = &mut self.group_dialog_input {
+                    input.toggle_group(group, is_included);
+                }
+            },
+            GroupDialogEvent::Confirm => {
+                if let Some(input) = self.group_dialog_input.take() {
+                    match input.action {
+                        GroupDialogAction::Paste => {
+                            if let Some((workspace, parameters)) = self.workspace.as_mut().zip(self.clipboard_parameters.as_ref()) {
+                                if !workspace.paste_parameters(parameters, &input.groups) {
+                                    Self::mask_limit_dialog(1);
+                                }
+                                self.viewport = Viewport::try_new(workspace);
+                            }
+                            self.save_parameter_groups(ParameterName::PasteGroups, &input.groups);
+                            self.paste_groups = input.groups;
+                        },
+                        GroupDialogAction::Sync => {
+                            self.sync_selected_photos(&input.groups);
+                            self.save_parameter_groups(ParameterName::SyncGroups, &input.groups);
+                            self.sync_groups = input.groups;
+                        }
+                    }
+                }
+            },
+            GroupDialogEvent::Cancel => {
+                self.group_dialog_input = None;
+            }
+        }
+    }
+
+    /**
+     * The last choice is remembered, also between sessions
+     */
+    fn save_parameter_groups(&self, parameter_name: ParameterName, groups: &[ParameterGroup]) {
+        if let Ok(value) = serde_json::to_string(groups) {
+            self.settings_repository.set_parameter_value(parameter_name, &value).ok();
+        }
+    }
+
+    /**
+     * Every selected photo gets its own undo step, so each can be undone separately
+     */
+    fn sync_selected_photos(&mut self, groups: &[ParameterGroup]) {
+        if let Some(workspace) = &self.workspace {
+            let photo_id = workspace.get_photo_id();
+            let parameters = workspace.current_parameters();
+            let refused_count = self.album.get_selected_photo_ids().into_iter()
+                .filter(|selected_photo_id| *selected_photo_id != photo_id)
+                .flat_map(|selected_photo_id| self.image_manager.get_workspace_image(selected_photo_id))
+                .map(|image| Workspace::new(image).sync_parameters(&parameters, groups))
+                .filter(|is_synced| !is_synced)
+                .count();
+            if refused_count > 0 {
+                Self::mask_limit_dialog(refused_count);
+            }
+        }
+    }
+
+    fn mask_limit_dialog(refused_count: usize) {
+        let text = format!(
+            "The change would leave more than {} masks, so it wasn't applied to {} photo(s).",
+            MAX_RADIAL_MASKS,
+            refused_count);
+        native_dialog::MessageDialog::new()
+            .set_type(native_dialog::MessageType::Warning)
+            .set_title("Mask limit reached")
+            .set_text(&text)
+            .show_alert()
+            .ok();
+    }
+
+    fn add_preset(&mut self, preset: Preset) {
+        if let Ok(preset_id) = self.preset_repository.add_preset(&preset) {
+            self.presets.push(Preset { id: preset_id, ..preset });
+            self.presets.sort_by(|a, b| a.folder.cmp(&b.folder).then_with(|| a.name.cmp(&b.name)));
+        }
+    }
+
+    fn export_preset_dialog(preset: &Preset) {
+        let path: PathBuf = std::env::current_dir().unwrap();
+        let file_name = format!("{}.json", preset.name);
+
+        let result = native_dialog::FileDialog::new()
+            .set_location(&path)
+            .set_filename(&file_name)
+            .add_filter("preset", &["json"])
+            .show_save_single_file();
+
+        if let (Ok(Some(file_path)), Ok(json)) = (result, preset.to_json()) {
+            std::fs::write(file_path, json).ok();
+        }
+    }
+
+    fn import_presets_dialog(&mut self) {
+        let path: PathBuf = std::env::current_dir().unwrap();
+
+        let result = native_dialog::FileDialog::new()
+            .set_location(&path)
+            .add_filter("preset", &["json"])
+            .show_open_multiple_file();
+
+        if let Ok(file_paths) = result {
+            let presets = file_paths.iter()
+                .filter_map(|file_path| std::fs::read_to_string(file_path).ok())
+                .filter_map(|json| Preset::from_json(&json).ok());
+            for preset in presets {
+                self.add_preset(preset);
+            }
+        }
+    }
+
     fn open_file_dialog(&mut self) {
         let path: PathBuf = std::env::current_dir().unwrap();
 
@@ -231,6 +679,12 @@ impl Main {
         }
     }
     
+    fn set_export_color_space(&mut self, color_space: ColorSpace) {
+        if self.settings_repository.set_parameter_value(ParameterName::ExportColorSpace, &color_space.to_name()).is_ok() {
+            self.export_color_space = color_space;
+        }
+    }
+    
     fn update_mouse_on_image(workspace: &mut Workspace, mouse_event: MouseEvent) {
         match mouse_event {
             MouseEvent::Press(_) => workspace.set_mouse_state(MouseState::Down),
@@ -247,6 +701,9 @@ impl Main {
             },
             ViewMode::Mask(mask_index) => {
                 Self::update_mouse_mask_mode(workspace, mouse_event, mask_index);
+            },
+            ViewMode::Brush(mask_index) => {
+                Self::update_mouse_brush_mode(workspace, mouse_event, mask_index);
             }
         }
     }
@@ -260,43 +717,101 @@ impl Main {
                 workspace.update_view_zoom(scroll_delta);
             },
             MouseEvent::Over(mouse_position) => {
-                if matches!(workspace.get_mouse_state(), MouseState::Down) {
+                if workspace.is_dragging_split() {
+                    workspace.update_split_position(mouse_position.view_x, mouse_position.view_y);
+                } else if matches!(workspace.get_mouse_state(), MouseState::Down) {
                     workspace.update_view_offset(mouse_position.relative_x, mouse_position.relative_y);
                 }
             },
             MouseEvent::Press(mouse_position) => {
-                workspace.new_view_offset_origin(mouse_position.relative_x, mouse_position.relative_y);
+                let is_split_grabbed = workspace.try_grab_split(mouse_position.view_x, mouse_position.view_y);
+                if !is_split_grabbed {
+                    workspace.new_view_offset_origin(mouse_position.relative_x, mouse_position.relative_y);
+                }
             },
             _ => {}
         }
     }
 
     fn update_mouse_crop_mode(workspace: &mut Workspace, mouse_event: MouseEvent) {
+        if workspace.is_straightening() {
+            Self::update_mouse_straighten(workspace, mouse_event);
+            return;
+        }
+
         match mouse_event {
             MouseEvent::Scroll(scroll_delta) => {
                 workspace.update_crop_scale(scroll_delta);
             },
             MouseEvent::Over(mouse_position) => {
-                if matches!(workspace.get_mouse_state(), MouseState::Down) {
+                if workspace.is_dragging_handle() {
+                    workspace.drag_crop_handle(mouse_position.view_x, mouse_position.view_y);
+                } else if matches!(workspace.get_mouse_state(), MouseState::Down) {
                     workspace.update_crop_offset(mouse_position.relative_x, mouse_position.relative_y);
                 }
             },
             MouseEvent::Press(mouse_position) => {
-                workspace.new_crop_offset_origin(mouse_position.relative_x, mouse_position.relative_y);
+                let is_handle_grabbed = workspace.try_grab_crop_handle(mouse_position.view_x, mouse_position.view_y);
+                if !is_handle_grabbed {
+                    workspace.new_crop_offset_origin(mouse_position.relative_x, mouse_position.relative_y);
+                }
             },
             _ => {}
         }
     }
 
-    fn update_mouse_mask_mode(workspace: &mut Workspace, mouse_event: MouseEvent, mask_index: usize) {
+    fn update_mouse_straighten(workspace: &mut Workspace, mouse_event: MouseEvent) {
         match mouse_event {
             MouseEvent::Over(mouse_position) => {
                 if matches!(workspace.get_mouse_state(), MouseState::Down) {
+                    workspace.update_straighten_line(mouse_position.image_x, mouse_position.image_y);
+                }
+            },
+            MouseEvent::Press(mouse_position) => {
+                workspace.start_straighten_line(mouse_position.image_x, mouse_position.image_y);
+            },
+            MouseEvent::Release => {
+                workspace.finish_straighten_line();
+            },
+            _ => {}
+        }
+    }
+
+    fn update_mouse_mask_mode(workspace: &mut Workspace, mouse_event: MouseEvent, mask_index: usize) {
+        match mouse_event {
+            MouseEvent::Over(mouse_position) => {
+                if workspace.is_dragging_handle() {
+                    workspace.drag_mask_handle(mask_index, mouse_position.image_x, mouse_position.image_y);
+                } else if matches!(workspace.get_mouse_state(), MouseState::Down) {
                     workspace.update_mask_size(mask_index, mouse_position.image_x, mouse_position.image_y);
                 }
             },
             MouseEvent::Press(mouse_position) => {
-                workspace.update_mask_position(mask_index, mouse_position.image_x, mouse_position.image_y);
+                // Pressing outside the handles draws the mask again
+                let is_handle_grabbed = workspace.try_grab_mask_handle(mask_index, mouse_position.image_x, mouse_position.image_y);
+                if !is_handle_grabbed {
+                    workspace.update_mask_position(mask_index, mouse_position.image_x, mouse_position.image_y);
+                }
+            },
+            MouseEvent::RightPress(mouse_position) => {
+                workspace.pick_mask_color(mask_index, mouse_position.image_x, mouse_position.image_y);
+            },
+            MouseEvent::Scroll(scroll_delta) => {
+                workspace.update_view_zoom(scroll_delta);
+            },
+            _ => {}
+        }
+    }
+
+    fn update_mouse_brush_mode(workspace: &mut Workspace, mouse_event: MouseEvent, mask_index: usize) {
+        match mouse_event {
+            MouseEvent::Over(mouse_position) => {
+                if matches!(workspace.get_mouse_state(), MouseState::Down) {
+                    workspace.continue_brush_stroke(mask_index, mouse_position.image_x, mouse_position.image_y);
+                }
+            },
+            MouseEvent::Press(mouse_position) => {
+                workspace.start_brush_stroke(mask_index, mouse_position.image_x, mouse_position.image_y);
             },
             MouseEvent::Scroll(scroll_delta) => {
                 workspace.update_view_zoom(scroll_delta);
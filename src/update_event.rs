@@ -1,11 +1,13 @@
-use crate::{pipeline::viewport, types::RawImage, ui::message::{BottomPaneMessage, ImageSelectionMessage, KeyboardMessage, MainParameterMessage, MaskChangeMessage, MaskMessage, Message, MiscMessage, MouseMessage, RenderMessage, TaskMessage, ToolboxMessage, TopPaneMessage, WelcomeMessage}, workspace::parameters::CropPreset};
+use crate::{color_profile::ColorSpace, composition_guide::CompositionGuide, pipeline::viewport, types::RawImage, ui::message::{BottomPaneMessage, BrushMaskChangeMessage, BrushMaskMessage, ImageSelectionMessage, KeyboardMessage, MainParameterMessage, MaskChangeMessage, MaskMessage, Message, MiscMessage, MouseMessage, GroupDialogMessage, HistoryMessage, PresetMessage, RenderMessage, SnapshotMessage, TaskMessage, ToolboxMessage, TopPaneMessage, WelcomeMessage}, ui::main_window::GroupDialogAction, workspace::{histogram::HistogramResult, parameter_group::ParameterGroup, parameters::{CropPreset, Parameter}}};
 
 #[derive(Debug, Clone, Copy)]
 pub struct MousePosition {
     pub image_x: i32,
     pub image_y: i32,
     pub relative_x: i32,
-    pub relative_y: i32
+    pub relative_y: i32,
+    pub view_x: f32,
+    pub view_y: f32
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -27,6 +29,7 @@ impl From<MouseEvent> for UpdateEvent {
 pub enum WorkspaceEvent {
     ToggleCropMode,
     ToggleMaskMode(usize),
+    ToggleBrushMode(usize),
     ExposureChanged(f32),
     ContrastChanged(f32),
     ShadowsChanged(f32),
@@ -35,22 +38,61 @@ pub enum WorkspaceEvent {
     TintChanged(f32),
     TemperatureChanged(f32),
     SaturationChanged(f32),
+    AutoTone,
     AddMask,
+    AddLuminanceMask,
+    AddColorMask,
     DeleteMask(usize),
+    DuplicateMask(usize),
+    MoveMask(usize, bool),
+    MaskNameChanged(usize, String),
     MaskToggleLinear(usize, bool),
-    MaskBrightnessChanged(usize, f32),
+    MaskToggleInverted(usize, bool),
+    MaskToggleHidden(usize, bool),
+    MaskToggleGlobal(usize, bool),
+    MaskParameterChanged(usize, Parameter, f32),
     MaskAngleChanged(usize, f32),
     MaskFeatherChanged(usize, f32),
+    MaskLuminanceRangeToggled(usize, bool),
+    MaskLuminanceLowChanged(usize, f32),
+    MaskLuminanceHighChanged(usize, f32),
+    MaskLuminanceFalloffChanged(usize, f32),
+    MaskColorRangeToggled(usize, bool),
+    MaskColorToleranceChanged(usize, f32),
+    AddBrushMask,
+    DeleteBrushMask(usize),
+    BrushMaskParameterChanged(usize, Parameter, f32),
+    BrushSizeChanged(f32),
+    BrushFeatherChanged(f32),
+    BrushFlowChanged(f32),
+    BrushEraseToggled(bool),
     AngleChanged(f32),
     CropScaleChanged(f32),
     CropRotateLeft,
     CropRotateRight,
+    CropFlipHorizontal,
+    CropFlipVertical,
     CropPresetChanged(CropPreset),
+    ToggleStraighten,
+    AutoStraighten,
+    PerspectiveVerticalChanged(f32),
+    PerspectiveHorizontalChanged(f32),
+    DistortionChanged(f32),
+    ChromaticRedCyanChanged(f32),
+    ChromaticBlueYellowChanged(f32),
+    DefringeChanged(f32),
     ToggleParametersVisibility,
+    ToggleClipping,
+    ToggleMaskOverlay,
+    ToggleComparisonMode,
+    CompositionGuideChanged(CompositionGuide),
+    CycleCompositionGuide,
+    RotateCompositionGuide,
     ToggleFavorite,
     ExportImage,
     Undo,
     Redo,
+    JumpToHistoryStep(usize),
     Copy,
     Paste,
     DecreaseParameter,
@@ -61,6 +103,27 @@ pub enum WorkspaceEvent {
     ImageMouseEvent(MouseEvent),
 }
 
+impl WorkspaceEvent {
+    /**
+     * Slider and keyboard changes of base parameters, which are applied relatively to the other selected photos
+     */
+    pub fn is_base_parameter_change(&self) -> bool {
+        matches!(self,
+            WorkspaceEvent::ExposureChanged(_) |
+            WorkspaceEvent::ContrastChanged(_) |
+            WorkspaceEvent::ShadowsChanged(_) |
+            WorkspaceEvent::MidtonesChanged(_) |
+            WorkspaceEvent::HighlightsChanged(_) |
+            WorkspaceEvent::TintChanged(_) |
+            WorkspaceEvent::TemperatureChanged(_) |
+            WorkspaceEvent::SaturationChanged(_) |
+            WorkspaceEvent::DecreaseParameter |
+            WorkspaceEvent::IncreaseParameter |
+            WorkspaceEvent::DecreaseParameterLarge |
+            WorkspaceEvent::IncreaseParameterLarge)
+    }
+}
+
 impl From<WorkspaceEvent> for UpdateEvent {
     fn from(event: WorkspaceEvent) -> UpdateEvent {
         UpdateEvent::WorkspaceEvent(event)
@@ -71,7 +134,8 @@ impl From<WorkspaceEvent> for UpdateEvent {
 pub enum AlbumEvent {
     NextImage,
     PreviousImage,
-    SetImage(usize)
+    // Extends the selection instead when shift or control is held
+    SelectImage(usize)
 }
 
 impl From<AlbumEvent> for UpdateEvent {
@@ -95,13 +159,82 @@ impl From<ImageManagerEvent> for UpdateEvent {
     }
 }
 
+#[derive(Debug, Clone)]
+pub enum CropPresetEvent {
+    WidthChanged(String),
+    HeightChanged(String),
+    AddRatio,
+    AddPixels,
+    Delete(CropPreset)
+}
+
+impl From<CropPresetEvent> for UpdateEvent {
+    fn from(event: CropPresetEvent) -> Self {
+        UpdateEvent::CropPresetEvent(event)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum PresetEvent {
+    NameChanged(String),
+    FolderChanged(String),
+    GroupToggled(ParameterGroup, bool),
+    Save,
+    Apply(i32),
+    Delete(i32),
+    Export(i32),
+    Import
+}
+
+#[derive(Debug, Clone)]
+pub enum SnapshotEvent {
+    NameChanged(String),
+    Save,
+    Restore(i32),
+    Compare(i32),
+    Delete(i32)
+}
+
+impl From<SnapshotEvent> for UpdateEvent {
+    fn from(event: SnapshotEvent) -> Self {
+        UpdateEvent::SnapshotEvent(event)
+    }
+}
+
+impl From<PresetEvent> for UpdateEvent {
+    fn from(event: PresetEvent) -> Self {
+        UpdateEvent::PresetEvent(event)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum GroupDialogEvent {
+    Open(GroupDialogAction),
+    GroupToggled(ParameterGroup, bool),
+    Confirm,
+    Cancel
+}
+
+impl From<GroupDialogEvent> for UpdateEvent {
+    fn from(event: GroupDialogEvent) -> Self {
+        UpdateEvent::GroupDialogEvent(event)
+    }
+}
+
 pub enum UpdateEvent {
     OnStart,
     OnExit(iced::window::Id),
     SetExportPath,
+    SetExportColorSpace(ColorSpace),
     WorkspaceEvent(WorkspaceEvent),
     AlbumEvent(AlbumEvent),
-    ImageManagerEvent(ImageManagerEvent)
+    ImageManagerEvent(ImageManagerEvent),
+    CropPresetEvent(CropPresetEvent),
+    PresetEvent(PresetEvent),
+    SnapshotEvent(SnapshotEvent),
+    GroupDialogEvent(GroupDialogEvent),
+    ModifiersChanged(iced::keyboard::Modifiers),
+    NewHistogram(Box<HistogramResult>)
 }
 
 impl From<BottomPaneMessage> for UpdateEvent {
@@ -109,10 +242,14 @@ impl From<BottomPaneMessage> for UpdateEvent {
         match message {
             BottomPaneMessage::NextImage => AlbumEvent::NextImage.into(),
             BottomPaneMessage::PreviousImage => AlbumEvent::PreviousImage.into(),
+            BottomPaneMessage::SyncSelected => GroupDialogEvent::Open(GroupDialogAction::Sync).into(),
             BottomPaneMessage::ToggleFavorite => WorkspaceEvent::ToggleFavorite.into(),
             BottomPaneMessage::DeleteImage(photo_id) => ImageManagerEvent::DeleteImage(photo_id).into(),
             BottomPaneMessage::ResetView => WorkspaceEvent::ResetView.into(),
-            BottomPaneMessage::ToggleParametersVisibility => WorkspaceEvent::ToggleParametersVisibility.into()
+            BottomPaneMessage::ToggleParametersVisibility => WorkspaceEvent::ToggleParametersVisibility.into(),
+            BottomPaneMessage::ToggleClipping => WorkspaceEvent::ToggleClipping.into(),
+            BottomPaneMessage::ToggleMaskOverlay => WorkspaceEvent::ToggleMaskOverlay.into(),
+            BottomPaneMessage::ToggleComparisonMode => WorkspaceEvent::ToggleComparisonMode.into()
         }
     }
 }
@@ -127,7 +264,8 @@ impl From<MainParameterMessage> for UpdateEvent {
             MainParameterMessage::HighlightsChanged(highlights) => WorkspaceEvent::HighlightsChanged(highlights).into(),
             MainParameterMessage::SaturationChanged(saturation) => WorkspaceEvent::SaturationChanged(saturation).into(),
             MainParameterMessage::TemperatureChanged(temperature) => WorkspaceEvent::TemperatureChanged(temperature).into(),
-            MainParameterMessage::TintChanged(tint) => WorkspaceEvent::TintChanged(tint).into()
+            MainParameterMessage::TintChanged(tint) => WorkspaceEvent::TintChanged(tint).into(),
+            MainParameterMessage::AutoTone => WorkspaceEvent::AutoTone.into()
         }
     }
 }
@@ -136,13 +274,28 @@ impl From<MaskMessage> for UpdateEvent {
     fn from(message: MaskMessage) -> Self {
         match message {
             MaskMessage::AddMask => WorkspaceEvent::AddMask.into(),
+            MaskMessage::AddLuminanceMask => WorkspaceEvent::AddLuminanceMask.into(),
+            MaskMessage::AddColorMask => WorkspaceEvent::AddColorMask.into(),
             MaskMessage::MaskChanged(mask_index, message) => {
                 match message {
                     MaskChangeMessage::MaskAngleChanged(angle) => WorkspaceEvent::MaskAngleChanged(mask_index, angle).into(),
                     MaskChangeMessage::FeatherChanged(angle) => WorkspaceEvent::MaskFeatherChanged(mask_index, angle).into(),
-                    MaskChangeMessage::BrightnessChanged(brightness) => WorkspaceEvent::MaskBrightnessChanged(mask_index, brightness).into(),
+                    MaskChangeMessage::ParameterChanged(parameter, value) => WorkspaceEvent::MaskParameterChanged(mask_index, parameter, value).into(),
                     MaskChangeMessage::MaskToggleLinear(toggle) => WorkspaceEvent::MaskToggleLinear(mask_index, toggle).into(),
+                    MaskChangeMessage::MaskToggleGlobal(toggle) => WorkspaceEvent::MaskToggleGlobal(mask_index, toggle).into(),
+                    MaskChangeMessage::MaskToggleInverted(toggle) => WorkspaceEvent::MaskToggleInverted(mask_index, toggle).into(),
+                    MaskChangeMessage::MaskToggleHidden(toggle) => WorkspaceEvent::MaskToggleHidden(mask_index, toggle).into(),
+                    MaskChangeMessage::NameChanged(name) => WorkspaceEvent::MaskNameChanged(mask_index, name).into(),
+                    MaskChangeMessage::LuminanceRangeToggled(toggle) => WorkspaceEvent::MaskLuminanceRangeToggled(mask_index, toggle).into(),
+                    MaskChangeMessage::LuminanceLowChanged(low) => WorkspaceEvent::MaskLuminanceLowChanged(mask_index, low).into(),
+                    MaskChangeMessage::LuminanceHighChanged(high) => WorkspaceEvent::MaskLuminanceHighChanged(mask_index, high).into(),
+                    MaskChangeMessage::LuminanceFalloffChanged(falloff) => WorkspaceEvent::MaskLuminanceFalloffChanged(mask_index, falloff).into(),
+                    MaskChangeMessage::ColorRangeToggled(toggle) => WorkspaceEvent::MaskColorRangeToggled(mask_index, toggle).into(),
+                    MaskChangeMessage::ColorToleranceChanged(tolerance) => WorkspaceEvent::MaskColorToleranceChanged(mask_index, tolerance).into(),
                     MaskChangeMessage::DeleteMask => WorkspaceEvent::DeleteMask(mask_index).into(),
+                    MaskChangeMessage::DuplicateMask => WorkspaceEvent::DuplicateMask(mask_index).into(),
+                    MaskChangeMessage::MoveMaskUp => WorkspaceEvent::MoveMask(mask_index, true).into(),
+                    MaskChangeMessage::MoveMaskDown => WorkspaceEvent::MoveMask(mask_index, false).into(),
                     MaskChangeMessage::ToggleMaskMode => WorkspaceEvent::ToggleMaskMode(mask_index).into()
                 }
             }
@@ -150,6 +303,25 @@ impl From<MaskMessage> for UpdateEvent {
     }
 }
 
+impl From<BrushMaskMessage> for UpdateEvent {
+    fn from(message: BrushMaskMessage) -> Self {
+        match message {
+            BrushMaskMessage::AddBrushMask => WorkspaceEvent::AddBrushMask.into(),
+            BrushMaskMessage::BrushMaskChanged(mask_index, message) => {
+                match message {
+                    BrushMaskChangeMessage::DeleteMask => WorkspaceEvent::DeleteBrushMask(mask_index).into(),
+                    BrushMaskChangeMessage::ToggleBrushMode => WorkspaceEvent::ToggleBrushMode(mask_index).into(),
+                    BrushMaskChangeMessage::ParameterChanged(parameter, value) => WorkspaceEvent::BrushMaskParameterChanged(mask_index, parameter, value).into()
+                }
+            },
+            BrushMaskMessage::BrushSizeChanged(size) => WorkspaceEvent::BrushSizeChanged(size).into(),
+            BrushMaskMessage::BrushFeatherChanged(feather) => WorkspaceEvent::BrushFeatherChanged(feather).into(),
+            BrushMaskMessage::BrushFlowChanged(flow) => WorkspaceEvent::BrushFlowChanged(flow).into(),
+            BrushMaskMessage::BrushEraseToggled(is_erase) => WorkspaceEvent::BrushEraseToggled(is_erase).into()
+        }
+    }
+}
+
 impl From<MiscMessage> for UpdateEvent {
     fn from(message: MiscMessage) -> Self {
         match message {
@@ -158,7 +330,24 @@ impl From<MiscMessage> for UpdateEvent {
             MiscMessage::ToggleCropMode => WorkspaceEvent::ToggleCropMode.into(),
             MiscMessage::CropRotateLeft => WorkspaceEvent::CropRotateLeft.into(),
             MiscMessage::CropRotateRight => WorkspaceEvent::CropRotateRight.into(),
-            MiscMessage::CropPresetChanged(crop_preset) => WorkspaceEvent::CropPresetChanged(crop_preset).into()
+            MiscMessage::CropFlipHorizontal => WorkspaceEvent::CropFlipHorizontal.into(),
+            MiscMessage::CropFlipVertical => WorkspaceEvent::CropFlipVertical.into(),
+            MiscMessage::CropPresetChanged(crop_preset) => WorkspaceEvent::CropPresetChanged(crop_preset).into(),
+            MiscMessage::CropPresetWidthChanged(width) => CropPresetEvent::WidthChanged(width).into(),
+            MiscMessage::CropPresetHeightChanged(height) => CropPresetEvent::HeightChanged(height).into(),
+            MiscMessage::AddCropRatioPreset => CropPresetEvent::AddRatio.into(),
+            MiscMessage::AddCropPixelsPreset => CropPresetEvent::AddPixels.into(),
+            MiscMessage::DeleteCropPreset(crop_preset) => CropPresetEvent::Delete(crop_preset).into(),
+            MiscMessage::CompositionGuideChanged(composition_guide) => WorkspaceEvent::CompositionGuideChanged(composition_guide).into(),
+            MiscMessage::RotateCompositionGuide => WorkspaceEvent::RotateCompositionGuide.into(),
+            MiscMessage::ToggleStraighten => WorkspaceEvent::ToggleStraighten.into(),
+            MiscMessage::AutoStraighten => WorkspaceEvent::AutoStraighten.into(),
+            MiscMessage::PerspectiveVerticalChanged(value) => WorkspaceEvent::PerspectiveVerticalChanged(value).into(),
+            MiscMessage::PerspectiveHorizontalChanged(value) => WorkspaceEvent::PerspectiveHorizontalChanged(value).into(),
+            MiscMessage::DistortionChanged(value) => WorkspaceEvent::DistortionChanged(value).into(),
+            MiscMessage::ChromaticRedCyanChanged(value) => WorkspaceEvent::ChromaticRedCyanChanged(value).into(),
+            MiscMessage::ChromaticBlueYellowChanged(value) => WorkspaceEvent::ChromaticBlueYellowChanged(value).into(),
+            MiscMessage::DefringeChanged(value) => WorkspaceEvent::DefringeChanged(value).into()
         }
     }
 }
@@ -168,6 +357,7 @@ impl From<ToolboxMessage> for UpdateEvent {
         match message {
             ToolboxMessage::MainParameterMessage(message) => message.into(),
             ToolboxMessage::MaskMessage(message) => message.into(),
+            ToolboxMessage::BrushMaskMessage(message) => message.into(),
             ToolboxMessage::MiscMessage(message) => message.into(),
         }
     }
@@ -178,25 +368,76 @@ impl From<TopPaneMessage> for UpdateEvent {
         match message {
             TopPaneMessage::AddImages => ImageManagerEvent::AddImages.into(),
             TopPaneMessage::SetExportPath => UpdateEvent::SetExportPath.into(),
+            TopPaneMessage::ExportColorSpaceChanged(color_space) => UpdateEvent::SetExportColorSpace(color_space),
             TopPaneMessage::Export => WorkspaceEvent::ExportImage.into(),
             TopPaneMessage::Undo => WorkspaceEvent::Undo.into(),
             TopPaneMessage::Redo => WorkspaceEvent::Redo.into(),
+            TopPaneMessage::PasteSpecial => GroupDialogEvent::Open(GroupDialogAction::Paste).into(),
             TopPaneMessage::ToggleFilter => ImageManagerEvent::ToggleFilter.into()
         }
     }
 }
 
+impl From<PresetMessage> for UpdateEvent {
+    fn from(message: PresetMessage) -> Self {
+        match message {
+            PresetMessage::NameChanged(name) => PresetEvent::NameChanged(name).into(),
+            PresetMessage::FolderChanged(folder) => PresetEvent::FolderChanged(folder).into(),
+            PresetMessage::GroupToggled(group, is_included) => PresetEvent::GroupToggled(group, is_included).into(),
+            PresetMessage::Save => PresetEvent::Save.into(),
+            PresetMessage::Apply(preset_id) => PresetEvent::Apply(preset_id).into(),
+            PresetMessage::Delete(preset_id) => PresetEvent::Delete(preset_id).into(),
+            PresetMessage::Export(preset_id) => PresetEvent::Export(preset_id).into(),
+            PresetMessage::Import => PresetEvent::Import.into()
+        }
+    }
+}
+
+impl From<HistoryMessage> for UpdateEvent {
+    fn from(message: HistoryMessage) -> Self {
+        match message {
+            HistoryMessage::JumpTo(step_index) => WorkspaceEvent::JumpToHistoryStep(step_index).into()
+        }
+    }
+}
+
+impl From<SnapshotMessage> for UpdateEvent {
+    fn from(message: SnapshotMessage) -> Self {
+        match message {
+            SnapshotMessage::NameChanged(name) => SnapshotEvent::NameChanged(name).into(),
+            SnapshotMessage::Save => SnapshotEvent::Save.into(),
+            SnapshotMessage::Restore(snapshot_id) => SnapshotEvent::Restore(snapshot_id).into(),
+            SnapshotMessage::Compare(snapshot_id) => SnapshotEvent::Compare(snapshot_id).into(),
+            SnapshotMessage::Delete(snapshot_id) => SnapshotEvent::Delete(snapshot_id).into()
+        }
+    }
+}
+
+impl From<GroupDialogMessage> for UpdateEvent {
+    fn from(message: GroupDialogMessage) -> Self {
+        match message {
+            GroupDialogMessage::GroupToggled(group, is_included) => GroupDialogEvent::GroupToggled(group, is_included).into(),
+            GroupDialogMessage::Confirm => GroupDialogEvent::Confirm.into(),
+            GroupDialogMessage::Cancel => GroupDialogEvent::Cancel.into()
+        }
+    }
+}
+
 impl From<MouseMessage> for UpdateEvent {
     fn from(message: MouseMessage) -> Self {
         let image_mouse_x: i32 = viewport::get_image_mouse_x();
         let image_mouse_y: i32 = viewport::get_image_mouse_y();
         let relative_mouse_x: i32 = viewport::get_relative_mouse_x();
         let relative_mouse_y: i32 = viewport::get_relative_mouse_y();
+        let view_mouse_x: f32 = viewport::get_view_mouse_x();
+        let view_mouse_y: f32 = viewport::get_view_mouse_y();
         let mouse_position: MousePosition = MousePosition {
             image_x: image_mouse_x, 
             image_y: image_mouse_y,
             relative_x: relative_mouse_x,
-            relative_y: relative_mouse_y
+            relative_y: relative_mouse_y,
+            view_x: view_mouse_x,
+            view_y: view_mouse_y
         };
         match message {
             MouseMessage::Over => MouseEvent::Over(mouse_position).into(),
@@ -219,7 +460,7 @@ impl From<RenderMessage> for UpdateEvent {
 impl From<ImageSelectionMessage> for UpdateEvent {
     fn from(message: ImageSelectionMessage) -> Self {
         match message {
-            ImageSelectionMessage::SelectImage(index) => AlbumEvent::SetImage(index).into()
+            ImageSelectionMessage::SelectImage(index) => AlbumEvent::SelectImage(index).into()
         }
     }
 }
@@ -239,12 +480,22 @@ impl From<KeyboardMessage> for UpdateEvent {
             KeyboardMessage::PreviousImage => AlbumEvent::PreviousImage.into(),
             KeyboardMessage::CropRotateLeft => WorkspaceEvent::CropRotateLeft.into(),
             KeyboardMessage::CropRotateRight => WorkspaceEvent::CropRotateRight.into(),
+            KeyboardMessage::CropFlipHorizontal => WorkspaceEvent::CropFlipHorizontal.into(),
+            KeyboardMessage::CropFlipVertical => WorkspaceEvent::CropFlipVertical.into(),
             KeyboardMessage::ToggleFavorite => WorkspaceEvent::ToggleFavorite.into(),
             KeyboardMessage::ToggleCropMode => WorkspaceEvent::ToggleCropMode.into(),
+            KeyboardMessage::ToggleStraighten => WorkspaceEvent::ToggleStraighten.into(),
+            KeyboardMessage::ToggleClipping => WorkspaceEvent::ToggleClipping.into(),
+            KeyboardMessage::ToggleMaskOverlay => WorkspaceEvent::ToggleMaskOverlay.into(),
+            KeyboardMessage::ToggleComparisonMode => WorkspaceEvent::ToggleComparisonMode.into(),
+            KeyboardMessage::CycleCompositionGuide => WorkspaceEvent::CycleCompositionGuide.into(),
+            KeyboardMessage::RotateCompositionGuide => WorkspaceEvent::RotateCompositionGuide.into(),
             KeyboardMessage::Undo => WorkspaceEvent::Undo.into(),
             KeyboardMessage::Redo => WorkspaceEvent::Redo.into(),
             KeyboardMessage::Copy => WorkspaceEvent::Copy.into(),
             KeyboardMessage::Paste => WorkspaceEvent::Paste.into(),
+            KeyboardMessage::PasteSpecial => GroupDialogEvent::Open(GroupDialogAction::Paste).into(),
+            KeyboardMessage::ModifiersChanged(modifiers) => UpdateEvent::ModifiersChanged(modifiers),
             KeyboardMessage::DecreaseParameter => WorkspaceEvent::DecreaseParameter.into(),
             KeyboardMessage::IncreaseParameter => WorkspaceEvent::IncreaseParameter.into(),
             KeyboardMessage::DecreaseParameterLarge => WorkspaceEvent::DecreaseParameterLarge.into(),
@@ -261,7 +512,8 @@ impl From<TaskMessage> for UpdateEvent {
                 let image = image_load_result.image;
                 let thumbnail = image_load_result.thumbnail;
                 ImageManagerEvent::LoadImage(photo_id, image, thumbnail).into()
-            }
+            },
+            TaskMessage::NewHistogram(histogram_result) => UpdateEvent::NewHistogram(histogram_result)
         }
     }
 }
@@ -274,6 +526,10 @@ impl From<Message> for UpdateEvent {
             Message::OnTimeTickMessage => ImageManagerEvent::Save.into(),
             Message::BottomPaneMessage(message) => message.into(),
             Message::ImageSelectionMessage(message) => message.into(),
+            Message::PresetMessage(message) => message.into(),
+            Message::SnapshotMessage(message) => message.into(),
+            Message::HistoryMessage(message) => message.into(),
+            Message::GroupDialogMessage(message) => message.into(),
             Message::RenderMessage(message) => message.into(),
             Message::ToolboxMessage(message) => message.into(),
             Message::TopPaneMessage(message) => message.into(),
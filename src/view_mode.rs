@@ -2,7 +2,8 @@
 pub enum ViewMode {
     Normal,
     Crop,
-    Mask(usize)
+    Mask(usize),
+    Brush(usize)
 }
 
 impl ViewMode {
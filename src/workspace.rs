@@ -1,6 +1,15 @@
 pub mod workspace;
+pub mod handles;
+pub mod crop_constraint;
+pub mod straighten;
 pub mod album;
 pub mod album_image;
+pub mod auto_tone;
 pub mod image_manager;
 pub mod image_loader;
-pub mod parameters;
\ No newline at end of file
+pub mod parameters;
+pub mod parameter_group;
+pub mod preset;
+pub mod snapshot;
+pub mod histogram;
+pub mod history_step;
\ No newline at end of file
@@ -1,3 +1,5 @@
+use std::collections::BTreeSet;
+
 use itertools::Itertools;
 
 use super::album_image::AlbumImage;
@@ -5,11 +7,15 @@ use super::album_image::AlbumImage;
 pub struct Album {
     images: Vec<AlbumImage>,
     image_index: usize,
+    // Always contains the current photo
+    selected_photo_ids: BTreeSet<i32>,
 }
 
 impl Album {
     pub fn new(images: Vec<AlbumImage>) -> Self {
-        Self { images, image_index: 0 }
+        let mut album = Self { images, image_index: 0, selected_photo_ids: BTreeSet::new() };
+        album.select_only_current();
+        album
     }
 
     pub fn set_images(&mut self, images: Vec<AlbumImage>) {
@@ -23,6 +29,10 @@ impl Album {
             .find_or_last(|(_, image)| image.photo_id >= current_photo_id)
             .map(|(index, _)| index)
             .unwrap_or(0);
+
+        let images = &self.images;
+        self.selected_photo_ids.retain(|photo_id| images.iter().any(|image| image.photo_id == *photo_id));
+        self.selected_photo_ids.extend(self.get_photo_id());
     }
 
     pub fn get_images(&self) -> &Vec<AlbumImage> {
@@ -40,15 +50,121 @@ impl Album {
 
     pub fn next_image(&mut self) {
         self.image_index = (self.image_index + 1) % self.images.len();
+        self.select_only_current();
     }
 
     pub fn previous_image(&mut self) {
         self.image_index = (self.images.len() + self.image_index - 1) % self.images.len();
+        self.select_only_current();
     }
 
     pub fn set_image_index(&mut self, index: usize) {
         if index < self.images.len() {
             self.image_index = index;
+            self.select_only_current();
+        }
+    }
+
+    /**
+     * Adds or removes a photo from the selection, the current photo stays selected
+     */
+    pub fn toggle_image_selection(&mut self, index: usize) {
+        if let Some(image) = self.images.get(index) {
+            if index != self.image_index && !self.selected_photo_ids.remove(&image.photo_id) {
+                self.selected_photo_ids.insert(image.photo_id);
+            }
+        }
+    }
+
+    /**
+     * Selects all photos between the current photo and the given one
+     */
+    pub fn select_image_range(&mut self, index: usize) {
+        if index < self.images.len() {
+            let range = self.image_index.min(index)..=self.image_index.max(index);
+            self.selected_photo_ids = self.images[range].iter()
+                .map(|image| image.photo_id)
+                .collect();
         }
     }
-}
\ No newline at end of file
+
+    pub fn get_selected_photo_ids(&self) -> Vec<i32> {
+        self.selected_photo_ids.iter().copied().collect()
+    }
+
+    fn select_only_current(&mut self) {
+        self.selected_photo_ids = self.get_photo_id().into_iter().collect();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::*;
+
+    fn create_album(photo_ids: &[i32]) -> Album {
+        let images = photo_ids.iter()
+            .map(|photo_id| AlbumImage::new(*photo_id, None))
+            .collect();
+        Album::new(images)
+    }
+
+    #[rstest]
+    fn test_toggle_image_selection() {
+        // Arrange
+        let mut album = create_album(&[1, 2, 3, 4]);
+        album.set_image_index(1);
+
+        // Act
+        album.toggle_image_selection(3);
+        album.toggle_image_selection(0);
+        album.toggle_image_selection(0);
+        album.toggle_image_selection(1);
+
+        // Assert
+        assert_eq!(album.get_selected_photo_ids(), vec![2, 4]);
+    }
+
+    #[rstest]
+    #[case(3, vec![2, 3, 4])]
+    #[case(0, vec![1, 2])]
+    #[case(1, vec![2])]
+    fn test_select_image_range(#[case] index: usize, #[case] expected: Vec<i32>) {
+        // Arrange
+        let mut album = create_album(&[1, 2, 3, 4]);
+        album.set_image_index(1);
+        album.toggle_image_selection(0);
+
+        // Act
+        album.select_image_range(index);
+
+        // Assert
+        assert_eq!(album.get_selected_photo_ids(), expected);
+    }
+
+    #[rstest]
+    fn test_next_image_clears_selection() {
+        // Arrange
+        let mut album = create_album(&[1, 2, 3]);
+        album.select_image_range(2);
+
+        // Act
+        album.next_image();
+
+        // Assert
+        assert_eq!(album.get_selected_photo_ids(), vec![2]);
+    }
+
+    #[rstest]
+    fn test_set_images_keeps_remaining_selection() {
+        // Arrange
+        let mut album = create_album(&[1, 2, 3]);
+        album.select_image_range(2);
+
+        // Act
+        album.set_images(vec![AlbumImage::new(1, None), AlbumImage::new(3, None)]);
+
+        // Assert
+        assert_eq!(album.get_selected_photo_ids(), vec![1, 3]);
+    }
+}
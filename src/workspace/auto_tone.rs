@@ -0,0 +1,289 @@
+use crate::color_profile::srgb_to_linear;
+use crate::types::{rgb_pixel_to_lab, LabPixel, RawImage, RgbPixel};
+
+use super::parameters::BaseParameters;
+
+// Analysis is done on a subset of the pixels, which is plenty for percentiles and averages
+const MAX_SAMPLE_COUNT: usize = 256 * 256;
+
+// Targets are in Oklab lightness, which is what the shader adjusts
+const TARGET_MEDIAN: f32 = 0.55;
+const TARGET_RANGE: f32 = 0.8;
+const BLACK_POINT: f32 = 0.1;
+const WHITE_POINT: f32 = 0.95;
+
+const LOW_PERCENTILE: f32 = 0.02;
+const HIGH_PERCENTILE: f32 = 0.98;
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct AutoTone {
+    pub exposure: f32,
+    pub contrast: f32,
+    pub shadows: f32,
+    pub highlights: f32,
+    pub tint: f32,
+    pub temperature: f32
+}
+
+impl AutoTone {
+    pub fn new(image: &RawImage) -> Self {
+        let samples = sample_lab_pixels(image);
+        if samples.is_empty() {
+            return Self::default();
+        }
+
+        let mut lightness: Vec<f32> = samples.iter()
+            .map(|pixel| pixel.lightness)
+            .collect();
+        lightness.sort_by(|a, b| a.total_cmp(b));
+
+        let low = percentile(&lightness, LOW_PERCENTILE);
+        let median = percentile(&lightness, 0.5);
+        let high = percentile(&lightness, HIGH_PERCENTILE);
+
+        // The steps below mirror the order the shader applies them in: exposure, then contrast around 0.5
+        let exposure = ((TARGET_MEDIAN / median.max(1e-3) - 1.0) * 100.0).clamp(-100.0, 100.0);
+        let exposure_factor = exposure * 0.01 + 1.0;
+        let exposed_low = low * exposure_factor;
+        let exposed_high = high * exposure_factor;
+
+        let contrast_factor = (TARGET_RANGE / (exposed_high - exposed_low).max(1e-3)).clamp(0.5, 1.5);
+        let contrast = (contrast_factor - 1.0) * 200.0;
+        let contrasted_low = (exposed_low - 0.5) * contrast_factor + 0.5;
+        let contrasted_high = (exposed_high - 0.5) * contrast_factor + 0.5;
+
+        // Shadows get twice the effect in the darkest tones and highlights half in the brightest tones
+        let shadows = if contrasted_low < BLACK_POINT {
+            ((BLACK_POINT / contrasted_low.max(1e-3) - 1.0) * 50.0).clamp(0.0, 50.0)
+        } else {
+            0.0
+        };
+        let highlights = if contrasted_high > WHITE_POINT {
+            ((WHITE_POINT / contrasted_high - 1.0) * 200.0).clamp(-100.0, 0.0)
+        } else {
+            0.0
+        };
+
+        let (tint, temperature) = estimate_white_balance(&samples, high);
+
+        Self {
+            exposure,
+            contrast,
+            shadows,
+            highlights,
+            tint,
+            temperature
+        }
+    }
+
+    pub fn apply(&self, base_parameters: &mut BaseParameters) {
+        base_parameters.exposure = self.exposure;
+        base_parameters.contrast = self.contrast;
+        base_parameters.shadows = self.shadows;
+        base_parameters.highlights = self.highlights;
+        base_parameters.tint = self.tint;
+        base_parameters.temperature = self.temperature;
+    }
+}
+
+fn sample_lab_pixels(image: &RawImage) -> Vec<LabPixel> {
+    let pixel_count = image.width * image.height;
+    let step = ((pixel_count as f32 / MAX_SAMPLE_COUNT as f32).sqrt().ceil() as usize).max(1);
+
+    (0..image.height).step_by(step)
+        .flat_map(|y| (0..image.width).step_by(step).map(move |x| (x, y)))
+        .filter_map(|(x, y)| image.rgb_pixel_at(x, y))
+        .map(|pixel| rgb_pixel_to_lab(RgbPixel {
+            red: srgb_to_linear(pixel.red),
+            green: srgb_to_linear(pixel.green),
+            blue: srgb_to_linear(pixel.blue)
+        }))
+        .collect()
+}
+
+fn percentile(sorted: &[f32], fraction: f32) -> f32 {
+    let index = ((sorted.len() - 1) as f32 * fraction).round() as usize;
+    sorted[index]
+}
+
+/**
+ * Average of a grey world estimate (mean of all pixels) and a white patch estimate (mean of the brightest pixels).
+ * Returned values are the tint and temperature slider values that neutralize the estimated cast.
+ */
+fn estimate_white_balance(samples: &[LabPixel], high: f32) -> (f32, f32) {
+    let grey_world = mean_color(samples.iter());
+    let white_patch = mean_color(samples.iter().filter(|pixel| pixel.lightness >= high));
+
+    let tint = (grey_world.0 + white_patch.0) / 2.0;
+    let temperature = (grey_world.1 + white_patch.1) / 2.0;
+    ((-tint * 1000.0).clamp(-100.0, 100.0), (-temperature * 1000.0).clamp(-100.0, 100.0))
+}
+
+fn mean_color<'a>(pixels: impl Iterator<Item = &'a LabPixel>) -> (f32, f32) {
+    let (tint_sum, temperature_sum, count) = pixels
+        .fold((0.0, 0.0, 0), |(tint, temperature, count), pixel| {
+            (tint + pixel.tint, temperature + pixel.temperature, count + 1)
+        });
+    if count > 0 {
+        (tint_sum / count as f32, temperature_sum / count as f32)
+    } else {
+        (0.0, 0.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::*;
+
+    fn create_image(width: usize, height: usize, pixel_at: impl Fn(usize, usize) -> [u8; 3]) -> RawImage {
+        let mut pixels = vec![255; width * height * 4];
+        for y in 0..height {
+            for x in 0..width {
+                let index = (y * width + x) * 4;
+                pixels[index..index + 3].copy_from_slice(&pixel_at(x, y));
+            }
+        }
+        RawImage { width, height, pixels, is_wide_gamut: false }
+    }
+
+    fn create_gradient(width: usize, height: usize, from: u8, to: u8) -> RawImage {
+        create_image(width, height, |x, _| {
+            let value = from as f32 + (to as f32 - from as f32) * x as f32 / (width - 1) as f32;
+            [value as u8; 3]
+        })
+    }
+
+    #[rstest]
+    #[case(10, 80)]
+    #[case(20, 120)]
+    fn test_dark_image_increases_exposure(#[case] from: u8, #[case] to: u8) {
+        // Arrange
+        let image = create_gradient(64, 16, from, to);
+
+        // Act
+        let actual = AutoTone::new(&image);
+
+        // Assert
+        assert!(actual.exposure > 0.0, "Was: {:?}", actual);
+    }
+
+    #[rstest]
+    #[case(180, 255)]
+    #[case(200, 250)]
+    fn test_bright_image_decreases_exposure(#[case] from: u8, #[case] to: u8) {
+        // Arrange
+        let image = create_gradient(64, 16, from, to);
+
+        // Act
+        let actual = AutoTone::new(&image);
+
+        // Assert
+        assert!(actual.exposure < 0.0, "Was: {:?}", actual);
+    }
+
+    #[rstest]
+    fn test_low_contrast_image_increases_contrast() {
+        // Arrange
+        let image = create_gradient(64, 16, 100, 140);
+
+        // Act
+        let actual = AutoTone::new(&image);
+
+        // Assert
+        assert!(actual.contrast > 0.0, "Was: {:?}", actual);
+    }
+
+    #[rstest]
+    fn test_clipped_highlights_are_recovered() {
+        // Arrange
+        let image = create_image(64, 16, |x, _| {
+            if x < 48 {
+                [(30 + x * 2) as u8; 3]
+            } else {
+                [255; 3]
+            }
+        });
+
+        // Act
+        let actual = AutoTone::new(&image);
+
+        // Assert
+        assert!(actual.highlights < 0.0, "Was: {:?}", actual);
+    }
+
+    #[rstest]
+    fn test_neutral_image_keeps_white_balance() {
+        // Arrange
+        let image = create_gradient(64, 16, 20, 230);
+
+        // Act
+        let actual = AutoTone::new(&image);
+
+        // Assert
+        assert!(actual.tint.abs() < 1.0, "Was: {:?}", actual);
+        assert!(actual.temperature.abs() < 1.0, "Was: {:?}", actual);
+    }
+
+    #[rstest]
+    fn test_blue_cast_is_warmed() {
+        // Arrange
+        let image = create_image(64, 16, |x, _| {
+            let value = (x * 3) as u8;
+            [value, value, value.saturating_add(40)]
+        });
+
+        // Act
+        let actual = AutoTone::new(&image);
+
+        // Assert
+        assert!(actual.temperature > 0.0, "Was: {:?}", actual);
+    }
+
+    #[rstest]
+    fn test_is_deterministic() {
+        // Arrange
+        let image = create_image(640, 480, |x, y| [(x % 256) as u8, (y % 256) as u8, ((x + y) % 256) as u8]);
+
+        // Act
+        let first = AutoTone::new(&image);
+        let second = AutoTone::new(&image);
+
+        // Assert
+        assert_eq!(first, second);
+    }
+
+    #[rstest]
+    fn test_apply_sets_base_parameters() {
+        // Arrange
+        let auto_tone = AutoTone {
+            exposure: 1.0,
+            contrast: 2.0,
+            shadows: 3.0,
+            highlights: 4.0,
+            tint: 5.0,
+            temperature: 6.0
+        };
+        let mut base_parameters = BaseParameters {
+            midtones: 7.0,
+            saturation: 8.0,
+            ..BaseParameters::default()
+        };
+
+        // Act
+        auto_tone.apply(&mut base_parameters);
+
+        // Assert
+        let expected = BaseParameters {
+            exposure: 1.0,
+            contrast: 2.0,
+            shadows: 3.0,
+            midtones: 7.0,
+            highlights: 4.0,
+            tint: 5.0,
+            temperature: 6.0,
+            saturation: 8.0
+        };
+        assert_eq!(base_parameters, expected);
+    }
+}
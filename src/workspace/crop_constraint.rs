@@ -0,0 +1,259 @@
+use cgmath::Matrix;
+
+use crate::pipeline::lens_correction::LensCorrection;
+use crate::pipeline::transform::{transform, Rectangle};
+use crate::pipeline::viewport::ViewportCrop;
+
+use super::parameters::Crop;
+
+// Bisection steps for how far the crop is shrunk towards the image center to fit the lens corrected image, the center
+// itself always fits. The smallest factor tried is a crop of a few pixels.
+const LENS_CORRECTION_ITERATIONS: usize = 20;
+const MIN_LENS_CORRECTION_FACTOR: f32 = 1.0 / 1024.0;
+// Points sampled along each edge, as distortion bends the edges in the source image
+const EDGE_SAMPLES: usize = 4;
+
+/**
+ * Shrinks and moves the crop so that the rotated crop rectangle stays within the source image. The image is axis
+ * aligned, so the crop is inside it when the bounding box of its corners is. With lens correction the crop is placed on
+ * the corrected image, so it's also shrunk and moved towards the center until its edges map inside the source image.
+ */
+pub fn constrain_crop(crop: &mut Crop) {
+    let image_width = crop.source_image_width as f32;
+    let image_height = crop.source_image_height as f32;
+    if image_width <= 0.0 || image_height <= 0.0 {
+        return;
+    }
+
+    let (half_width, half_height) = half_extents(&crop.clone().into());
+    let factor = (image_width / (2.0 * half_width)).min(image_height / (2.0 * half_height));
+    if factor < 1.0 {
+        crop.scale += factor.log2();
+    }
+
+    // Rounded up, as the crop size is truncated to whole pixels
+    let (half_width, half_height) = half_extents(&crop.clone().into());
+    crop.center_x = clamp_center(crop.center_x, half_width.ceil() as i32, image_width as i32);
+    crop.center_y = clamp_center(crop.center_y, half_height.ceil() as i32, image_height as i32);
+
+    let lens_correction = LensCorrection::from(&*crop);
+    if lens_correction == LensCorrection::default() {
+        return;
+    }
+    if is_inside_source(crop, &lens_correction) {
+        return;
+    }
+
+    // Largest factor known to fit and smallest known not to
+    let (mut fitting, mut not_fitting) = (MIN_LENS_CORRECTION_FACTOR, 1.0);
+    for _ in 0..LENS_CORRECTION_ITERATIONS {
+        let factor = (fitting + not_fitting) / 2.0;
+        if is_inside_source(&shrink_towards_center(crop, factor), &lens_correction) {
+            fitting = factor;
+        } else {
+            not_fitting = factor;
+        }
+    }
+    *crop = shrink_towards_center(crop, fitting);
+}
+
+/**
+ * Crop scaled by the factor, with its center moved towards the image center by the same factor
+ */
+fn shrink_towards_center(crop: &Crop, factor: f32) -> Crop {
+    let image_center_x = crop.source_image_width as f32 / 2.0;
+    let image_center_y = crop.source_image_height as f32 / 2.0;
+    Crop {
+        scale: crop.scale + factor.log2(),
+        center_x: (image_center_x + (crop.center_x as f32 - image_center_x) * factor).round() as i32,
+        center_y: (image_center_y + (crop.center_y as f32 - image_center_y) * factor).round() as i32,
+        ..crop.clone()
+    }
+}
+
+fn is_inside_source(crop: &Crop, lens_correction: &LensCorrection) -> bool {
+    let image_width = crop.source_image_width;
+    let image_height = crop.source_image_height;
+    let [top_left, top_right, bottom_left, bottom_right] = crop_corners(&crop.clone().into());
+    [(top_left, top_right), (top_right, bottom_right), (bottom_right, bottom_left), (bottom_left, top_left)].iter()
+        .flat_map(|(from, to)| (0..EDGE_SAMPLES).map(move |i| {
+            let t = i as f32 / EDGE_SAMPLES as f32;
+            (from.0 + (to.0 - from.0) * t, from.1 + (to.1 - from.1) * t)
+        }))
+        .all(|(x, y)| {
+            let (source_x, source_y) = lens_correction.source_position(x, y, image_width, image_height);
+            // Half a pixel of slack, as the crop size is truncated to whole pixels
+            (-0.5..=image_width as f32 + 0.5).contains(&source_x) && (-0.5..=image_height as f32 + 0.5).contains(&source_y)
+        })
+}
+
+/**
+ * Corners of the rotated crop in image coordinates
+ */
+pub fn crop_corners(view: &ViewportCrop) -> [(f32, f32); 4] {
+    let uv_area = Rectangle {
+        center_x: 0.5,
+        center_y: 0.5,
+        width: 1.0,
+        height: 1.0,
+        angle_degrees: 0.0
+    };
+    let (width, height) = view.signed_size();
+    let crop_area = Rectangle {
+        center_x: view.center_x as f32,
+        center_y: view.center_y as f32,
+        width,
+        height,
+        angle_degrees: view.angle_degrees
+    };
+    let matrix = transform(&uv_area, &crop_area).transpose();
+    [(0.0, 0.0), (1.0, 0.0), (0.0, 1.0), (1.0, 1.0)].map(|(x, y)| {
+        let corner = matrix * cgmath::vec4(x, y, 0.0, 1.0);
+        (corner.x, corner.y)
+    })
+}
+
+fn half_extents(view: &ViewportCrop) -> (f32, f32) {
+    let center_x = view.center_x as f32;
+    let center_y = view.center_y as f32;
+    crop_corners(view).iter()
+        .fold((0.0, 0.0), |(half_width, half_height), (x, y)| {
+            (f32::max(half_width, (x - center_x).abs()), f32::max(half_height, (y - center_y).abs()))
+        })
+}
+
+fn clamp_center(center: i32, half_size: i32, image_size: i32) -> i32 {
+    let min = half_size;
+    let max = image_size - half_size;
+    if min > max {
+        image_size / 2
+    } else {
+        center.clamp(min, max)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::*;
+
+    use crate::workspace::parameters::CropPreset;
+
+    fn create_crop(center_x: i32, center_y: i32, scale: f32, angle_degrees: f32, preset: CropPreset) -> Crop {
+        Crop {
+            center_x,
+            center_y,
+            source_image_width: 1000,
+            source_image_height: 500,
+            scale,
+            angle_degrees,
+            preset,
+            rotation: 0,
+            ..Crop::default()
+        }
+    }
+
+    #[rstest]
+    #[case(500, 250, 0.0, 10.0, CropPreset::Original)]
+    #[case(500, 250, 0.0, -30.0, CropPreset::Original)]
+    #[case(900, 100, -1.0, 0.0, CropPreset::Original)]
+    #[case(0, 500, -1.0, 45.0, CropPreset::Original)]
+    #[case(500, 250, 0.0, 20.0, CropPreset::Ratio(1, 1))]
+    #[case(-200, 800, -2.0, 80.0, CropPreset::Ratio(16, 9))]
+    fn test_constrain_crop_inside_image(
+            #[case] center_x: i32,
+            #[case] center_y: i32,
+            #[case] scale: f32,
+            #[case] angle_degrees: f32,
+            #[case] preset: CropPreset) {
+        // Arrange
+        let mut crop = create_crop(center_x, center_y, scale, angle_degrees, preset);
+
+        // Act
+        constrain_crop(&mut crop);
+
+        // Assert
+        for (x, y) in crop_corners(&crop.clone().into()) {
+            assert!((-1e-2..=1000.01).contains(&x) && (-1e-2..=500.01).contains(&y), "Corner outside image: {}, {} for {:?}", x, y, crop);
+        }
+    }
+
+    #[rstest]
+    #[case(100.0, 0.0, 0.0)]
+    #[case(0.0, -100.0, 0.0)]
+    #[case(60.0, 40.0, 0.0)]
+    #[case(0.0, 0.0, -100.0)]
+    #[case(-50.0, 0.0, 100.0)]
+    fn test_constrain_crop_with_lens_correction_inside_source(
+            #[case] perspective_vertical: f32,
+            #[case] perspective_horizontal: f32,
+            #[case] distortion: f32) {
+        // Arrange
+        let mut crop = Crop {
+            perspective_vertical,
+            perspective_horizontal,
+            distortion,
+            ..create_crop(500, 250, 0.0, 0.0, CropPreset::Original)
+        };
+
+        // Act
+        constrain_crop(&mut crop);
+
+        // Assert
+        let lens_correction = LensCorrection::from(&crop);
+        assert!(crop.scale < 0.0, "Was: {}", crop.scale);
+        for (x, y) in crop_corners(&crop.clone().into()) {
+            let (source_x, source_y) = lens_correction.source_position(x, y, 1000, 500);
+            assert!((-0.5..=1000.5).contains(&source_x) && (-0.5..=500.5).contains(&source_y), "Corner outside source: {}, {} for {:?}", source_x, source_y, crop);
+        }
+    }
+
+    #[rstest]
+    #[case(100.0, 100.0, 100.0)]
+    #[case(-100.0, -100.0, -100.0)]
+    #[case(100.0, -100.0, -100.0)]
+    #[case(-100.0, 100.0, 100.0)]
+    fn test_constrain_crop_with_extreme_lens_correction(
+            #[case] perspective_vertical: f32,
+            #[case] perspective_horizontal: f32,
+            #[case] distortion: f32) {
+        // Arrange
+        let mut crop = Crop {
+            perspective_vertical,
+            perspective_horizontal,
+            distortion,
+            ..create_crop(900, 50, 0.0, 45.0, CropPreset::Ratio(16, 9))
+        };
+
+        // Act
+        constrain_crop(&mut crop);
+
+        // Assert
+        assert!(is_inside_source(&crop, &LensCorrection::from(&crop)), "Outside source: {:?}", crop);
+    }
+
+    #[rstest]
+    fn test_constrain_crop_keeps_crop_inside_image() {
+        // Arrange
+        let mut crop = create_crop(400, 200, -1.0, 5.0, CropPreset::Original);
+        let expected = crop.clone();
+
+        // Act
+        constrain_crop(&mut crop);
+
+        // Assert
+        assert_eq!(crop, expected);
+    }
+
+    #[rstest]
+    fn test_constrain_crop_shrinks_rotated_crop() {
+        // Arrange
+        let mut crop = create_crop(500, 250, 0.0, 90.0, CropPreset::Ratio(1, 1));
+
+        // Act
+        constrain_crop(&mut crop);
+
+        // Assert
+        assert!((crop.scale - (-1.0)).abs() < 1e-3, "Was: {}", crop.scale);
+    }
+}
@@ -0,0 +1,395 @@
+use cgmath::Matrix;
+
+use crate::pipeline::transform::{transform, Rectangle};
+use crate::pipeline::viewport::ViewportCrop;
+
+use super::parameters::RadialMask;
+
+// Handle radius as a fraction of the longest side of the visible view
+const HANDLE_SIZE: f32 = 0.012;
+// Distance of the rotation handle from the mask boundary, in handle radii
+const ROTATION_HANDLE_OFFSET: f32 = 4.0;
+const MIN_CROP_SCALE: f32 = -5.0;
+
+/**
+ * Handle radius in image pixels, so that handles keep their size on screen regardless of zoom
+ */
+pub fn handle_radius(view: &ViewportCrop) -> f32 {
+    HANDLE_SIZE * (view.width.max(view.height) as f32) * view.scale
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MaskHandle {
+    Center,
+    Width,
+    Height,
+    Rotation,
+    Feather
+}
+
+/**
+ * Handle positions in image coordinates. Global masks cover the whole image, so they don't have any handles, and
+ * linear masks don't have a height.
+ */
+pub fn mask_handle_positions(radial_mask: &RadialMask, handle_radius: f32) -> Vec<(MaskHandle, f32, f32)> {
+    if radial_mask.is_global {
+        return Vec::new();
+    }
+
+    let width = radial_mask.width as f32;
+    let height = radial_mask.height as f32;
+    let feather = (radial_mask.feather + 100.0) / 200.0;
+    let rotation_distance = if radial_mask.is_linear { width } else { height } + handle_radius * ROTATION_HANDLE_OFFSET;
+
+    let mut handles = vec![
+        (MaskHandle::Center, 0.0, 0.0),
+        (MaskHandle::Width, width, 0.0),
+        (MaskHandle::Feather, -width * (1.0 - feather), 0.0),
+        (MaskHandle::Rotation, 0.0, -rotation_distance)
+    ];
+    if !radial_mask.is_linear {
+        handles.push((MaskHandle::Height, 0.0, height));
+    }
+
+    handles.into_iter()
+        .map(|(handle, local_x, local_y)| {
+            let (x, y) = mask_to_image(radial_mask, local_x, local_y);
+            (handle, x, y)
+        })
+        .collect()
+}
+
+/**
+ * Closest handle within the handle radius. The center wins ties, so a mask without a size can still be moved.
+ */
+pub fn find_mask_handle(radial_mask: &RadialMask, x: f32, y: f32, handle_radius: f32) -> Option<MaskHandle> {
+    mask_handle_positions(radial_mask, handle_radius).into_iter()
+        .map(|(handle, handle_x, handle_y)| (handle, (handle_x - x).hypot(handle_y - y)))
+        .filter(|(_, distance)| *distance <= handle_radius)
+        .min_by(|(handle_a, distance_a), (handle_b, distance_b)| {
+            distance_a.total_cmp(distance_b)
+                .then_with(|| (*handle_b == MaskHandle::Center).cmp(&(*handle_a == MaskHandle::Center)))
+        })
+        .map(|(handle, _)| handle)
+}
+
+pub fn drag_mask_handle(radial_mask: &mut RadialMask, handle: MaskHandle, x: f32, y: f32) {
+    let (local_x, local_y) = image_to_mask(radial_mask, x, y);
+    match handle {
+        MaskHandle::Center => {
+            radial_mask.center_x = x as i32;
+            radial_mask.center_y = y as i32;
+        },
+        MaskHandle::Width => {
+            radial_mask.width = local_x.abs() as i32;
+        },
+        MaskHandle::Height => {
+            radial_mask.height = local_y.abs() as i32;
+        },
+        MaskHandle::Rotation => {
+            let delta_x = x - radial_mask.center_x as f32;
+            let delta_y = y - radial_mask.center_y as f32;
+            radial_mask.angle_degrees = (-delta_x).atan2(-delta_y).to_degrees();
+        },
+        MaskHandle::Feather => {
+            let width = (radial_mask.width as f32).max(1.0);
+            let feather = (1.0 - local_x.abs() / width).clamp(0.0, 1.0);
+            radial_mask.feather = feather * 200.0 - 100.0;
+        }
+    }
+}
+
+/**
+ * Mask coordinates are rotated by the mask angle around the mask center, matching `calculate_alpha` in the shader
+ */
+fn mask_to_image(radial_mask: &RadialMask, local_x: f32, local_y: f32) -> (f32, f32) {
+    let (sin, cos) = radial_mask.angle_degrees.to_radians().sin_cos();
+    (
+        radial_mask.center_x as f32 + local_x * cos + local_y * sin,
+        radial_mask.center_y as f32 - local_x * sin + local_y * cos
+    )
+}
+
+fn image_to_mask(radial_mask: &RadialMask, x: f32, y: f32) -> (f32, f32) {
+    let (sin, cos) = radial_mask.angle_degrees.to_radians().sin_cos();
+    let delta_x = x - radial_mask.center_x as f32;
+    let delta_y = y - radial_mask.center_y as f32;
+    (delta_x * cos - delta_y * sin, delta_x * sin + delta_y * cos)
+}
+
+/**
+ * Edge or corner of the crop in view coordinates, where each coordinate is 0.0, 0.5 or 1.0. The opposite side stays
+ * in place while dragging.
+ */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CropHandle {
+    pub x: f32,
+    pub y: f32
+}
+
+#[derive(Debug, Clone)]
+pub struct CropDrag {
+    pub handle: CropHandle,
+    // Crop view and scale when the drag started
+    pub view: ViewportCrop,
+    pub scale: f32,
+    pub free_size: Option<(i32, i32)>
+}
+
+pub struct CropResize {
+    pub center_x: i32,
+    pub center_y: i32,
+    pub scale: f32,
+    pub free_size: Option<(i32, i32)>
+}
+
+/**
+ * In crop mode the view is the crop, so the crop spans the view coordinates [0, 1] on both axes
+ */
+pub fn find_crop_handle(view: &ViewportCrop, view_x: f32, view_y: f32) -> Option<CropHandle> {
+    let radius = handle_radius(view);
+    let radius_x = radius / (view.width.max(1) as f32);
+    let radius_y = radius / (view.height.max(1) as f32);
+
+    let snap = |position: f32, radius: f32| {
+        if position.abs() <= radius {
+            Some(0.0)
+        } else if (position - 1.0).abs() <= radius {
+            Some(1.0)
+        } else if (0.0..=1.0).contains(&position) {
+            Some(0.5)
+        } else {
+            None
+        }
+    };
+    let x = snap(view_x, radius_x)?;
+    let y = snap(view_y, radius_y)?;
+    let is_middle = x == 0.5 && y == 0.5;
+    (!is_middle).then_some(CropHandle { x, y })
+}
+
+/**
+ * The crop keeps its aspect ratio, so it's scaled by the most dragged axis. A free crop keeps its scale and resizes
+ * each axis on its own instead.
+ */
+pub fn resize_crop(crop_drag: &CropDrag, view_x: f32, view_y: f32) -> CropResize {
+    let handle = crop_drag.handle;
+    let axis_factor = |handle_position: f32, position: f32| {
+        if handle_position == 0.5 {
+            None
+        } else {
+            let anchor = 1.0 - handle_position;
+            Some((position - anchor).abs())
+        }
+    };
+    let factor_x = axis_factor(handle.x, view_x);
+    let factor_y = axis_factor(handle.y, view_y);
+
+    let (scale, free_size, factor_x, factor_y) = match crop_drag.free_size {
+        Some((width, height)) => {
+            let factor_x = factor_x.map(|factor| factor.max(1.0 / width.max(1) as f32));
+            let factor_y = factor_y.map(|factor| factor.max(1.0 / height.max(1) as f32));
+            let free_size = (
+                (width as f32 * factor_x.unwrap_or(1.0)).round() as i32,
+                (height as f32 * factor_y.unwrap_or(1.0)).round() as i32
+            );
+            (crop_drag.scale, Some(free_size), factor_x, factor_y)
+        },
+        None => {
+            let factor = [factor_x, factor_y].into_iter()
+                .flatten()
+                .fold(0.0, f32::max)
+                .max(f32::EPSILON);
+            let scale = (crop_drag.scale + factor.log2()).clamp(MIN_CROP_SCALE, 0.0);
+            let factor = f32::powf(2.0, scale - crop_drag.scale);
+            (scale, None, Some(factor), Some(factor))
+        }
+    };
+
+    // The new center in the view coordinates of the drag origin
+    let center_x = (1.0 - handle.x) + (handle.x - 0.5) * factor_x.unwrap_or(1.0);
+    let center_y = (1.0 - handle.y) + (handle.y - 0.5) * factor_y.unwrap_or(1.0);
+    let center_x = if handle.x == 0.5 { 0.5 } else { center_x };
+    let center_y = if handle.y == 0.5 { 0.5 } else { center_y };
+
+    let uv_area = Rectangle {
+        center_x: 0.5,
+        center_y: 0.5,
+        width: 1.0,
+        height: 1.0,
+        angle_degrees: 0.0
+    };
+    let (width, height) = crop_drag.view.signed_size();
+    let image_area = Rectangle {
+        center_x: crop_drag.view.center_x as f32,
+        center_y: crop_drag.view.center_y as f32,
+        width,
+        height,
+        angle_degrees: crop_drag.view.angle_degrees
+    };
+    let center = transform(&uv_area, &image_area).transpose() * cgmath::vec4(center_x, center_y, 0.0, 1.0);
+
+    CropResize {
+        center_x: center.x.round() as i32,
+        center_y: center.y.round() as i32,
+        scale,
+        free_size
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::*;
+
+    fn create_radial_mask(angle_degrees: f32) -> RadialMask {
+        RadialMask {
+            center_x: 100,
+            center_y: 100,
+            width: 40,
+            height: 20,
+            angle_degrees,
+            ..RadialMask::default()
+        }
+    }
+
+    fn create_view() -> ViewportCrop {
+        ViewportCrop {
+            center_x: 500,
+            center_y: 250,
+            width: 1000,
+            height: 500,
+            angle_degrees: 0.0,
+            scale: 1.0,
+            ..ViewportCrop::default()
+        }
+    }
+
+    #[rstest]
+    #[case(0.0, MaskHandle::Width, (140.0, 100.0))]
+    #[case(0.0, MaskHandle::Height, (100.0, 120.0))]
+    #[case(90.0, MaskHandle::Width, (100.0, 60.0))]
+    #[case(90.0, MaskHandle::Height, (120.0, 100.0))]
+    fn test_mask_handle_positions(#[case] angle_degrees: f32, #[case] handle: MaskHandle, #[case] expected: (f32, f32)) {
+        // Arrange
+        let radial_mask = create_radial_mask(angle_degrees);
+
+        // Act
+        let positions = mask_handle_positions(&radial_mask, 5.0);
+
+        // Assert
+        let (_, x, y) = positions.into_iter().find(|(h, _, _)| *h == handle).unwrap();
+        assert!((x - expected.0).abs() < 1e-3 && (y - expected.1).abs() < 1e-3, "Was: {}, {}", x, y);
+    }
+
+    #[rstest]
+    #[case(false, 5)]
+    #[case(true, 4)]
+    fn test_mask_handle_positions_linear(#[case] is_linear: bool, #[case] expected: usize) {
+        // Arrange
+        let radial_mask = RadialMask {
+            is_linear,
+            ..create_radial_mask(0.0)
+        };
+
+        // Act
+        let actual = mask_handle_positions(&radial_mask, 5.0).len();
+
+        // Assert
+        assert_eq!(actual, expected);
+    }
+
+    #[rstest]
+    #[case(141.0, 101.0, Some(MaskHandle::Width))]
+    #[case(100.0, 100.0, Some(MaskHandle::Center))]
+    #[case(100.0, 60.0, Some(MaskHandle::Rotation))]
+    #[case(120.0, 110.0, None)]
+    fn test_find_mask_handle(#[case] x: f32, #[case] y: f32, #[case] expected: Option<MaskHandle>) {
+        // Arrange
+        let radial_mask = create_radial_mask(0.0);
+
+        // Act
+        let actual = find_mask_handle(&radial_mask, x, y, 5.0);
+
+        // Assert
+        assert_eq!(actual, expected);
+    }
+
+    #[rstest]
+    #[case(0.0)]
+    #[case(35.0)]
+    #[case(-120.0)]
+    fn test_drag_mask_handle_roundtrip(#[case] angle_degrees: f32) {
+        // Arrange
+        let mut radial_mask = create_radial_mask(angle_degrees);
+        let expected = radial_mask.clone();
+        let positions = mask_handle_positions(&radial_mask, 5.0);
+
+        // Act
+        for (handle, x, y) in positions {
+            drag_mask_handle(&mut radial_mask, handle, x, y);
+        }
+
+        // Assert
+        assert_eq!(radial_mask.center_x, expected.center_x);
+        assert_eq!(radial_mask.center_y, expected.center_y);
+        assert!((radial_mask.width - expected.width).abs() <= 1, "Was: {}", radial_mask.width);
+        assert!((radial_mask.height - expected.height).abs() <= 1, "Was: {}", radial_mask.height);
+        assert!((radial_mask.angle_degrees - expected.angle_degrees).abs() < 1e-2, "Was: {}", radial_mask.angle_degrees);
+    }
+
+    #[rstest]
+    #[case(0.0, 0.0, Some(CropHandle { x: 0.0, y: 0.0 }))]
+    #[case(0.999, 0.5, Some(CropHandle { x: 1.0, y: 0.5 }))]
+    #[case(0.4, 0.01, Some(CropHandle { x: 0.5, y: 0.0 }))]
+    #[case(0.5, 0.5, None)]
+    #[case(1.5, 0.5, None)]
+    fn test_find_crop_handle(#[case] view_x: f32, #[case] view_y: f32, #[case] expected: Option<CropHandle>) {
+        // Act
+        let actual = find_crop_handle(&create_view(), view_x, view_y);
+
+        // Assert
+        assert_eq!(actual, expected);
+    }
+
+    #[rstest]
+    #[case(CropHandle { x: 1.0, y: 1.0 }, (0.5, 0.5), (250, 125, -2.0))]
+    #[case(CropHandle { x: 0.0, y: 0.5 }, (0.5, 0.5), (750, 250, -2.0))]
+    #[case(CropHandle { x: 1.0, y: 0.0 }, (2.0, -1.0), (1000, 0, 0.0))]
+    fn test_resize_crop(#[case] handle: CropHandle, #[case] view_position: (f32, f32), #[case] expected: (i32, i32, f32)) {
+        // Arrange
+        let crop_drag = CropDrag {
+            handle,
+            view: create_view(),
+            scale: -1.0,
+            free_size: None
+        };
+
+        // Act
+        let actual = resize_crop(&crop_drag, view_position.0, view_position.1);
+
+        // Assert
+        assert_eq!((actual.center_x, actual.center_y, actual.scale), expected);
+    }
+
+    #[rstest]
+    #[case(CropHandle { x: 1.0, y: 0.5 }, (0.5, 0.5), (250, 250, (1000, 1000)))]
+    #[case(CropHandle { x: 0.5, y: 0.0 }, (0.2, 0.5), (500, 375, (2000, 500)))]
+    #[case(CropHandle { x: 0.0, y: 0.0 }, (0.25, 0.5), (625, 375, (1500, 500)))]
+    fn test_resize_free_crop(#[case] handle: CropHandle, #[case] view_position: (f32, f32), #[case] expected: (i32, i32, (i32, i32))) {
+        // Arrange
+        let crop_drag = CropDrag {
+            handle,
+            view: create_view(),
+            scale: -1.0,
+            free_size: Some((2000, 1000))
+        };
+
+        // Act
+        let actual = resize_crop(&crop_drag, view_position.0, view_position.1);
+
+        // Assert
+        assert_eq!((actual.center_x, actual.center_y, actual.free_size.unwrap()), expected);
+        assert_eq!(actual.scale, -1.0);
+    }
+}
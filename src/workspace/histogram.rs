@@ -0,0 +1,263 @@
+use std::future::Future;
+use std::sync::Arc;
+
+use cgmath::Matrix;
+
+use crate::pipeline::cpu_pipeline::CpuPipeline;
+use crate::pipeline::transform::{transform, Rectangle};
+use crate::pipeline::viewport::{ViewportCrop, ViewportParameters};
+use crate::types::{RawImage, RgbPixel};
+
+use super::workspace::Workspace;
+
+pub const BIN_COUNT: usize = 256;
+
+// The histogram is computed on the CPU from a grid of samples within the crop area. This keeps it cheap enough to
+// recompute while a slider is dragged.
+const SAMPLE_GRID_SIZE: usize = 128;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Histogram {
+    pub red: Vec<u32>,
+    pub green: Vec<u32>,
+    pub blue: Vec<u32>,
+    pub luminance: Vec<u32>
+}
+
+impl Histogram {
+    pub fn new(image: &RawImage, parameters: &ViewportParameters) -> Self {
+        let pipeline = CpuPipeline::new(parameters, image.width, image.height);
+        let mut histogram = Self {
+            red: vec![0; BIN_COUNT],
+            green: vec![0; BIN_COUNT],
+            blue: vec![0; BIN_COUNT],
+            luminance: vec![0; BIN_COUNT]
+        };
+
+        for (image_x, image_y) in sample_positions(&parameters.crop) {
+            if let Some(lab) = parameters.lens_correction.sample(image, image_x, image_y) {
+                histogram.add(&pipeline.process(lab, image_x, image_y));
+            }
+        }
+
+        histogram
+    }
+
+    pub fn max_count(&self) -> u32 {
+        [&self.red, &self.green, &self.blue, &self.luminance].iter()
+            .flat_map(|bins| bins.iter())
+            .max()
+            .copied()
+            .unwrap_or(0)
+    }
+
+    fn add(&mut self, pixel: &RgbPixel) {
+        let luminance = 0.2126 * pixel.red + 0.7152 * pixel.green + 0.0722 * pixel.blue;
+        self.red[to_bin(pixel.red)] += 1;
+        self.green[to_bin(pixel.green)] += 1;
+        self.blue[to_bin(pixel.blue)] += 1;
+        self.luminance[to_bin(luminance)] += 1;
+    }
+}
+
+fn to_bin(value: f32) -> usize {
+    ((value.clamp(0.0, 1.0) * (BIN_COUNT - 1) as f32).round() as usize).min(BIN_COUNT - 1)
+}
+
+fn sample_positions(crop: &ViewportCrop) -> Vec<(f32, f32)> {
+    let uv_area = Rectangle {
+        center_x: 0.5,
+        center_y: 0.5,
+        width: 1.0,
+        height: 1.0,
+        angle_degrees: 0.0
+    };
+    let (width, height) = crop.signed_size();
+    let crop_area = Rectangle {
+        center_x: crop.center_x as f32,
+        center_y: crop.center_y as f32,
+        width,
+        height,
+        angle_degrees: crop.angle_degrees
+    };
+    let uv_to_image = transform(&uv_area, &crop_area).transpose();
+
+    (0..SAMPLE_GRID_SIZE)
+        .flat_map(|y| (0..SAMPLE_GRID_SIZE).map(move |x| (x, y)))
+        .map(|(x, y)| {
+            let u = (x as f32 + 0.5) / SAMPLE_GRID_SIZE as f32;
+            let v = (y as f32 + 0.5) / SAMPLE_GRID_SIZE as f32;
+            let position = uv_to_image * cgmath::vec4(u, v, 0.0, 1.0);
+            (position.x / position.w, position.y / position.w)
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone)]
+pub struct HistogramResult {
+    pub photo_id: i32,
+    pub parameters: ViewportParameters,
+    pub histogram: Histogram
+}
+
+pub async fn compute_histogram(photo_id: i32, image: Arc<RawImage>, parameters: ViewportParameters) -> HistogramResult {
+    let histogram = Histogram::new(&image, &parameters);
+    HistogramResult { photo_id, parameters, histogram }
+}
+
+/**
+ * Keeps the histogram of the current workspace. It's computed in the background, one at a time, so dragging a slider
+ * only recomputes it once the previous one is done.
+ */
+#[derive(Default)]
+pub struct HistogramCache {
+    photo_id: Option<i32>,
+    parameters: Option<ViewportParameters>,
+    histogram: Option<Histogram>,
+    is_computing: bool
+}
+
+impl HistogramCache {
+    /**
+     * Returns the computation to run when the histogram is out of date and none is running already
+     */
+    pub fn update(&mut self, workspace: Option<&Workspace>) -> Option<impl Future<Output = HistogramResult>> {
+        let Some(workspace) = workspace else {
+            self.histogram = None;
+            return None;
+        };
+        let Some(image) = workspace.current_source_image() else {
+            self.histogram = None;
+            return None;
+        };
+
+        let photo_id = workspace.get_photo_id();
+        let parameters = workspace.parameters_to_display();
+        let is_up_to_date = self.histogram.is_some() &&
+            self.photo_id == Some(photo_id) &&
+            self.parameters.as_ref() == Some(&parameters);
+
+        if is_up_to_date || self.is_computing {
+            None
+        } else {
+            self.is_computing = true;
+            Some(compute_histogram(photo_id, image, parameters))
+        }
+    }
+
+    pub fn set(&mut self, histogram_result: HistogramResult) {
+        self.photo_id = Some(histogram_result.photo_id);
+        self.parameters = Some(histogram_result.parameters);
+        self.histogram = Some(histogram_result.histogram);
+        self.is_computing = false;
+    }
+
+    pub fn get(&self) -> Option<&Histogram> {
+        self.histogram.as_ref()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::*;
+
+    use std::sync::Mutex;
+
+    use crate::workspace::parameters::{ParameterHistory, Parameters};
+    use crate::workspace::workspace::{ImageView, WorkspaceImage};
+
+    fn create_image(width: usize, height: usize, value: u8) -> RawImage {
+        RawImage {
+            width,
+            height,
+            pixels: vec![value; width * height * 4],
+            is_wide_gamut: false
+        }
+    }
+
+    fn create_parameters(width: usize, height: usize) -> ViewportParameters {
+        ViewportParameters {
+            crop: ViewportCrop {
+                center_x: (width / 2) as i32,
+                center_y: (height / 2) as i32,
+                width: width as i32,
+                height: height as i32,
+                angle_degrees: 0.0,
+                scale: 1.0,
+                ..ViewportCrop::default()
+            },
+            ..ViewportParameters::default()
+        }
+    }
+
+    #[rstest]
+    #[case(0, 0)]
+    #[case(128, 128)]
+    #[case(255, 255)]
+    fn test_uniform_image_fills_single_bin(#[case] value: u8, #[case] expected_bin: usize) {
+        // Arrange
+        let image = create_image(64, 32, value);
+        let parameters = create_parameters(64, 32);
+
+        // Act
+        let actual = Histogram::new(&image, &parameters);
+
+        // Assert
+        let sample_count = (SAMPLE_GRID_SIZE * SAMPLE_GRID_SIZE) as u32;
+        assert_eq!(actual.red[expected_bin], sample_count);
+        assert_eq!(actual.green[expected_bin], sample_count);
+        assert_eq!(actual.blue[expected_bin], sample_count);
+        assert_eq!(actual.luminance[expected_bin], sample_count);
+    }
+
+    #[rstest]
+    fn test_exposure_shifts_histogram() {
+        // Arrange
+        let image = create_image(64, 32, 128);
+        let parameters = ViewportParameters {
+            exposure: 30.0,
+            ..create_parameters(64, 32)
+        };
+
+        // Act
+        let actual = Histogram::new(&image, &parameters);
+
+        // Assert
+        let peak_bin = actual.luminance.iter().enumerate()
+            .max_by_key(|(_, count)| **count)
+            .map(|(bin, _)| bin)
+            .unwrap();
+        assert!(peak_bin > 128, "Peak was at: {}", peak_bin);
+    }
+
+    #[rstest]
+    fn test_cache_computes_one_histogram_at_a_time() {
+        // Arrange
+        let parameter_history = Arc::new(Mutex::new(ParameterHistory::from(Parameters::default())));
+        let image = WorkspaceImage::new(
+            1,
+            Some(Arc::new(create_image(64, 32, 128))),
+            parameter_history.clone(),
+            Arc::new(Mutex::new(ImageView::default())),
+            String::new());
+        let mut workspace = Workspace::new(image);
+        let mut histogram_cache = HistogramCache::default();
+        let computed_parameters = workspace.parameters_to_display();
+
+        // Act
+        let first = histogram_cache.update(Some(&workspace)).is_some();
+        workspace.set_exposure(20.0);
+        let while_computing = histogram_cache.update(Some(&workspace)).is_some();
+        histogram_cache.set(HistogramResult {
+            photo_id: 1,
+            parameters: computed_parameters,
+            histogram: Histogram::new(&create_image(64, 32, 128), &create_parameters(64, 32))
+        });
+        let after_computing = histogram_cache.update(Some(&workspace)).is_some();
+
+        // Assert
+        assert_eq!((first, while_computing, after_computing), (true, false, true));
+        assert!(histogram_cache.get().is_some());
+    }
+}
@@ -0,0 +1,157 @@
+use super::parameters::{Crop, Parameter, Parameters};
+
+/**
+ * Entry in the undo history of a photo
+ */
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct HistoryStep {
+    pub label: String,
+    pub parameters: Parameters
+}
+
+impl HistoryStep {
+    pub fn new(label: impl Into<String>, parameters: Parameters) -> Self {
+        Self { label: label.into(), parameters }
+    }
+}
+
+/**
+ * Names the first difference found, as edits rarely change more than one thing at a time
+ */
+pub fn describe_change(previous: &Parameters, current: &Parameters) -> String {
+    let changed_parameters: Vec<Parameter> = Parameter::BASE.into_iter()
+        .filter(|parameter| previous.base_parameters.get(*parameter) != current.base_parameters.get(*parameter))
+        .collect();
+    match changed_parameters.as_slice() {
+        [parameter] => return parameter.to_string(),
+        [_, ..] => return "Adjustments".into(),
+        [] => {}
+    }
+
+    if previous.radial_masks != current.radial_masks {
+        return describe_mask_change(previous.radial_masks.len(), current.radial_masks.len(), "mask", || {
+            previous.radial_masks.iter().zip(&current.radial_masks)
+                .position(|(previous_mask, current_mask)| previous_mask != current_mask)
+                .map(|index| &current.radial_masks[index])
+                .filter(|mask| !mask.name.is_empty())
+                .map(|mask| mask.name.clone())
+        });
+    }
+
+    if previous.brush_masks != current.brush_masks {
+        return describe_mask_change(previous.brush_masks.len(), current.brush_masks.len(), "brush mask", || None);
+    }
+
+    if previous.crop != current.crop {
+        if let (Some(previous_crop), Some(current_crop)) = (&previous.crop, &current.crop) {
+            return describe_crop_change(previous_crop, current_crop);
+        }
+        return "Crop".into();
+    }
+
+    if previous.is_favorite != current.is_favorite {
+        return "Favorite".into();
+    }
+
+    "Edit".into()
+}
+
+fn describe_mask_change(previous_count: usize, current_count: usize, kind: &str, changed_name: impl Fn() -> Option<String>) -> String {
+    if current_count > previous_count {
+        format!("Add {kind}")
+    } else if current_count < previous_count {
+        format!("Delete {kind}")
+    } else if let Some(name) = changed_name() {
+        format!("Edit {kind} {name}")
+    } else {
+        format!("Edit {kind}")
+    }
+}
+
+fn describe_crop_change(previous: &Crop, current: &Crop) -> String {
+    let is_lens_correction_changed = (previous.perspective_vertical, previous.perspective_horizontal, previous.distortion)
+        != (current.perspective_vertical, current.perspective_horizontal, current.distortion)
+        || (previous.chromatic_red_cyan, previous.chromatic_blue_yellow, previous.defringe)
+        != (current.chromatic_red_cyan, current.chromatic_blue_yellow, current.defringe);
+
+    if previous.angle_degrees != current.angle_degrees {
+        Parameter::CropAngle.to_string()
+    } else if previous.scale != current.scale {
+        Parameter::CropScale.to_string()
+    } else if is_lens_correction_changed {
+        "Lens correction".into()
+    } else {
+        "Crop".into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::*;
+
+    use crate::workspace::parameters::{BaseParameters, BrushMask, RadialMask};
+
+    #[rstest]
+    #[case(BaseParameters { exposure: 1.0, ..BaseParameters::default() }, "Exposure")]
+    #[case(BaseParameters { tint: 1.0, temperature: 1.0, ..BaseParameters::default() }, "Adjustments")]
+    #[case(BaseParameters::default(), "Edit")]
+    fn test_describe_base_parameter_change(#[case] base_parameters: BaseParameters, #[case] expected: &str) {
+        // Arrange
+        let previous = Parameters::default();
+        let current = Parameters { base_parameters, ..Parameters::default() };
+
+        // Act
+        let label = describe_change(&previous, &current);
+
+        // Assert
+        assert_eq!(label, expected);
+    }
+
+    #[rstest]
+    #[case(vec![], vec![RadialMask::default()], "Add mask")]
+    #[case(vec![RadialMask::default()], vec![], "Delete mask")]
+    #[case(vec![RadialMask::default()], vec![RadialMask { name: "Sky".into(), ..RadialMask::default() }], "Edit mask Sky")]
+    #[case(vec![RadialMask::default()], vec![RadialMask { feather: 1.0, ..RadialMask::default() }], "Edit mask")]
+    fn test_describe_mask_change(#[case] previous_masks: Vec<RadialMask>, #[case] current_masks: Vec<RadialMask>, #[case] expected: &str) {
+        // Arrange
+        let previous = Parameters { radial_masks: previous_masks, ..Parameters::default() };
+        let current = Parameters { radial_masks: current_masks, ..Parameters::default() };
+
+        // Act
+        let label = describe_change(&previous, &current);
+
+        // Assert
+        assert_eq!(label, expected);
+    }
+
+    #[rstest]
+    fn test_describe_brush_mask_change() {
+        // Arrange
+        let previous = Parameters::default();
+        let current = Parameters { brush_masks: vec![BrushMask::default()], ..Parameters::default() };
+
+        // Act
+        let label = describe_change(&previous, &current);
+
+        // Assert
+        assert_eq!(label, "Add brush mask");
+    }
+
+    #[rstest]
+    #[case(Crop { angle_degrees: 2.0, ..Crop::default() }, "Crop angle")]
+    #[case(Crop { scale: 0.5, ..Crop::default() }, "Crop scale")]
+    #[case(Crop { defringe: 0.5, ..Crop::default() }, "Lens correction")]
+    #[case(Crop { center_x: 10, ..Crop::default() }, "Crop")]
+    fn test_describe_crop_change(#[case] crop: Crop, #[case] expected: &str) {
+        // Arrange
+        let previous = Parameters { crop: Some(Crop::default()), ..Parameters::default() };
+        let current = Parameters { crop: Some(crop), ..Parameters::default() };
+
+        // Act
+        let label = describe_change(&previous, &current);
+
+        // Assert
+        assert_eq!(label, expected);
+    }
+}
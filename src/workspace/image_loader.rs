@@ -1,7 +1,9 @@
 use std::path::PathBuf;
 
+use image::ImageDecoder;
 use rayon::prelude::*;
 
+use crate::color_profile::{wide_gamut_to_srgb, IccProfile};
 use crate::types::{RawImage, RgbImage, RgbPixel};
 
 #[derive(Clone, Debug)]
@@ -12,7 +14,12 @@ pub struct ImageLoadResult {
 }
 
 pub async fn load_image(photo_id: i32, path: PathBuf) -> ImageLoadResult {
-    let source_image = image::open(path).unwrap().into_rgb32f();
+    let mut decoder = image::ImageReader::open(path).unwrap()
+        .with_guessed_format().unwrap()
+        .into_decoder().unwrap();
+    let icc_profile = decoder.icc_profile().ok().flatten()
+        .and_then(|data| IccProfile::parse(&data));
+    let source_image = image::DynamicImage::from_decoder(decoder).unwrap().into_rgb32f();
     let width: u32 = source_image.width();
     let height: u32 = source_image.height();
     let size = width * height;
@@ -32,12 +39,47 @@ pub async fn load_image(photo_id: i32, path: PathBuf) -> ImageLoadResult {
         height: height as usize,
         pixels: pixels
     };
-    let image = convert_to_raw_image(&rgb_image);
-    let thumbnail = convert_to_raw_image(&resize_to_thumbnail_size(&rgb_image));
-    ImageLoadResult { photo_id, image, thumbnail }
+    // Images without a profile are assumed to be sRGB, which is used as it is
+    match icc_profile.filter(|icc_profile| !icc_profile.is_srgb()) {
+        Some(icc_profile) => {
+            let wide_gamut_image = convert_to_wide_gamut(&rgb_image, &icc_profile);
+            let image = convert_to_raw_image(&wide_gamut_image, true);
+            let thumbnail = convert_to_raw_image(&convert_to_srgb(resize_to_thumbnail_size(&wide_gamut_image)), false);
+            ImageLoadResult { photo_id, image, thumbnail }
+        },
+        None => {
+            let image = convert_to_raw_image(&rgb_image, false);
+            let thumbnail = convert_to_raw_image(&resize_to_thumbnail_size(&rgb_image), false);
+            ImageLoadResult { photo_id, image, thumbnail }
+        }
+    }
+}
+
+// Wide gamut colors are kept until they're mapped into the export color space on export
+fn convert_to_wide_gamut(image: &RgbImage, icc_profile: &IccProfile) -> RgbImage {
+    let converter = icc_profile.to_wide_gamut_converter();
+    let pixels: Vec<RgbPixel> = image.pixels.par_iter()
+        .map(&converter)
+        .collect();
+    RgbImage {
+        width: image.width,
+        height: image.height,
+        pixels
+    }
+}
+
+// Thumbnails are only displayed, so they're stored as sRGB
+fn convert_to_srgb(image: RgbImage) -> RgbImage {
+    let pixels: Vec<RgbPixel> = image.pixels.iter()
+        .map(wide_gamut_to_srgb)
+        .collect();
+    RgbImage {
+        pixels,
+        ..image
+    }
 }
 
-fn convert_to_raw_image(image: &RgbImage) -> RawImage {
+fn convert_to_raw_image(image: &RgbImage, is_wide_gamut: bool) -> RawImage {
     let mut buffer: Vec<u8> = vec![255; image.width * image.height * 4];
 
     buffer.par_iter_mut()
@@ -58,7 +100,8 @@ fn convert_to_raw_image(image: &RgbImage) -> RawImage {
     RawImage {
         width: image.width,
         height: image.height,
-        pixels: buffer
+        pixels: buffer,
+        is_wide_gamut
     }
 }
 
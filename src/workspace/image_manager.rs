@@ -4,7 +4,7 @@ use itertools::Itertools;
 
 use crate::{repository::album_repository::{AlbumPhotoDto, AlbumRepository}, types::RawImage};
 
-use super::{album_image::AlbumImage, parameters::{Crop, CropPreset, ParameterHistory, Parameters}, workspace::{ImageView, WorkspaceImage}};
+use super::{album_image::AlbumImage, crop_constraint::constrain_crop, history_step::HistoryStep, parameters::{Crop, Parameter, ParameterHistory, Parameters}, workspace::{ImageView, WorkspaceImage}};
 
 // TODO: Adapt these values
 const CACHE_SIZE: usize = 20;
@@ -87,11 +87,7 @@ impl ImageManager {
             source_image.image = Some(Arc::new(image));
             source_image.thumbnail = Some(Arc::new(thumbnail));
             source_image.parameter_history.lock().unwrap()
-                .update(|parameters| {
-                    if parameters.crop.is_none() {
-                        parameters.crop = Some(Self::create_default_crop(image_width, image_height));
-                    }
-                });
+                .update_all(|parameters| Self::place_crop(parameters, image_width, image_height));
         }
     }
 
@@ -143,9 +139,30 @@ impl ImageManager {
 
     pub fn save(&self) {
         for (photo_id, image) in &self.source_images {
-            let parameters = image.parameter_history.lock().unwrap().current();
+            let mut parameter_history = image.parameter_history.lock().unwrap();
+            let parameters = parameter_history.current();
             let parameters_str: String = serde_json::to_string(&parameters).ok().unwrap_or("{}".into());
             self.repository.save_photo_parameters(*photo_id, parameters_str).ok();
+
+            // Unchanged histories are skipped, as they can be large
+            if parameter_history.take_unsaved_changes() {
+                if let Ok(steps) = serde_json::to_string(parameter_history.get_steps()) {
+                    self.repository.save_photo_history(*photo_id, steps, parameter_history.get_step_index()).ok();
+                }
+            }
+        }
+    }
+
+    /**
+     * Nudges the parameter by the same amount on every photo, so their individual values are kept. Values stop at the
+     * ends of the slider.
+     */
+    pub fn adjust_parameter(&self, photo_ids: &[i32], parameter: Parameter, delta: f32) {
+        for photo_id in photo_ids {
+            if let Some(source_image) = self.source_images.get(photo_id) {
+                source_image.parameter_history.lock().unwrap()
+                    .update_f32(parameter, |value| *value = parameter.clamp(*value + delta));
+            }
         }
     }
 
@@ -167,8 +184,7 @@ impl ImageManager {
         let image = None;
         let thumbnail = album_photo.thumbnail.as_ref()
             .map(|thumbnail| Arc::new(thumbnail.clone()));
-        let paramters_raw = Self::parse_parameters(&album_photo.parameters);
-        let parameter_history = Arc::new(Mutex::new(paramters_raw.into()));
+        let parameter_history = Arc::new(Mutex::new(Self::parse_parameter_history(album_photo)));
         let image_view = Arc::new(Mutex::new(ImageView::default()));
         let pending_load = false;
 
@@ -183,20 +199,34 @@ impl ImageManager {
         }
     }
 
+    /**
+     * Photos saved before the history was kept start a new history from their parameters
+     */
+    fn parse_parameter_history(album_photo: &AlbumPhotoDto) -> ParameterHistory {
+        album_photo.history.as_ref()
+            .and_then(|(steps, step_index)| {
+                let steps: Vec<HistoryStep> = serde_json::from_str(steps).ok()?;
+                ParameterHistory::from_steps(steps, *step_index)
+            })
+            .unwrap_or_else(|| Self::parse_parameters(&album_photo.parameters).into())
+    }
+
     fn parse_parameters(parameters: &String) -> Parameters {
         serde_json::from_str(&parameters).ok().unwrap_or(Parameters::default())
     }
 
-    fn create_default_crop(image_width: usize, image_height: usize) -> Crop {
-        Crop {
-            center_x: (image_width as i32) / 2,
-            center_y: (image_height as i32) / 2,
-            source_image_width: image_width,
-            source_image_height: image_height,
-            scale: 0.0,
-            angle_degrees: 0.0,
-            rotation: 0,
-            preset: CropPreset::Original
+    /**
+     * Photos that haven't been loaded have no crop, or one pasted from another photo without its size and center.
+     * Either way the crop is centered on the whole image, keeping any edits in it.
+     */
+    fn place_crop(parameters: &mut Parameters, image_width: usize, image_height: usize) {
+        let crop = parameters.crop.get_or_insert_with(Crop::default);
+        if crop.source_image_width == 0 || crop.source_image_height == 0 {
+            crop.center_x = (image_width as i32) / 2;
+            crop.center_y = (image_height as i32) / 2;
+            crop.source_image_width = image_width;
+            crop.source_image_height = image_height;
+            constrain_crop(crop);
         }
     }
 
@@ -231,4 +261,76 @@ impl ImageManager {
             .take(cache_size)
             .collect()
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::*;
+
+    use crate::workspace::{parameter_group::ParameterGroup, parameters::BaseParameters, workspace::Workspace};
+
+    fn create_source_image(exposure: f32) -> SourceImage {
+        let parameters = Parameters {
+            base_parameters: BaseParameters { exposure, ..BaseParameters::default() },
+            ..Parameters::default()
+        };
+        SourceImage {
+            path: PathBuf::new(),
+            image: None,
+            thumbnail: None,
+            parameter_history: Arc::new(Mutex::new(ParameterHistory::from(parameters))),
+            image_view: Arc::new(Mutex::new(ImageView::default())),
+            pending_load: false
+        }
+    }
+
+    #[rstest]
+    fn test_adjust_parameter_keeps_offsets_within_range() {
+        // Arrange
+        let connection = rusqlite::Connection::open_in_memory().unwrap();
+        let repository = Arc::new(AlbumRepository::new(Arc::new(Mutex::new(connection))));
+        let source_images = BTreeMap::from([(1, create_source_image(-50.0)), (2, create_source_image(10.0)), (3, create_source_image(90.0))]);
+        let image_manager = ImageManager::new(repository, source_images);
+
+        // Act
+        image_manager.adjust_parameter(&[1, 2], Parameter::Exposure, 20.0);
+        image_manager.adjust_parameter(&[2, 3], Parameter::Exposure, 20.0);
+
+        // Assert
+        let actual: Vec<f32> = image_manager.source_images.values()
+            .map(|source_image| source_image.parameter_history.lock().unwrap().current().base_parameters.exposure)
+            .collect();
+        assert_eq!(actual, vec![-30.0, 50.0, 100.0]);
+    }
+
+    #[rstest]
+    #[case(ParameterGroup::Crop)]
+    #[case(ParameterGroup::LensCorrection)]
+    fn test_sync_onto_photo_without_crop_uses_its_image_size(#[case] group: ParameterGroup) {
+        // Arrange
+        let source = Parameters {
+            crop: Some(Crop {
+                center_x: 300,
+                center_y: 200,
+                source_image_width: 600,
+                source_image_height: 400,
+                angle_degrees: 1.0,
+                perspective_vertical: 10.0,
+                ..Crop::default()
+            }),
+            ..Parameters::default()
+        };
+        let parameter_history = Arc::new(Mutex::new(ParameterHistory::from(Parameters::default())));
+        let image = WorkspaceImage::new(1, None, parameter_history.clone(), Arc::new(Mutex::new(ImageView::default())), String::new());
+        Workspace::new(image).sync_parameters(&source, &[group]);
+
+        // Act
+        parameter_history.lock().unwrap().update_all(|parameters| ImageManager::place_crop(parameters, 1000, 500));
+
+        // Assert
+        let crop = parameter_history.lock().unwrap().current().crop.clone().unwrap();
+        assert_eq!((crop.center_x, crop.center_y, crop.source_image_width, crop.source_image_height), (500, 250, 1000, 500));
+        assert_eq!(crop.angle_degrees == 1.0, group == ParameterGroup::Crop);
+        assert_eq!(crop.perspective_vertical == 10.0, group == ParameterGroup::LensCorrection);
+    }
+}
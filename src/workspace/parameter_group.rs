@@ -0,0 +1,249 @@
+use super::parameters::{Crop, Parameters};
+
+/**
+ * Parts of the parameters that can be copied between photos on their own
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+pub enum ParameterGroup {
+    Exposure,
+    Tones,
+    Color,
+    Masks,
+    BrushMasks,
+    Crop,
+    LensCorrection,
+    Favorite
+}
+
+impl ParameterGroup {
+    pub const ALL: [ParameterGroup; 8] = [
+        ParameterGroup::Exposure,
+        ParameterGroup::Tones,
+        ParameterGroup::Color,
+        ParameterGroup::Masks,
+        ParameterGroup::BrushMasks,
+        ParameterGroup::Crop,
+        ParameterGroup::LensCorrection,
+        ParameterGroup::Favorite
+    ];
+
+    /**
+     * The groups in the base parameters, which is what pasting used to copy
+     */
+    pub const BASE: [ParameterGroup; 3] = [
+        ParameterGroup::Exposure,
+        ParameterGroup::Tones,
+        ParameterGroup::Color
+    ];
+}
+
+impl std::fmt::Display for ParameterGroup {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParameterGroup::Exposure => write!(f, "Exposure"),
+            ParameterGroup::Tones => write!(f, "Tones"),
+            ParameterGroup::Color => write!(f, "Color"),
+            ParameterGroup::Masks => write!(f, "Masks"),
+            ParameterGroup::BrushMasks => write!(f, "Brush masks"),
+            ParameterGroup::Crop => write!(f, "Crop"),
+            ParameterGroup::LensCorrection => write!(f, "Lens correction"),
+            ParameterGroup::Favorite => write!(f, "Favorite")
+        }
+    }
+}
+
+/**
+ * Copies the groups from the source to the target and leaves everything else in the target as it was. The crop
+ * position is kept, as it depends on the size of the target image.
+ */
+pub fn copy_groups(groups: &[ParameterGroup], source: &Parameters, target: &mut Parameters) {
+    for group in groups {
+        match group {
+            ParameterGroup::Exposure => {
+                let from = &source.base_parameters;
+                let to = &mut target.base_parameters;
+                to.exposure = from.exposure;
+                to.contrast = from.contrast;
+            },
+            ParameterGroup::Tones => {
+                let from = &source.base_parameters;
+                let to = &mut target.base_parameters;
+                to.shadows = from.shadows;
+                to.midtones = from.midtones;
+                to.highlights = from.highlights;
+            },
+            ParameterGroup::Color => {
+                let from = &source.base_parameters;
+                let to = &mut target.base_parameters;
+                to.tint = from.tint;
+                to.temperature = from.temperature;
+                to.saturation = from.saturation;
+            },
+            ParameterGroup::Masks => {
+                target.radial_masks = source.radial_masks.clone();
+            },
+            ParameterGroup::BrushMasks => {
+                target.brush_masks = source.brush_masks.clone();
+            },
+            ParameterGroup::Crop => {
+                update_crop(source, target, |from, to| {
+                    to.scale = from.scale;
+                    to.angle_degrees = from.angle_degrees;
+                    to.preset = from.preset;
+                    to.rotation = from.rotation;
+                    to.flip_horizontal = from.flip_horizontal;
+                    to.flip_vertical = from.flip_vertical;
+                    to.free_size = from.free_size;
+                });
+            },
+            ParameterGroup::LensCorrection => {
+                update_crop(source, target, |from, to| {
+                    to.perspective_vertical = from.perspective_vertical;
+                    to.perspective_horizontal = from.perspective_horizontal;
+                    to.distortion = from.distortion;
+                    to.chromatic_red_cyan = from.chromatic_red_cyan;
+                    to.chromatic_blue_yellow = from.chromatic_blue_yellow;
+                    to.defringe = from.defringe;
+                });
+            },
+            ParameterGroup::Favorite => {
+                target.is_favorite = source.is_favorite;
+            }
+        }
+    }
+}
+
+/**
+ * Parameters without a crop haven't been opened yet. They get a default crop with only the group's fields, which is
+ * placed on the image once it's loaded, as the size and center of the source crop belong to another image.
+ */
+fn update_crop(source: &Parameters, target: &mut Parameters, update: impl Fn(&Crop, &mut Crop)) {
+    if let Some(from) = &source.crop {
+        update(from, target.crop.get_or_insert_with(Crop::default));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::*;
+
+    use crate::workspace::parameters::{BaseParameters, RadialMask};
+
+    fn create_parameters(value: f32) -> Parameters {
+        Parameters {
+            base_parameters: BaseParameters {
+                exposure: value,
+                contrast: value,
+                tint: value,
+                temperature: value,
+                saturation: value,
+                shadows: value,
+                midtones: value,
+                highlights: value
+            },
+            radial_masks: vec![RadialMask { brightness: value, ..RadialMask::default() }],
+            crop: Some(Crop {
+                center_x: value as i32,
+                angle_degrees: value,
+                distortion: value,
+                ..Crop::default()
+            }),
+            is_favorite: value > 1.0,
+            ..Parameters::default()
+        }
+    }
+
+    #[rstest]
+    fn test_copy_groups_tones() {
+        // Arrange
+        let source = create_parameters(1.0);
+        let mut target = create_parameters(2.0);
+
+        // Act
+        copy_groups(&[ParameterGroup::Tones], &source, &mut target);
+
+        // Assert
+        assert_eq!(target.base_parameters.highlights, 1.0);
+        assert_eq!(target.base_parameters.exposure, 2.0);
+        assert_eq!(target.base_parameters.temperature, 2.0);
+        assert_eq!(target.radial_masks, create_parameters(2.0).radial_masks);
+    }
+
+    #[rstest]
+    fn test_copy_groups_crop_keeps_position() {
+        // Arrange
+        let source = create_parameters(1.0);
+        let mut target = create_parameters(2.0);
+
+        // Act
+        copy_groups(&[ParameterGroup::Crop], &source, &mut target);
+
+        // Assert
+        let crop = target.crop.unwrap();
+        assert_eq!((crop.center_x, crop.angle_degrees, crop.distortion), (2, 1.0, 2.0));
+    }
+
+    #[rstest]
+    #[case(ParameterGroup::Crop)]
+    #[case(ParameterGroup::LensCorrection)]
+    fn test_copy_crop_groups_without_target_crop(#[case] group: ParameterGroup) {
+        // Arrange
+        let source = Parameters {
+            crop: Some(Crop {
+                center_x: 300,
+                center_y: 200,
+                source_image_width: 600,
+                source_image_height: 400,
+                angle_degrees: 1.0,
+                distortion: 1.0,
+                ..Crop::default()
+            }),
+            ..Parameters::default()
+        };
+        let mut target = Parameters::default();
+
+        // Act
+        copy_groups(&[group], &source, &mut target);
+
+        // Assert
+        let crop = target.crop.unwrap();
+        assert_eq!((crop.center_x, crop.center_y, crop.source_image_width, crop.source_image_height), (0, 0, 0, 0));
+        assert_eq!(crop.angle_degrees == 1.0, group == ParameterGroup::Crop);
+        assert_eq!(crop.distortion == 1.0, group == ParameterGroup::LensCorrection);
+    }
+
+    #[rstest]
+    fn test_copy_base_groups() {
+        // Arrange
+        let source = create_parameters(1.0);
+        let mut target = create_parameters(2.0);
+
+        // Act
+        copy_groups(&ParameterGroup::BASE, &source, &mut target);
+
+        // Assert
+        let expected = Parameters {
+            base_parameters: source.base_parameters.clone(),
+            ..create_parameters(2.0)
+        };
+        assert_eq!(target, expected);
+    }
+
+    #[rstest]
+    fn test_copy_all_groups() {
+        // Arrange
+        let source = create_parameters(1.0);
+        let mut target = create_parameters(2.0);
+
+        // Act
+        copy_groups(&ParameterGroup::ALL, &source, &mut target);
+
+        // Assert
+        let expected = Parameters {
+            crop: Some(Crop { center_x: 2, ..source.crop.clone().unwrap() }),
+            ..source
+        };
+        assert_eq!(target, expected);
+    }
+}
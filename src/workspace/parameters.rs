@@ -1,8 +1,10 @@
-use std::time::SystemTime;
+use std::{ops::RangeInclusive, time::SystemTime};
 
 use serde;
 
-#[derive(Clone, Copy)]
+use super::history_step::{self, HistoryStep};
+
+#[derive(Debug, Clone, Copy)]
 pub enum Parameter {
     Exposure,
     Contrast,
@@ -16,6 +18,51 @@ pub enum Parameter {
     CropScale
 }
 
+impl std::fmt::Display for Parameter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Parameter::Exposure => write!(f, "Exposure"),
+            Parameter::Contrast => write!(f, "Contrast"),
+            Parameter::Shadows => write!(f, "Shadows"),
+            Parameter::Midtones => write!(f, "Midtones"),
+            Parameter::Highlights => write!(f, "Highlights"),
+            Parameter::Tint => write!(f, "Tint"),
+            Parameter::Temperature => write!(f, "Temperature"),
+            Parameter::Saturation => write!(f, "Saturation"),
+            Parameter::CropAngle => write!(f, "Crop angle"),
+            Parameter::CropScale => write!(f, "Crop scale")
+        }
+    }
+}
+
+impl Parameter {
+    pub const BASE: [Parameter; 8] = [
+        Parameter::Exposure,
+        Parameter::Contrast,
+        Parameter::Shadows,
+        Parameter::Midtones,
+        Parameter::Highlights,
+        Parameter::Tint,
+        Parameter::Temperature,
+        Parameter::Saturation
+    ];
+
+    /**
+     * Same as the sliders, the crop scale is in powers of two
+     */
+    pub fn range(&self) -> RangeInclusive<f32> {
+        match self {
+            Parameter::CropAngle => -90.0..=90.0,
+            Parameter::CropScale => -5.0..=0.0,
+            _ => -100.0..=100.0
+        }
+    }
+
+    pub fn clamp(&self, value: f32) -> f32 {
+        value.clamp(*self.range().start(), *self.range().end())
+    }
+}
+
 #[derive(Debug, Default, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
 pub struct BaseParameters {
     pub exposure: f32,
@@ -28,17 +75,37 @@ pub struct BaseParameters {
     pub highlights: f32,
 }
 
+impl BaseParameters {
+    pub fn get(&self, parameter: Parameter) -> Option<f32> {
+        match parameter {
+            Parameter::Exposure => Some(self.exposure),
+            Parameter::Contrast => Some(self.contrast),
+            Parameter::Shadows => Some(self.shadows),
+            Parameter::Midtones => Some(self.midtones),
+            Parameter::Highlights => Some(self.highlights),
+            Parameter::Tint => Some(self.tint),
+            Parameter::Temperature => Some(self.temperature),
+            Parameter::Saturation => Some(self.saturation),
+            Parameter::CropAngle | Parameter::CropScale => None
+        }
+    }
+}
+
 #[derive(Debug, Default, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
 pub struct Parameters {
     #[serde(default)]
     pub base_parameters: BaseParameters,
     pub radial_masks: Vec<RadialMask>,
+    #[serde(default)]
+    pub brush_masks: Vec<BrushMask>,
     pub crop: Option<Crop>,
     pub is_favorite: bool,
 }
 
 #[derive(Debug, Default, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
 pub struct RadialMask {
+    #[serde(default)]
+    pub name: String,
     pub center_x: i32,
     pub center_y: i32,
     pub width: i32,
@@ -46,7 +113,153 @@ pub struct RadialMask {
     pub angle_degrees: f32,
     pub feather: f32,
     pub brightness: f32, // TODO: Rename to exposure
-    pub is_linear: bool
+    #[serde(default)]
+    pub adjustments: MaskAdjustments,
+    pub is_linear: bool,
+    #[serde(default)]
+    pub is_global: bool,
+    #[serde(default)]
+    pub luminance_range: Option<LuminanceRange>,
+    #[serde(default)]
+    pub color_range: Option<ColorRange>,
+    #[serde(default)]
+    pub is_inverted: bool,
+    #[serde(default)]
+    pub is_hidden: bool
+}
+
+/**
+ * Mask adjustments besides `brightness`, which works as the exposure of the mask
+ */
+#[derive(Debug, Default, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct MaskAdjustments {
+    pub contrast: f32,
+    pub tint: f32,
+    pub temperature: f32,
+    pub saturation: f32,
+    pub shadows: f32,
+    pub midtones: f32,
+    pub highlights: f32,
+}
+
+impl MaskAdjustments {
+    fn base_parameters(&self, exposure: f32) -> BaseParameters {
+        BaseParameters {
+            exposure,
+            contrast: self.contrast,
+            tint: self.tint,
+            temperature: self.temperature,
+            saturation: self.saturation,
+            shadows: self.shadows,
+            midtones: self.midtones,
+            highlights: self.highlights
+        }
+    }
+
+    /**
+     * Crop parameters don't apply to masks and are ignored
+     */
+    fn parameter_mut<'a>(&'a mut self, exposure: &'a mut f32, parameter: Parameter) -> Option<&'a mut f32> {
+        match parameter {
+            Parameter::Exposure => Some(exposure),
+            Parameter::Contrast => Some(&mut self.contrast),
+            Parameter::Shadows => Some(&mut self.shadows),
+            Parameter::Midtones => Some(&mut self.midtones),
+            Parameter::Highlights => Some(&mut self.highlights),
+            Parameter::Tint => Some(&mut self.tint),
+            Parameter::Temperature => Some(&mut self.temperature),
+            Parameter::Saturation => Some(&mut self.saturation),
+            Parameter::CropAngle | Parameter::CropScale => None
+        }
+    }
+}
+
+impl RadialMask {
+    pub fn base_parameters(&self) -> BaseParameters {
+        self.adjustments.base_parameters(self.brightness)
+    }
+
+    pub fn parameter_mut(&mut self, parameter: Parameter) -> Option<&mut f32> {
+        self.adjustments.parameter_mut(&mut self.brightness, parameter)
+    }
+}
+
+/**
+ * Selects pixels by their lightness in the source image. Values are in percent of Oklab lightness.
+ */
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct LuminanceRange {
+    pub low: f32,
+    pub high: f32,
+    pub falloff: f32
+}
+
+impl Default for LuminanceRange {
+    fn default() -> Self {
+        Self {
+            low: 0.0,
+            high: 50.0,
+            falloff: 20.0
+        }
+    }
+}
+
+/**
+ * Selects pixels by their Oklab distance to a sampled color in the source image
+ */
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct ColorRange {
+    pub lightness: f32,
+    pub tint: f32,
+    pub temperature: f32,
+    pub tolerance: f32 // [1, 100]
+}
+
+impl Default for ColorRange {
+    fn default() -> Self {
+        Self {
+            lightness: 0.5,
+            tint: 0.0,
+            temperature: 0.0,
+            tolerance: 20.0
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct BrushMask {
+    pub strokes: Vec<BrushStroke>,
+    pub brightness: f32,
+    #[serde(default)]
+    pub adjustments: MaskAdjustments
+}
+
+impl BrushMask {
+    pub fn base_parameters(&self) -> BaseParameters {
+        self.adjustments.base_parameters(self.brightness)
+    }
+
+    pub fn parameter_mut(&mut self, parameter: Parameter) -> Option<&mut f32> {
+        self.adjustments.parameter_mut(&mut self.brightness, parameter)
+    }
+}
+
+/**
+ * Positions and radius are relative to the longest side of the image, so strokes are independent of resolution
+ */
+#[derive(Debug, Default, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct BrushStroke {
+    pub points: Vec<BrushPoint>,
+    pub radius: f32,
+    pub feather: f32, // [0, 1]
+    pub flow: f32, // [0, 1]
+    pub is_erase: bool
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct BrushPoint {
+    pub x: f32,
+    pub y: f32
 }
 
 #[derive(Debug, Default, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
@@ -59,18 +272,63 @@ pub struct Crop {
     pub angle_degrees: f32,
     pub preset: CropPreset,
     pub rotation: i32,
+    #[serde(default)]
+    pub perspective_vertical: f32,
+    #[serde(default)]
+    pub perspective_horizontal: f32,
+    #[serde(default)]
+    pub distortion: f32,
+    #[serde(default)]
+    pub chromatic_red_cyan: f32,
+    #[serde(default)]
+    pub chromatic_blue_yellow: f32,
+    #[serde(default)]
+    pub defringe: f32,
+    #[serde(default)]
+    pub flip_horizontal: bool,
+    #[serde(default)]
+    pub flip_vertical: bool,
+    // Unscaled width and height of a free crop, which don't follow any aspect ratio
+    #[serde(default)]
+    pub free_size: Option<(i32, i32)>,
 }
 
 impl Crop {
     pub fn get_full_angle(&self) -> f32 {
         self.angle_degrees + (self.rotation as f32) * 90.0
     }
+
+    /**
+     * Size of the crop before scaling, in image pixels
+     */
+    pub fn unscaled_size(&self) -> (f32, f32) {
+        let original = || {
+            if self.rotation % 2 == 0 {
+                (self.source_image_width as f32, self.source_image_height as f32)
+            } else {
+                (self.source_image_height as f32, self.source_image_width as f32)
+            }
+        };
+        match self.preset {
+            CropPreset::Original => original(),
+            CropPreset::Free => self.free_size
+                .map_or_else(original, |(width, height)| (width as f32, height as f32)),
+            CropPreset::Ratio(width, height) | CropPreset::Pixels(width, height) => {
+                let crop_width = self.source_image_width as f32;
+                let crop_height = crop_width * (height as f32) / (width as f32);
+                (crop_width, crop_height)
+            }
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
 pub enum CropPreset {
     Original,
-    Ratio(i32, i32)
+    Free,
+    Ratio(i32, i32),
+    // Exported at exactly this width and height
+    Pixels(i32, i32)
 }
 
 impl Default for CropPreset {
@@ -83,7 +341,9 @@ impl std::fmt::Display for CropPreset {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Self::Original => write!(f, "Original"),
-            Self::Ratio(width, height) => write!(f, "{}:{}", width, height)
+            Self::Free => write!(f, "Free"),
+            Self::Ratio(width, height) => write!(f, "{}:{}", width, height),
+            Self::Pixels(width, height) => write!(f, "{}×{} px", width, height)
         }
     }
 }
@@ -92,22 +352,36 @@ impl CropPreset {
     pub fn rotate(&self) -> CropPreset {
         match self {
             Self::Original => Self::Original,
-            Self::Ratio(width, height) => Self::Ratio(*height, *width)
+            Self::Free => Self::Free,
+            Self::Ratio(width, height) => Self::Ratio(*height, *width),
+            Self::Pixels(width, height) => Self::Pixels(*height, *width)
+        }
+    }
+
+    pub fn export_size(&self) -> Option<(u32, u32)> {
+        match self {
+            Self::Pixels(width, height) => Some((*width as u32, *height as u32)),
+            _ => None
         }
     }
 }
 
+// Oldest steps are dropped beyond this, as the history is saved with the album
+const MAX_HISTORY_STEPS: usize = 100;
+
 pub struct ParameterHistory {
     parameters: Parameters,
-    parameter_history: Vec<Parameters>,
+    parameter_history: Vec<HistoryStep>,
     parameter_history_index: usize,
     last_updated: SystemTime,
-    last_updated_parameter: Option<Parameter>
+    last_updated_parameter: Option<Parameter>,
+    next_label: Option<String>,
+    has_unsaved_changes: bool
 }
 
 impl From<Parameters> for ParameterHistory {
     fn from(parameters: Parameters) -> Self {
-        let parameter_history = vec![parameters.clone()];
+        let parameter_history = vec![HistoryStep::new("Open", parameters.clone())];
         let parameter_history_index = 0;
         let last_updated = SystemTime::now();
         let last_updated_parameter = None;
@@ -117,14 +391,39 @@ impl From<Parameters> for ParameterHistory {
             parameter_history,
             parameter_history_index,
             last_updated,
-            last_updated_parameter
+            last_updated_parameter,
+            next_label: None,
+            has_unsaved_changes: false
         }
     }
 }
 
 impl ParameterHistory {
+    /**
+     * Restores a saved history, the index is the current step
+     */
+    pub fn from_steps(steps: Vec<HistoryStep>, step_index: usize) -> Option<Self> {
+        let parameters = steps.get(step_index)?.parameters.clone();
+        Some(Self {
+            parameters,
+            parameter_history: steps,
+            parameter_history_index: step_index,
+            last_updated: SystemTime::UNIX_EPOCH,
+            last_updated_parameter: None,
+            next_label: None,
+            has_unsaved_changes: false
+        })
+    }
 
     pub fn update_f32<F>(&mut self, parameter: Parameter, function: F) where F: FnOnce(&mut f32) {
+        self.update_f32_and(parameter, function, |_| {});
+    }
+
+    /**
+     * Follow-up changes, like constraining the crop, are part of the same history step
+     */
+    pub fn update_f32_and<F, G>(&mut self, parameter: Parameter, function: F, follow_up: G)
+            where F: FnOnce(&mut f32), G: FnOnce(&mut Parameters) {
         let maybe_value = match parameter {
             Parameter::Exposure => Some(&mut self.parameters.base_parameters.exposure),
             Parameter::Contrast => Some(&mut self.parameters.base_parameters.contrast),
@@ -140,14 +439,15 @@ impl ParameterHistory {
 
         if let Some(value) = maybe_value {
             function(value);
+            follow_up(&mut self.parameters);
             self.last_updated_parameter = Some(parameter);
             self.update_history();
         }
     }
 
-    pub fn update_last_f32<F>(&mut self, function: F) where F: FnOnce(&mut f32) {
+    pub fn update_last_f32<F, G>(&mut self, function: F, follow_up: G) where F: FnOnce(&mut f32), G: FnOnce(&mut Parameters) {
         if let Some(last_updated_parameter) = self.last_updated_parameter {
-            self.update_f32(last_updated_parameter, function);
+            self.update_f32_and(last_updated_parameter, function, follow_up);
         }
     }
 
@@ -157,24 +457,66 @@ impl ParameterHistory {
         self.update_history();
     }
 
+    /**
+     * Always starts a new history entry, so the change is undone on its own instead of merged with recent edits
+     */
+    pub fn update_step<F>(&mut self, label: &str, function: F) where F: FnOnce(&mut Parameters) {
+        self.last_updated = SystemTime::UNIX_EPOCH;
+        self.next_label = Some(label.into());
+        self.update(function);
+        self.next_label = None;
+    }
+
+    /**
+     * Changes every step without adding one, for values that aren't edits such as the crop of a newly loaded image
+     */
+    pub fn update_all<F>(&mut self, function: F) where F: Fn(&mut Parameters) {
+        let previous_parameters = self.parameters.clone();
+        function(&mut self.parameters);
+        for step in &mut self.parameter_history {
+            function(&mut step.parameters);
+        }
+        self.has_unsaved_changes |= self.parameters != previous_parameters;
+    }
+
     pub fn current(&self) -> Parameters {
         self.parameters.clone()
     }
 
+    pub fn get_steps(&self) -> &[HistoryStep] {
+        &self.parameter_history
+    }
+
+    pub fn get_step_index(&self) -> usize {
+        self.parameter_history_index
+    }
+
     pub fn undo(&mut self) {
         if self.parameter_history_index > 0 {
-            self.parameter_history_index -= 1;
-            self.parameters = self.parameter_history[self.parameter_history_index].clone()
+            self.jump_to(self.parameter_history_index - 1);
         }
     }
 
     pub fn redo(&mut self) {
-        if self.parameter_history_index < self.parameter_history.len() - 1 {
-            self.parameter_history_index += 1;
-            self.parameters = self.parameter_history[self.parameter_history_index].clone()
+        self.jump_to(self.parameter_history_index + 1);
+    }
+
+    pub fn jump_to(&mut self, step_index: usize) {
+        if let Some(step) = self.parameter_history.get(step_index) {
+            self.parameter_history_index = step_index;
+            self.parameters = step.parameters.clone();
+            self.last_updated = SystemTime::UNIX_EPOCH;
+            self.has_unsaved_changes = true;
         }
     }
 
+    /**
+     * Whether the history changed since this was last called
+     */
+    pub fn take_unsaved_changes(&mut self) -> bool {
+        std::mem::take(&mut self.has_unsaved_changes)
+    }
+
     fn needs_new(&self) -> bool {
         if let Ok(elapsed) = self.last_updated.elapsed() {
             elapsed.as_secs() >= 1
@@ -184,22 +526,135 @@ impl ParameterHistory {
     }
 
     fn has_changed(&self) -> bool {
-        !self.parameter_history[self.parameter_history_index].eq(&self.parameters)
+        !self.parameter_history[self.parameter_history_index].parameters.eq(&self.parameters)
     }
 
+    /**
+     * Continuous edits of the same thing, like dragging a slider, are merged into one step
+     */
     fn update_history(&mut self) {
         if self.has_changed() {
-            if self.needs_new() {
-                while self.parameter_history_index + 1 < self.parameter_history.len() {
-                    self.parameter_history.pop();
-                }
+            let current_step = &self.parameter_history[self.parameter_history_index];
+            let label = self.next_label.clone()
+                .unwrap_or_else(|| history_step::describe_change(&current_step.parameters, &self.parameters));
+            let is_same_edit = self.parameter_history_index > 0 && current_step.label == label;
 
-                self.parameter_history.push(self.parameters.clone());
-                self.parameter_history_index += 1;
-                self.last_updated = SystemTime::now();
+            if self.needs_new() || !is_same_edit {
+                self.parameter_history.truncate(self.parameter_history_index + 1);
+                self.parameter_history.push(HistoryStep::new(label, self.parameters.clone()));
+                if self.parameter_history.len() > MAX_HISTORY_STEPS {
+                    self.parameter_history.remove(0);
+                }
+                self.parameter_history_index = self.parameter_history.len() - 1;
             } else {
-                self.parameter_history[self.parameter_history_index] = self.parameters.clone();
+                self.parameter_history[self.parameter_history_index].parameters = self.parameters.clone();
             }
+            self.last_updated = SystemTime::now();
+            self.has_unsaved_changes = true;
+        }
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::*;
+
+    #[rstest]
+    #[case(Parameter::Exposure, Some(1.0))]
+    #[case(Parameter::Highlights, Some(5.0))]
+    #[case(Parameter::Saturation, Some(8.0))]
+    #[case(Parameter::CropAngle, None)]
+    fn test_base_parameters_get(#[case] parameter: Parameter, #[case] expected: Option<f32>) {
+        // Arrange
+        let base_parameters = BaseParameters {
+            exposure: 1.0,
+            contrast: 2.0,
+            tint: 3.0,
+            temperature: 4.0,
+            saturation: 8.0,
+            shadows: 6.0,
+            midtones: 7.0,
+            highlights: 5.0
+        };
+
+        // Act
+        let actual = base_parameters.get(parameter);
+
+        // Assert
+        assert_eq!(actual, expected);
+    }
+
+    #[rstest]
+    #[case(Parameter::Exposure, 120.0, 100.0)]
+    #[case(Parameter::Tint, -150.0, -100.0)]
+    #[case(Parameter::Contrast, 40.0, 40.0)]
+    #[case(Parameter::CropScale, 0.5, 0.0)]
+    fn test_parameter_clamp(#[case] parameter: Parameter, #[case] value: f32, #[case] expected: f32) {
+        // Act
+        let actual = parameter.clamp(value);
+
+        // Assert
+        assert_eq!(actual, expected);
+    }
+
+    fn labels(parameter_history: &ParameterHistory) -> Vec<&str> {
+        parameter_history.get_steps().iter()
+            .map(|step| step.label.as_str())
+            .collect()
+    }
+
+    #[rstest]
+    fn test_history_merges_same_edit() {
+        // Arrange
+        let mut parameter_history = ParameterHistory::from(Parameters::default());
+
+        // Act
+        parameter_history.update_f32(Parameter::Exposure, |value| *value = 1.0);
+        parameter_history.update_f32(Parameter::Exposure, |value| *value = 2.0);
+        parameter_history.update_f32(Parameter::Contrast, |value| *value = 1.0);
+        parameter_history.update_step("Paste", |parameters| parameters.base_parameters.contrast = 2.0);
+        parameter_history.update_step("Paste", |parameters| parameters.base_parameters.contrast = 3.0);
+
+        // Assert
+        assert_eq!(labels(&parameter_history), vec!["Open", "Exposure", "Contrast", "Paste", "Paste"]);
+        assert_eq!(parameter_history.get_steps()[1].parameters.base_parameters.exposure, 2.0);
+    }
+
+    #[rstest]
+    fn test_history_jump_to_and_edit() {
+        // Arrange
+        let mut parameter_history = ParameterHistory::from(Parameters::default());
+        parameter_history.update_f32(Parameter::Exposure, |value| *value = 1.0);
+        parameter_history.update_f32(Parameter::Contrast, |value| *value = 1.0);
+
+        // Act
+        parameter_history.jump_to(1);
+        parameter_history.update_f32(Parameter::Tint, |value| *value = 1.0);
+
+        // Assert
+        assert_eq!(labels(&parameter_history), vec!["Open", "Exposure", "Tint"]);
+        assert_eq!(parameter_history.current().base_parameters.contrast, 0.0);
+        assert!(parameter_history.take_unsaved_changes());
+        assert!(!parameter_history.take_unsaved_changes());
+    }
+
+    #[rstest]
+    #[case(1, true)]
+    #[case(2, false)]
+    fn test_history_from_steps(#[case] step_index: usize, #[case] is_valid: bool) {
+        // Arrange
+        let steps = vec![
+            HistoryStep::new("Open", Parameters::default()),
+            HistoryStep::new("Favorite", Parameters { is_favorite: true, ..Parameters::default() })
+        ];
+
+        // Act
+        let parameter_history = ParameterHistory::from_steps(steps, step_index);
+
+        // Assert
+        assert_eq!(parameter_history.is_some(), is_valid);
+        if let Some(parameter_history) = parameter_history {
+            assert!(parameter_history.current().is_favorite);
         }
     }
-}
\ No newline at end of file
+}
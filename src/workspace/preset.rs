@@ -0,0 +1,87 @@
+use super::parameter_group::{self, ParameterGroup};
+use super::parameters::Parameters;
+
+/**
+ * Named set of parameters. Only the groups are applied, so the rest of the parameters can be anything.
+ */
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Preset {
+    // Row id in the album, not part of exported files
+    #[serde(skip)]
+    pub id: i32,
+    pub name: String,
+    #[serde(default)]
+    pub folder: String,
+    pub groups: Vec<ParameterGroup>,
+    pub parameters: Parameters
+}
+
+impl Preset {
+    /**
+     * Groups that aren't included are left at their defaults, so exported files only contain what's applied
+     */
+    pub fn new(name: String, folder: String, groups: Vec<ParameterGroup>, parameters: &Parameters) -> Self {
+        let mut preset_parameters = Parameters::default();
+        parameter_group::copy_groups(&groups, parameters, &mut preset_parameters);
+        Self { id: 0, name, folder, groups, parameters: preset_parameters }
+    }
+
+    pub fn apply_to(&self, parameters: &mut Parameters) {
+        parameter_group::copy_groups(&self.groups, &self.parameters, parameters);
+    }
+
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string_pretty(self)
+    }
+
+    pub fn from_json(json: &str) -> serde_json::Result<Self> {
+        serde_json::from_str(json)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::*;
+
+    use crate::workspace::parameters::{BaseParameters, Crop};
+
+    #[rstest]
+    fn test_json_roundtrip() {
+        // Arrange
+        let parameters = Parameters {
+            base_parameters: BaseParameters { exposure: 0.5, tint: 0.2, ..BaseParameters::default() },
+            ..Parameters::default()
+        };
+        let preset = Preset::new("Bright".into(), "Portraits".into(), vec![ParameterGroup::Exposure], &parameters);
+
+        // Act
+        let actual = Preset::from_json(&preset.to_json().unwrap()).unwrap();
+
+        // Assert
+        assert_eq!(actual, preset);
+        assert_eq!(actual.parameters.base_parameters.tint, 0.0);
+    }
+
+    #[rstest]
+    fn test_new_keeps_only_crop_group_fields() {
+        // Arrange
+        let parameters = Parameters {
+            crop: Some(Crop {
+                center_x: 300,
+                source_image_width: 600,
+                perspective_vertical: 10.0,
+                angle_degrees: 2.0,
+                ..Crop::default()
+            }),
+            ..Parameters::default()
+        };
+
+        // Act
+        let preset = Preset::new("Keystone".into(), String::new(), vec![ParameterGroup::LensCorrection], &parameters);
+
+        // Assert
+        let expected = Crop { perspective_vertical: 10.0, ..Crop::default() };
+        assert_eq!(preset.parameters.crop, Some(expected));
+    }
+}
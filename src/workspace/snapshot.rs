@@ -0,0 +1,57 @@
+use super::parameters::Parameters;
+
+/**
+ * Named edit state of a single photo, kept in the album so it outlives the undo history
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct Snapshot {
+    pub id: i32,
+    pub photo_id: i32,
+    pub name: String,
+    pub parameters: Parameters
+}
+
+impl Snapshot {
+    pub fn new(photo_id: i32, name: String, parameters: Parameters) -> Self {
+        Self { id: 0, photo_id, name, parameters }
+    }
+
+    /**
+     * Whether the photo is a favorite isn't part of its edit, so restoring keeps it
+     */
+    pub fn restore_to(&self, parameters: &mut Parameters) {
+        *parameters = Parameters {
+            is_favorite: parameters.is_favorite,
+            ..self.parameters.clone()
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::*;
+
+    use crate::workspace::parameters::BaseParameters;
+
+    #[rstest]
+    #[case(true)]
+    #[case(false)]
+    fn test_restore_keeps_favorite(#[case] is_favorite: bool) {
+        // Arrange
+        let snapshot_parameters = Parameters {
+            base_parameters: BaseParameters { exposure: 1.0, ..BaseParameters::default() },
+            is_favorite: !is_favorite,
+            ..Parameters::default()
+        };
+        let snapshot = Snapshot::new(1, "Print".into(), snapshot_parameters);
+        let mut parameters = Parameters { is_favorite, ..Parameters::default() };
+
+        // Act
+        snapshot.restore_to(&mut parameters);
+
+        // Assert
+        assert_eq!(parameters.base_parameters.exposure, 1.0);
+        assert_eq!(parameters.is_favorite, is_favorite);
+    }
+}
@@ -0,0 +1,254 @@
+use crate::types::RawImage;
+
+// Detection is done on a downscaled copy, long edges survive downscaling fine
+const MAX_DETECTION_SIZE: usize = 400;
+
+// Only lines this close to horizontal or vertical are considered
+const MAX_DETECTION_ANGLE: f32 = 20.0;
+const ANGLE_STEP: f32 = 0.25;
+
+// Sobel gradient magnitude, normalized to a hard black to white edge
+const MIN_EDGE_STRENGTH: f32 = 0.1;
+
+// Dominant line must span this fraction of the shortest side of the downscaled image
+const MIN_LINE_FRACTION: f32 = 0.2;
+
+// Lines shorter than this are most likely clicks rather than drawn lines
+const MIN_LINE_LENGTH: f32 = 4.0;
+
+/**
+ * Line drawn along something that should be level, in image coordinates
+ */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StraightenLine {
+    pub start_x: f32,
+    pub start_y: f32,
+    pub end_x: f32,
+    pub end_y: f32
+}
+
+impl StraightenLine {
+    pub fn new(x: f32, y: f32) -> Self {
+        Self { start_x: x, start_y: y, end_x: x, end_y: y }
+    }
+
+    /**
+     * Crop angle that makes the line horizontal, or vertical when it's closer to vertical
+     */
+    pub fn level_angle(&self) -> Option<f32> {
+        let delta_x = self.end_x - self.start_x;
+        let delta_y = self.end_y - self.start_y;
+        if delta_x.hypot(delta_y) < MIN_LINE_LENGTH {
+            return None;
+        }
+
+        Some(deviation_from_axis(delta_y.atan2(delta_x).to_degrees()))
+    }
+}
+
+fn deviation_from_axis(angle_degrees: f32) -> f32 {
+    angle_degrees - (angle_degrees / 90.0).round() * 90.0
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum LineFamily {
+    Horizontal,
+    Vertical
+}
+
+struct EdgePixel {
+    x: f32,
+    y: f32,
+    family: LineFamily
+}
+
+/**
+ * Finds the most prominent near horizontal or near vertical line with a Hough transform, and returns the crop
+ * angle that levels it. Only angles within MAX_DETECTION_ANGLE are searched, so every edge votes for each angle.
+ */
+pub fn detect_angle(image: &RawImage) -> Option<f32> {
+    let (luminance, width, height) = downscaled_luminance(image);
+    if width < 3 || height < 3 {
+        return None;
+    }
+
+    let edges = edge_pixels(&luminance, width, height);
+    let angle_count = (2.0 * MAX_DETECTION_ANGLE / ANGLE_STEP).round() as usize + 1;
+    let diagonal = (width as f32).hypot(height as f32).ceil() as usize;
+    let distance_count = 2 * diagonal + 1;
+
+    let mut best: Option<(usize, f32)> = None;
+    for angle_index in 0..angle_count {
+        let angle_degrees = -MAX_DETECTION_ANGLE + angle_index as f32 * ANGLE_STEP;
+        let (sin, cos) = angle_degrees.to_radians().sin_cos();
+        let mut horizontal_accumulator = vec![0usize; distance_count];
+        let mut vertical_accumulator = vec![0usize; distance_count];
+
+        for edge in &edges {
+            // Distance of the line through the pixel, for a line at the angle from the axis
+            let (accumulator, distance) = match edge.family {
+                LineFamily::Horizontal => (&mut horizontal_accumulator, -edge.x * sin + edge.y * cos),
+                LineFamily::Vertical => (&mut vertical_accumulator, edge.x * cos + edge.y * sin)
+            };
+            let distance_index = (distance.round() as isize + diagonal as isize) as usize;
+            accumulator[distance_index] += 1;
+        }
+
+        let votes = horizontal_accumulator.iter()
+            .chain(vertical_accumulator.iter())
+            .copied()
+            .max()
+            .unwrap_or(0);
+        if best.is_none_or(|(best_votes, _)| votes > best_votes) {
+            best = Some((votes, angle_degrees));
+        }
+    }
+
+    let min_votes = (width.min(height) as f32 * MIN_LINE_FRACTION) as usize;
+    best.filter(|(votes, _)| *votes >= min_votes.max(1))
+        .map(|(_, angle_degrees)| angle_degrees)
+}
+
+fn downscaled_luminance(image: &RawImage) -> (Vec<f32>, usize, usize) {
+    let step = image.width.max(image.height).div_ceil(MAX_DETECTION_SIZE).max(1);
+    let width = image.width / step;
+    let height = image.height / step;
+
+    let luminance = (0..height)
+        .flat_map(|y| (0..width).map(move |x| (x, y)))
+        .map(|(x, y)| {
+            image.rgb_pixel_at(x * step, y * step)
+                .map_or(0.0, |pixel| 0.2126 * pixel.red + 0.7152 * pixel.green + 0.0722 * pixel.blue)
+        })
+        .collect();
+    (luminance, width, height)
+}
+
+/**
+ * Edge pixels from the Sobel gradient. A mostly vertical gradient belongs to a near horizontal line and vice versa.
+ */
+fn edge_pixels(luminance: &[f32], width: usize, height: usize) -> Vec<EdgePixel> {
+    let at = |x: usize, y: usize| luminance[y * width + x];
+
+    (1..height - 1)
+        .flat_map(|y| (1..width - 1).map(move |x| (x, y)))
+        .filter_map(|(x, y)| {
+            let gradient_x = (at(x + 1, y - 1) + 2.0 * at(x + 1, y) + at(x + 1, y + 1)
+                - at(x - 1, y - 1) - 2.0 * at(x - 1, y) - at(x - 1, y + 1)) / 4.0;
+            let gradient_y = (at(x - 1, y + 1) + 2.0 * at(x, y + 1) + at(x + 1, y + 1)
+                - at(x - 1, y - 1) - 2.0 * at(x, y - 1) - at(x + 1, y - 1)) / 4.0;
+            if gradient_x.hypot(gradient_y) < MIN_EDGE_STRENGTH {
+                return None;
+            }
+
+            let family = if gradient_y.abs() >= gradient_x.abs() {
+                LineFamily::Horizontal
+            } else {
+                LineFamily::Vertical
+            };
+            Some(EdgePixel { x: x as f32, y: y as f32, family })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::*;
+
+    use crate::workspace::crop_constraint::crop_corners;
+    use crate::pipeline::viewport::ViewportCrop;
+
+    /**
+     * Dark below a line through the center at the given angle, bright above it
+     */
+    fn create_horizon(width: usize, height: usize, angle_degrees: f32) -> RawImage {
+        let (sin, cos) = angle_degrees.to_radians().sin_cos();
+        let mut pixels = vec![255; width * height * 4];
+        for y in 0..height {
+            for x in 0..width {
+                let relative_x = x as f32 - width as f32 / 2.0;
+                let relative_y = y as f32 - height as f32 / 2.0;
+                if -relative_x * sin + relative_y * cos > 0.0 {
+                    let index = (y * width + x) * 4;
+                    pixels[index..index + 3].copy_from_slice(&[30, 30, 30]);
+                }
+            }
+        }
+        RawImage { width, height, pixels, is_wide_gamut: false }
+    }
+
+    #[rstest]
+    #[case((0.0, 0.0), (100.0, 10.0), 5.711)]
+    #[case((100.0, 10.0), (0.0, 0.0), 5.711)]
+    #[case((0.0, 0.0), (100.0, -10.0), -5.711)]
+    #[case((0.0, 0.0), (10.0, 100.0), -5.711)]
+    #[case((0.0, 0.0), (-10.0, 100.0), 5.711)]
+    fn test_level_angle(#[case] start: (f32, f32), #[case] end: (f32, f32), #[case] expected: f32) {
+        // Arrange
+        let line = StraightenLine { start_x: start.0, start_y: start.1, end_x: end.0, end_y: end.1 };
+
+        // Act
+        let actual = line.level_angle().unwrap();
+
+        // Assert
+        assert!((actual - expected).abs() < 1e-2, "Was: {}", actual);
+    }
+
+    #[rstest]
+    fn test_level_angle_of_click_is_none() {
+        // Arrange
+        let line = StraightenLine::new(50.0, 50.0);
+
+        // Act
+        let actual = line.level_angle();
+
+        // Assert
+        assert_eq!(actual, None);
+    }
+
+    #[rstest]
+    #[case(3.0)]
+    #[case(-7.5)]
+    fn test_level_angle_levels_crop(#[case] angle_degrees: f32) {
+        // Arrange
+        let (sin, cos) = angle_degrees.to_radians().sin_cos();
+        let line = StraightenLine { start_x: 0.0, start_y: 0.0, end_x: 100.0 * cos, end_y: 100.0 * sin };
+
+        // Act
+        let level_angle = line.level_angle().unwrap();
+
+        // Assert
+        let crop = ViewportCrop { center_x: 500, center_y: 500, width: 400, height: 200, angle_degrees: level_angle, scale: 1.0, ..ViewportCrop::default() };
+        let [top_left, top_right, _, _] = crop_corners(&crop);
+        let top_edge_angle = (top_right.1 - top_left.1).atan2(top_right.0 - top_left.0).to_degrees();
+        assert!((top_edge_angle - angle_degrees).abs() < 1e-2, "Was: {}", top_edge_angle);
+    }
+
+    #[rstest]
+    #[case(0.0)]
+    #[case(4.0)]
+    #[case(-2.5)]
+    fn test_detect_angle(#[case] angle_degrees: f32) {
+        // Arrange
+        let image = create_horizon(300, 200, angle_degrees);
+
+        // Act
+        let actual = detect_angle(&image).unwrap();
+
+        // Assert
+        assert!((actual - angle_degrees).abs() <= 0.5, "Was: {}", actual);
+    }
+
+    #[rstest]
+    fn test_detect_angle_without_edges() {
+        // Arrange
+        let image = RawImage { width: 100, height: 100, pixels: vec![128; 100 * 100 * 4], is_wide_gamut: false };
+
+        // Act
+        let actual = detect_angle(&image);
+
+        // Assert
+        assert_eq!(actual, None);
+    }
+}
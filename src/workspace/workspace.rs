@@ -2,14 +2,28 @@ use core::f32;
 use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
 
+use crate::color_profile::ColorSpace;
+use crate::comparison_mode::ComparisonMode;
+use crate::composition_guide::CompositionGuide;
+use crate::pipeline::brush_raster::MAX_BRUSH_MASKS;
+use crate::pipeline::radial_parameter::MAX_RADIAL_MASKS;
 use crate::pipeline::export_image::export_image;
+use crate::pipeline::lens_correction::LensCorrection;
 use crate::pipeline::viewport::{ViewportCrop, ViewportParameters};
-use crate::types::{LabPixel, RawImage};
+use crate::color_profile::srgb_to_linear;
+use crate::types::{rgb_pixel_to_lab, LabPixel, RawImage, RgbPixel};
 use crate::ui::message::MouseState;
 use crate::view_mode::ViewMode;
 use crate::view_mode;
 
-use super::parameters::{CropPreset, Parameter, ParameterHistory, Parameters, RadialMask};
+use super::auto_tone::AutoTone;
+use super::crop_constraint::constrain_crop;
+use super::handles::{self, CropDrag, MaskHandle};
+use super::parameter_group::{self, ParameterGroup};
+use super::preset::Preset;
+use super::snapshot::Snapshot;
+use super::straighten::{self, StraightenLine};
+use super::parameters::{BaseParameters, BrushMask, BrushPoint, BrushStroke, ColorRange, Crop, CropPreset, LuminanceRange, Parameter, ParameterHistory, Parameters, RadialMask};
 
 #[derive(Clone)]
 pub struct WorkspaceImage {
@@ -80,11 +94,62 @@ impl ImageView {
     }
 }
 
+/**
+ * Brush tool state shared by all brush masks. Values are in slider units, strokes store the converted values.
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct BrushSettings {
+    pub size: f32, // [1, 100]
+    pub feather: f32, // [0, 100]
+    pub flow: f32, // [1, 100]
+    pub is_erase: bool
+}
+
+impl Default for BrushSettings {
+    fn default() -> Self {
+        Self {
+            size: 20.0,
+            feather: 50.0,
+            flow: 100.0,
+            is_erase: false
+        }
+    }
+}
+
+impl BrushSettings {
+    /**
+     * Radius relative to the longest side of the image. The largest brush covers half of the image.
+     */
+    pub fn radius(&self) -> f32 {
+        self.size / 100.0 * 0.25
+    }
+
+    fn create_stroke(&self, point: BrushPoint) -> BrushStroke {
+        BrushStroke {
+            points: vec![point],
+            radius: self.radius(),
+            feather: self.feather / 100.0,
+            flow: self.flow / 100.0,
+            is_erase: self.is_erase
+        }
+    }
+}
+
 #[derive(Clone)]
 pub struct Workspace {
     image: WorkspaceImage,
     view_mode: ViewMode,
     parameters_visible: bool,
+    display_clipping: bool,
+    display_mask_overlay: bool,
+    comparison_mode: ComparisonMode,
+    // Shown as the before side of the comparison instead of the unedited image
+    compared_snapshot: Option<Snapshot>,
+    split_position: f32,
+    composition_guide: CompositionGuide,
+    // Bit 0 mirrors the guide horizontally and bit 1 vertically
+    composition_orientation: u32,
+    brush_settings: BrushSettings,
 
     // For view/crop dragging (there's probably a better way to handle this)
     mouse_state: MouseState,
@@ -92,6 +157,16 @@ pub struct Workspace {
     mouse_origin_y: i32,
     offset_origin_x: i32,
     offset_origin_y: i32,
+    is_dragging_split: bool,
+    handle_drag: Option<HandleDrag>,
+    is_straightening: bool,
+    straighten_line: Option<StraightenLine>,
+}
+
+#[derive(Clone)]
+enum HandleDrag {
+    Mask(MaskHandle),
+    Crop(CropDrag)
 }
 
 impl Workspace {
@@ -100,18 +175,34 @@ impl Workspace {
             image,
             view_mode: ViewMode::Normal,
             parameters_visible: true,
+            display_clipping: false,
+            display_mask_overlay: false,
+            comparison_mode: ComparisonMode::Off,
+            compared_snapshot: None,
+            split_position: 0.5,
+            composition_guide: CompositionGuide::default(),
+            composition_orientation: 0,
+            brush_settings: BrushSettings::default(),
             mouse_state: MouseState::Up,
             mouse_origin_x: 0,
             mouse_origin_y: 0,
             offset_origin_x: 0,
             offset_origin_y: 0,
+            is_dragging_split: false,
+            handle_drag: None,
+            is_straightening: false,
+            straighten_line: None,
         }
     }
 
     pub fn update(image: WorkspaceImage, workspace: &Option<Self>) -> Self {
         if let Some(workspace) = workspace {
+            // A compared snapshot belongs to the photo it was taken of
+            let compared_snapshot = workspace.compared_snapshot.clone()
+                .filter(|snapshot| snapshot.photo_id == image.photo_id);
             Self {
                 image,
+                compared_snapshot,
                 ..workspace.clone()
             }
         } else {
@@ -132,6 +223,10 @@ impl Workspace {
     }
 
     pub fn set_mouse_state(&mut self, mouse_state: MouseState) {
+        if matches!(mouse_state, MouseState::Up) {
+            self.is_dragging_split = false;
+            self.handle_drag = None;
+        }
         self.mouse_state = mouse_state
     }
 
@@ -147,6 +242,34 @@ impl Workspace {
         self.parameters_visible
     }
 
+    pub fn get_display_clipping(&self) -> bool {
+        self.display_clipping
+    }
+
+    pub fn get_display_mask_overlay(&self) -> bool {
+        self.display_mask_overlay
+    }
+
+    pub fn get_comparison_mode(&self) -> ComparisonMode {
+        self.comparison_mode
+    }
+
+    pub fn get_split_position(&self) -> f32 {
+        self.split_position
+    }
+
+    pub fn get_composition_guide(&self) -> CompositionGuide {
+        self.composition_guide
+    }
+
+    pub fn get_composition_orientation(&self) -> u32 {
+        self.composition_orientation
+    }
+
+    pub fn is_dragging_split(&self) -> bool {
+        self.is_dragging_split
+    }
+
     pub fn parameters_to_display(&self) -> ViewportParameters {
         let parameters = self.image.parameter_history.lock().unwrap().current().into();
 
@@ -155,6 +278,7 @@ impl Workspace {
         } else {
             ViewportParameters {
                 crop: parameters.crop,
+                lens_correction: parameters.lens_correction,
                 ..ViewportParameters::default()
             }
         }
@@ -194,8 +318,20 @@ impl Workspace {
         }
     }
 
-    pub fn export_image(&self, export_directory: PathBuf) {
-        futures_executor::block_on(export_image(&self, export_directory));
+    pub fn get_brush_mask_index(&self) -> Option<usize> {
+        if let ViewMode::Brush(index) = self.view_mode {
+            Some(index)
+        } else {
+            None
+        }
+    }
+
+    pub fn get_brush_settings(&self) -> BrushSettings {
+        self.brush_settings.clone()
+    }
+
+    pub fn export_image(&self, export_directory: PathBuf, color_space: ColorSpace) {
+        futures_executor::block_on(export_image(&self, export_directory, color_space));
     }
 
     pub fn undo(&mut self) {
@@ -206,37 +342,130 @@ impl Workspace {
         self.image.parameter_history.lock().unwrap().redo()
     }
 
+    pub fn jump_to_history_step(&mut self, step_index: usize) {
+        self.image.parameter_history.lock().unwrap().jump_to(step_index)
+    }
+
+    /**
+     * Labels of the undo history and the index of the current step
+     */
+    pub fn get_history(&self) -> (Vec<String>, usize) {
+        let parameter_history = self.image.parameter_history.lock().unwrap();
+        let labels = parameter_history.get_steps().iter()
+            .map(|step| step.label.clone())
+            .collect();
+        (labels, parameter_history.get_step_index())
+    }
+
     pub fn copy_parameters(&mut self) -> Parameters {
         self.current_parameters()
     }
 
-    pub fn paste_parameters(&mut self, clipboard_parameters: &Parameters) {
-        self.image.parameter_history.lock().unwrap()
-            .update(|parameters| parameters.base_parameters = clipboard_parameters.base_parameters.clone());
+    pub fn paste_parameters(&mut self, clipboard_parameters: &Parameters, groups: &[ParameterGroup]) -> bool {
+        self.copy_groups_from("Paste", clipboard_parameters, groups)
+    }
+
+    pub fn sync_parameters(&mut self, source_parameters: &Parameters, groups: &[ParameterGroup]) -> bool {
+        self.copy_groups_from("Sync", source_parameters, groups)
+    }
+
+    fn copy_groups_from(&mut self, label: &str, source_parameters: &Parameters, groups: &[ParameterGroup]) -> bool {
+        self.update_step_within_mask_limit(label, |parameters| {
+            parameter_group::copy_groups(groups, source_parameters, parameters);
+            constrain_parameters_crop(parameters);
+        })
+    }
+
+    pub fn apply_preset(&mut self, preset: &Preset) -> bool {
+        self.update_step_within_mask_limit(&format!("Preset {}", preset.name), |parameters| {
+            preset.apply_to(parameters);
+            constrain_parameters_crop(parameters);
+        })
+    }
+
+    /**
+     * Makes the change as one step, unless it leaves more masks than can be rendered. Returns whether the change was made.
+     */
+    fn update_step_within_mask_limit<F>(&mut self, label: &str, function: F) -> bool where F: FnOnce(&mut Parameters) {
+        let mut changed_parameters = self.current_parameters();
+        function(&mut changed_parameters);
+        let is_within_limit = changed_parameters.radial_masks.len() <= MAX_RADIAL_MASKS;
+        if is_within_limit {
+            self.image.parameter_history.lock().unwrap()
+                .update_step(label, |parameters| *parameters = changed_parameters);
+        }
+        is_within_limit
     }
 
     pub fn decrease_last_parameter(&mut self) {
         self.image.parameter_history.lock().unwrap()
-            .update_last_f32(|value| *value = *value - 0.1);
+            .update_last_f32(|value| *value = *value - 0.1, constrain_parameters_crop);
     }
 
     pub fn decrease_last_parameter_large(&mut self) {
         self.image.parameter_history.lock().unwrap()
-            .update_last_f32(|value| *value = *value - 1.0);
+            .update_last_f32(|value| *value = *value - 1.0, constrain_parameters_crop);
     }
 
     pub fn increase_last_parameter(&mut self) {
         self.image.parameter_history.lock().unwrap()
-            .update_last_f32(|value| *value = *value + 0.1);
+            .update_last_f32(|value| *value = *value + 0.1, constrain_parameters_crop);
     }
 
     pub fn increase_last_parameter_large(&mut self) {
         self.image.parameter_history.lock().unwrap()
-            .update_last_f32(|value| *value = *value + 1.0);
+            .update_last_f32(|value| *value = *value + 1.0, constrain_parameters_crop);
     }
 
     pub fn toggle_view_mode(&mut self, view_mode: ViewMode) {
         self.view_mode = self.view_mode.toggle_view_mode(view_mode);
+        if !self.is_crop_mode() {
+            self.is_straightening = false;
+            self.straighten_line = None;
+        }
+    }
+
+    pub fn is_straightening(&self) -> bool {
+        self.is_straightening
+    }
+
+    pub fn current_straighten_line(&self) -> Option<StraightenLine> {
+        self.straighten_line
+    }
+
+    /**
+     * Straightening is done in crop mode, so the straightened crop can be seen right away
+     */
+    pub fn toggle_straighten(&mut self) {
+        self.is_straightening = !self.is_straightening;
+        self.straighten_line = None;
+        if self.is_straightening {
+            self.view_mode = ViewMode::Crop;
+        }
+    }
+
+    pub fn start_straighten_line(&mut self, x: i32, y: i32) {
+        self.straighten_line = Some(StraightenLine::new(x as f32, y as f32));
+    }
+
+    pub fn update_straighten_line(&mut self, x: i32, y: i32) {
+        if let Some(straighten_line) = &mut self.straighten_line {
+            straighten_line.end_x = x as f32;
+            straighten_line.end_y = y as f32;
+        }
+    }
+
+    pub fn finish_straighten_line(&mut self) {
+        if let Some(angle_degrees) = self.straighten_line.take().and_then(|line| line.level_angle()) {
+            self.update_constrained_crop(|crop| crop.angle_degrees = angle_degrees);
+            self.is_straightening = false;
+        }
+    }
+
+    pub fn auto_straighten(&mut self) {
+        if let Some(angle_degrees) = self.image.image.as_ref().and_then(|image| straighten::detect_angle(image)) {
+            self.update_constrained_crop(|crop| crop.angle_degrees = angle_degrees);
+        }
     }
 
     pub fn set_exposure(&mut self, exposure: f32) {
@@ -272,11 +501,33 @@ impl Workspace {
     }
 
     pub fn add_mask(&mut self) {
+        self.add_range_mask(RadialMask::default());
+    }
+
+    pub fn add_luminance_mask(&mut self) {
+        self.add_range_mask(RadialMask {
+            is_global: true,
+            luminance_range: Some(LuminanceRange::default()),
+            ..RadialMask::default()
+        });
+    }
+
+    pub fn add_color_mask(&mut self) {
+        self.add_range_mask(RadialMask {
+            is_global: true,
+            color_range: Some(ColorRange::default()),
+            ..RadialMask::default()
+        });
+    }
+
+    fn add_range_mask(&mut self, radial_mask: RadialMask) {
         self.image.parameter_history.lock().unwrap()
             .update(|parameters| {
-                let new_mask_index = parameters.radial_masks.len();
-                parameters.radial_masks.push(RadialMask::default());
-                self.view_mode = ViewMode::Mask(new_mask_index);
+                if parameters.radial_masks.len() < MAX_RADIAL_MASKS {
+                    let new_mask_index = parameters.radial_masks.len();
+                    parameters.radial_masks.push(radial_mask);
+                    self.view_mode = ViewMode::Mask(new_mask_index);
+                }
             });
     }
 
@@ -288,6 +539,55 @@ impl Workspace {
         self.view_mode = ViewMode::Normal;
     }
 
+    pub fn duplicate_mask(&mut self, mask_index: usize) {
+        self.image.parameter_history.lock().unwrap()
+            .update(|parameters| {
+                if parameters.radial_masks.len() < MAX_RADIAL_MASKS {
+                    let duplicate = parameters.radial_masks[mask_index].clone();
+                    parameters.radial_masks.insert(mask_index + 1, duplicate);
+                    self.view_mode = ViewMode::Mask(mask_index + 1);
+                }
+            });
+    }
+
+    /**
+     * Swaps the mask with its neighbour. Masks are applied in order, so this changes how they stack.
+     */
+    pub fn move_mask(&mut self, mask_index: usize, is_up: bool) {
+        let mask_count = self.current_parameters().radial_masks.len();
+        let target_index = if is_up {
+            mask_index.checked_sub(1)
+        } else {
+            Some(mask_index + 1).filter(|index| *index < mask_count)
+        };
+        let Some(target_index) = target_index else {
+            return;
+        };
+
+        self.image.parameter_history.lock().unwrap()
+            .update(|parameters| parameters.radial_masks.swap(mask_index, target_index));
+        self.view_mode = match self.view_mode {
+            ViewMode::Mask(index) if index == mask_index => ViewMode::Mask(target_index),
+            ViewMode::Mask(index) if index == target_index => ViewMode::Mask(mask_index),
+            view_mode => view_mode
+        };
+    }
+
+    pub fn set_mask_name(&mut self, mask_index: usize, name: String) {
+        self.image.parameter_history.lock().unwrap()
+            .update(|parameters| parameters.radial_masks[mask_index].name = name);
+    }
+
+    pub fn set_mask_is_inverted(&mut self, mask_index: usize, is_inverted: bool) {
+        self.image.parameter_history.lock().unwrap()
+            .update(|parameters| parameters.radial_masks[mask_index].is_inverted = is_inverted);
+    }
+
+    pub fn set_mask_is_hidden(&mut self, mask_index: usize, is_hidden: bool) {
+        self.image.parameter_history.lock().unwrap()
+            .update(|parameters| parameters.radial_masks[mask_index].is_hidden = is_hidden);
+    }
+
     pub fn update_mask_position(&mut self, mask_index: usize, x: i32, y: i32) {
         self.image.parameter_history.lock().unwrap()
             .update(|parameters| {
@@ -299,6 +599,55 @@ impl Workspace {
             });
     }
 
+    pub fn is_dragging_handle(&self) -> bool {
+        self.handle_drag.is_some()
+    }
+
+    pub fn try_grab_mask_handle(&mut self, mask_index: usize, x: i32, y: i32) -> bool {
+        let handle_radius = handles::handle_radius(&self.current_view());
+        let handle = self.current_parameters().radial_masks.get(mask_index)
+            .and_then(|radial_mask| handles::find_mask_handle(radial_mask, x as f32, y as f32, handle_radius));
+        self.handle_drag = handle.map(HandleDrag::Mask);
+        self.handle_drag.is_some()
+    }
+
+    pub fn try_grab_crop_handle(&mut self, view_x: f32, view_y: f32) -> bool {
+        let view = self.current_view();
+        let scale = self.current_crop_scale();
+        let free_size = self.current_parameters().crop
+            .filter(|crop| crop.preset == CropPreset::Free)
+            .map(|crop| crop.free_size.unwrap_or_else(|| {
+                let (width, height) = crop.unscaled_size();
+                (width as i32, height as i32)
+            }));
+        self.handle_drag = handles::find_crop_handle(&view, view_x, view_y)
+            .map(|handle| HandleDrag::Crop(CropDrag { handle, view, scale, free_size }));
+        self.handle_drag.is_some()
+    }
+
+    pub fn drag_mask_handle(&mut self, mask_index: usize, x: i32, y: i32) {
+        if let Some(HandleDrag::Mask(handle)) = self.handle_drag {
+            self.image.parameter_history.lock().unwrap()
+                .update(|parameters| {
+                    handles::drag_mask_handle(&mut parameters.radial_masks[mask_index], handle, x as f32, y as f32);
+                });
+        }
+    }
+
+    pub fn drag_crop_handle(&mut self, view_x: f32, view_y: f32) {
+        if let Some(HandleDrag::Crop(crop_drag)) = &self.handle_drag {
+            let crop_resize = handles::resize_crop(crop_drag, view_x, view_y);
+            self.update_constrained_crop(|crop| {
+                crop.center_x = crop_resize.center_x;
+                crop.center_y = crop_resize.center_y;
+                crop.scale = crop_resize.scale;
+                if crop_resize.free_size.is_some() {
+                    crop.free_size = crop_resize.free_size;
+                }
+            });
+        }
+    }
+
     pub fn update_mask_size(&mut self, mask_index: usize, x: i32, y: i32) {
         self.image.parameter_history.lock().unwrap()
             .update(|parameters| {
@@ -318,9 +667,93 @@ impl Workspace {
             .update(|parameters| parameters.radial_masks[mask_index].is_linear = is_linear);
     }
 
-    pub fn set_mask_brightness(&mut self, mask_index: usize, brightness: f32) {
+    pub fn set_mask_is_global(&mut self, mask_index: usize, is_global: bool) {
+        self.image.parameter_history.lock().unwrap()
+            .update(|parameters| parameters.radial_masks[mask_index].is_global = is_global);
+    }
+
+    pub fn set_mask_luminance_range_enabled(&mut self, mask_index: usize, is_enabled: bool) {
         self.image.parameter_history.lock().unwrap()
-            .update(|parameters| parameters.radial_masks[mask_index].brightness = brightness);
+            .update(|parameters| {
+                parameters.radial_masks[mask_index].luminance_range = is_enabled.then(LuminanceRange::default);
+            });
+    }
+
+    pub fn set_mask_luminance_low(&mut self, mask_index: usize, low: f32) {
+        self.update_luminance_range(mask_index, |luminance_range| {
+            luminance_range.low = low;
+            luminance_range.high = luminance_range.high.max(low);
+        });
+    }
+
+    pub fn set_mask_luminance_high(&mut self, mask_index: usize, high: f32) {
+        self.update_luminance_range(mask_index, |luminance_range| {
+            luminance_range.high = high;
+            luminance_range.low = luminance_range.low.min(high);
+        });
+    }
+
+    pub fn set_mask_luminance_falloff(&mut self, mask_index: usize, falloff: f32) {
+        self.update_luminance_range(mask_index, |luminance_range| luminance_range.falloff = falloff);
+    }
+
+    pub fn set_mask_color_range_enabled(&mut self, mask_index: usize, is_enabled: bool) {
+        self.image.parameter_history.lock().unwrap()
+            .update(|parameters| {
+                parameters.radial_masks[mask_index].color_range = is_enabled.then(ColorRange::default);
+            });
+    }
+
+    pub fn set_mask_color_tolerance(&mut self, mask_index: usize, tolerance: f32) {
+        self.update_color_range(mask_index, |color_range| color_range.tolerance = tolerance);
+    }
+
+    /**
+     * Samples the color for the color range of the mask. Colors are compared in linear Oklab, same as the shader.
+     */
+    pub fn pick_mask_color(&mut self, mask_index: usize, x: i32, y: i32) {
+        let lab_pixel: Option<LabPixel> = self.image.image.as_ref()
+            .zip(self.source_position(x, y))
+            .and_then(|(image, (source_x, source_y))| image.rgb_pixel_at(source_x, source_y))
+            .map(|pixel| rgb_pixel_to_lab(RgbPixel {
+                red: srgb_to_linear(pixel.red),
+                green: srgb_to_linear(pixel.green),
+                blue: srgb_to_linear(pixel.blue)
+            }));
+        if let Some(pixel) = lab_pixel {
+            self.update_color_range(mask_index, |color_range| {
+                color_range.lightness = pixel.lightness;
+                color_range.tint = pixel.tint;
+                color_range.temperature = pixel.temperature;
+            });
+        }
+    }
+
+    fn update_luminance_range<F>(&mut self, mask_index: usize, function: F) where F: FnOnce(&mut LuminanceRange) {
+        self.image.parameter_history.lock().unwrap()
+            .update(|parameters| {
+                if let Some(luminance_range) = parameters.radial_masks[mask_index].luminance_range.as_mut() {
+                    function(luminance_range);
+                }
+            });
+    }
+
+    fn update_color_range<F>(&mut self, mask_index: usize, function: F) where F: FnOnce(&mut ColorRange) {
+        self.image.parameter_history.lock().unwrap()
+            .update(|parameters| {
+                if let Some(color_range) = parameters.radial_masks[mask_index].color_range.as_mut() {
+                    function(color_range);
+                }
+            });
+    }
+
+    pub fn set_mask_parameter(&mut self, mask_index: usize, parameter: Parameter, value: f32) {
+        self.image.parameter_history.lock().unwrap()
+            .update(|parameters| {
+                if let Some(field) = parameters.radial_masks[mask_index].parameter_mut(parameter) {
+                    *field = value;
+                }
+            });
     }
 
     pub fn set_mask_angle_degrees(&mut self, mask_index: usize, angle_degrees: f32) {
@@ -339,6 +772,72 @@ impl Workspace {
             });
     }
 
+    pub fn add_brush_mask(&mut self) {
+        self.image.parameter_history.lock().unwrap()
+            .update(|parameters| {
+                if parameters.brush_masks.len() < MAX_BRUSH_MASKS {
+                    let new_mask_index = parameters.brush_masks.len();
+                    parameters.brush_masks.push(BrushMask::default());
+                    self.view_mode = ViewMode::Brush(new_mask_index);
+                }
+            });
+    }
+
+    pub fn delete_brush_mask(&mut self, mask_index: usize) {
+        self.image.parameter_history.lock().unwrap()
+            .update(|parameters| {
+                parameters.brush_masks.remove(mask_index);
+            });
+        self.view_mode = ViewMode::Normal;
+    }
+
+    pub fn set_brush_mask_parameter(&mut self, mask_index: usize, parameter: Parameter, value: f32) {
+        self.image.parameter_history.lock().unwrap()
+            .update(|parameters| {
+                if let Some(field) = parameters.brush_masks[mask_index].parameter_mut(parameter) {
+                    *field = value;
+                }
+            });
+    }
+
+    pub fn start_brush_stroke(&mut self, mask_index: usize, x: i32, y: i32) {
+        if let Some(point) = self.to_brush_point(x, y) {
+            let stroke = self.brush_settings.create_stroke(point);
+            self.image.parameter_history.lock().unwrap()
+                .update(|parameters| parameters.brush_masks[mask_index].strokes.push(stroke));
+        }
+    }
+
+    pub fn continue_brush_stroke(&mut self, mask_index: usize, x: i32, y: i32) {
+        if let Some(point) = self.to_brush_point(x, y) {
+            self.image.parameter_history.lock().unwrap()
+                .update(|parameters| {
+                    let last_stroke = parameters.brush_masks[mask_index].strokes.last_mut();
+                    if let Some(stroke) = last_stroke {
+                        if stroke.points.last() != Some(&point) {
+                            stroke.points.push(point);
+                        }
+                    }
+                });
+        }
+    }
+
+    pub fn set_brush_size(&mut self, size: f32) {
+        self.brush_settings.size = size;
+    }
+
+    pub fn set_brush_feather(&mut self, feather: f32) {
+        self.brush_settings.feather = feather;
+    }
+
+    pub fn set_brush_flow(&mut self, flow: f32) {
+        self.brush_settings.flow = flow;
+    }
+
+    pub fn set_brush_erase(&mut self, is_erase: bool) {
+        self.brush_settings.is_erase = is_erase;
+    }
+
     pub fn set_crop_angle(&mut self, angle_degrees: f32) {
         self.set_parameter_value(Parameter::CropAngle, angle_degrees);
     }
@@ -359,7 +858,9 @@ impl Workspace {
             .update(|parameters| {
                 if let Some(crop) = &mut parameters.crop {
                     crop.preset = crop.preset.rotate();
+                    crop.free_size = crop.free_size.map(|(width, height)| (height, width));
                     crop.rotation = (crop.rotation + 1) % 4;
+                    constrain_crop(crop);
                 }
             });
     }
@@ -369,10 +870,66 @@ impl Workspace {
             .update(|parameters| {
                 if let Some(crop) = &mut parameters.crop {
                     crop.preset = crop.preset.rotate();
+                    crop.free_size = crop.free_size.map(|(width, height)| (height, width));
                     crop.rotation -= 1;
                     if crop.rotation < 0 {
                         crop.rotation = 3;
                     }
+                    constrain_crop(crop);
+                }
+            });
+    }
+
+    pub fn set_perspective_vertical(&mut self, perspective_vertical: f32) {
+        self.update_constrained_crop(|crop| crop.perspective_vertical = perspective_vertical);
+    }
+
+    pub fn set_perspective_horizontal(&mut self, perspective_horizontal: f32) {
+        self.update_constrained_crop(|crop| crop.perspective_horizontal = perspective_horizontal);
+    }
+
+    pub fn set_distortion(&mut self, distortion: f32) {
+        self.update_constrained_crop(|crop| crop.distortion = distortion);
+    }
+
+    pub fn set_chromatic_red_cyan(&mut self, chromatic_red_cyan: f32) {
+        self.update_lens_correction(|crop| crop.chromatic_red_cyan = chromatic_red_cyan);
+    }
+
+    pub fn set_chromatic_blue_yellow(&mut self, chromatic_blue_yellow: f32) {
+        self.update_lens_correction(|crop| crop.chromatic_blue_yellow = chromatic_blue_yellow);
+    }
+
+    pub fn set_defringe(&mut self, defringe: f32) {
+        self.update_lens_correction(|crop| crop.defringe = defringe);
+    }
+
+    /**
+     * Color corrections of the lens don't move anything, so the crop doesn't need to be constrained
+     */
+    fn update_lens_correction(&mut self, update: impl Fn(&mut Crop)) {
+        self.image.parameter_history.lock().unwrap()
+            .update(|parameters| {
+                if let Some(crop) = &mut parameters.crop {
+                    update(crop);
+                }
+            });
+    }
+
+    pub fn crop_flip_horizontal(&mut self) {
+        self.image.parameter_history.lock().unwrap()
+            .update_step("Flip", |parameters| {
+                if let Some(crop) = &mut parameters.crop {
+                    crop.flip_horizontal = !crop.flip_horizontal;
+                }
+            });
+    }
+
+    pub fn crop_flip_vertical(&mut self) {
+        self.image.parameter_history.lock().unwrap()
+            .update_step("Flip", |parameters| {
+                if let Some(crop) = &mut parameters.crop {
+                    crop.flip_vertical = !crop.flip_vertical;
                 }
             });
     }
@@ -381,7 +938,13 @@ impl Workspace {
         self.image.parameter_history.lock().unwrap()
             .update(|parameters| {
                 if let Some(crop) = &mut parameters.crop {
+                    // A free crop starts from the current size, so the crop doesn't jump
+                    if crop_preset == CropPreset::Free && crop.preset != CropPreset::Free {
+                        let (width, height) = crop.unscaled_size();
+                        crop.free_size = Some((width as i32, height as i32));
+                    }
                     crop.preset = crop_preset;
+                    constrain_crop(crop);
                 }
             });
     }
@@ -390,6 +953,86 @@ impl Workspace {
         self.parameters_visible = !self.parameters_visible;
     }
 
+    pub fn toggle_clipping(&mut self) {
+        self.display_clipping = !self.display_clipping;
+    }
+
+    pub fn toggle_mask_overlay(&mut self) {
+        self.display_mask_overlay = !self.display_mask_overlay;
+    }
+
+    pub fn toggle_comparison_mode(&mut self) {
+        self.comparison_mode = self.comparison_mode.next();
+        if self.comparison_mode == ComparisonMode::Off {
+            self.compared_snapshot = None;
+        }
+    }
+
+    pub fn toggle_compared_snapshot(&mut self, snapshot: &Snapshot) {
+        if self.get_compared_snapshot_id() == Some(snapshot.id) {
+            self.compared_snapshot = None;
+        } else {
+            self.compared_snapshot = Some(snapshot.clone());
+            if self.comparison_mode == ComparisonMode::Off {
+                self.comparison_mode = ComparisonMode::SplitVertical;
+            }
+        }
+    }
+
+    pub fn forget_snapshot(&mut self, snapshot_id: i32) {
+        if self.get_compared_snapshot_id() == Some(snapshot_id) {
+            self.compared_snapshot = None;
+        }
+    }
+
+    pub fn get_compared_snapshot_id(&self) -> Option<i32> {
+        self.compared_snapshot.as_ref().map(|snapshot| snapshot.id)
+    }
+
+    /**
+     * Only the global adjustments of the snapshot are compared, its masks and crop aren't
+     */
+    pub fn get_compared_base_parameters(&self) -> Option<BaseParameters> {
+        self.compared_snapshot.as_ref().map(|snapshot| snapshot.parameters.base_parameters.clone())
+    }
+
+    pub fn restore_snapshot(&mut self, snapshot: &Snapshot) -> bool {
+        self.update_step_within_mask_limit(&format!("Snapshot {}", snapshot.name), |parameters| snapshot.restore_to(parameters))
+    }
+
+    pub fn set_composition_guide(&mut self, composition_guide: CompositionGuide) {
+        self.composition_guide = composition_guide;
+    }
+
+    pub fn cycle_composition_guide(&mut self) {
+        self.composition_guide = self.composition_guide.next();
+    }
+
+    pub fn rotate_composition_guide(&mut self) {
+        self.composition_orientation = (self.composition_orientation + 1) % 4;
+    }
+
+    /**
+     * Starts dragging the split line if the position (in view space) is close enough to it
+     */
+    pub fn try_grab_split(&mut self, view_x: f32, view_y: f32) -> bool {
+        let distance = match self.comparison_mode {
+            ComparisonMode::SplitVertical => (view_x - self.split_position).abs(),
+            ComparisonMode::SplitHorizontal => (view_y - self.split_position).abs(),
+            _ => f32::INFINITY
+        };
+        self.is_dragging_split = distance < 0.02;
+        self.is_dragging_split
+    }
+
+    pub fn update_split_position(&mut self, view_x: f32, view_y: f32) {
+        let position = match self.comparison_mode {
+            ComparisonMode::SplitHorizontal => view_y,
+            _ => view_x
+        };
+        self.split_position = position.clamp(0.0, 1.0);
+    }
+
     pub fn toggle_favorite(&mut self) {
         self.image.parameter_history.lock().unwrap()
             .update(|parameters| {
@@ -397,13 +1040,22 @@ impl Workspace {
             });
     }
 
+    pub fn auto_tone(&mut self) {
+        if let Some(image) = self.image.image.as_ref() {
+            let auto_tone = AutoTone::new(image);
+            self.image.parameter_history.lock().unwrap()
+                .update_step("Auto tone", |parameters| auto_tone.apply(&mut parameters.base_parameters));
+        }
+    }
+
     pub fn white_balance_at(&mut self, x: i32, y: i32) {
         let lab_pixel: Option<LabPixel> = self.image.image.as_ref()
-            .and_then(|opt| opt.lab_pixel_at(x as usize, y as usize));
+            .zip(self.source_position(x, y))
+            .and_then(|(image, (source_x, source_y))| image.lab_pixel_at(source_x, source_y));
         match lab_pixel {
             Some(pixel) => {
                 self.image.parameter_history.lock().unwrap()
-                    .update(|parameters| {
+                    .update_step("White balance", |parameters| {
                         parameters.base_parameters.tint = -pixel.tint * 1000.0;
                         parameters.base_parameters.temperature = -pixel.temperature * 1000.0;
                     });
@@ -430,11 +1082,18 @@ impl Workspace {
     }
 
     pub fn update_crop(&mut self, x: i32, y: i32) {
+        self.update_constrained_crop(|crop| {
+            crop.center_x = x;
+            crop.center_y = y;
+        });
+    }
+
+    fn update_constrained_crop(&mut self, update: impl Fn(&mut Crop)) {
         self.image.parameter_history.lock().unwrap()
             .update(|parameters| {
                 if let Some(crop) = &mut parameters.crop {
-                    crop.center_x = x;
-                    crop.center_y = y;
+                    update(crop);
+                    constrain_crop(crop);
                 }
             });
     }
@@ -487,10 +1146,8 @@ impl Workspace {
                     ViewportCrop {
                         center_x: view.center_x + offset_x,
                         center_y: view.center_y + offset_y,
-                        width: view.width,
-                        height: view.height,
-                        angle_degrees: view.angle_degrees,
-                        scale
+                        scale,
+                        ..view
                     }
                 }
             }
@@ -499,8 +1156,104 @@ impl Workspace {
         }
     }
 
+    /**
+     * Pixel of the source image shown at the image position, which differs from it when there's lens correction
+     */
+    fn source_position(&self, x: i32, y: i32) -> Option<(usize, usize)> {
+        let image = self.image.image.as_ref()?;
+        let parameters = self.current_parameters();
+        let lens_correction = parameters.crop.as_ref()
+            .map(LensCorrection::from)
+            .unwrap_or_default();
+        let (source_x, source_y) = lens_correction.source_position(x as f32, y as f32, image.width, image.height);
+        (source_x >= 0.0 && source_y >= 0.0).then_some((source_x as usize, source_y as usize))
+    }
+
+    fn to_brush_point(&self, x: i32, y: i32) -> Option<BrushPoint> {
+        self.image.image.as_ref()
+            .map(|image| image.width.max(image.height) as f32)
+            .map(|longest_side| BrushPoint {
+                x: x as f32 / longest_side,
+                y: y as f32 / longest_side
+            })
+    }
+
     fn set_parameter_value(&mut self, parameter: Parameter, new_value: f32) {
         self.image.parameter_history.lock().unwrap()
-            .update_f32(parameter, |value| *value = new_value)
+            .update_f32_and(parameter, |value| *value = new_value, constrain_parameters_crop)
     }
-}
\ No newline at end of file
+}
+
+/**
+ * Moves and shrinks the crop so it doesn't include anything outside the image. Done in the same update as the edit,
+ * so the history never has a step with the unconstrained crop.
+ */
+fn constrain_parameters_crop(parameters: &mut Parameters) {
+    if let Some(crop) = &mut parameters.crop {
+        constrain_crop(crop);
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::*;
+
+    fn create_workspace(parameter_history: &Arc<Mutex<ParameterHistory>>) -> Workspace {
+        let image = WorkspaceImage::new(1, None, parameter_history.clone(), Arc::new(Mutex::new(ImageView::default())), String::new());
+        Workspace::new(image)
+    }
+
+    #[rstest]
+    fn test_dragging_crop_angle_adds_one_constrained_step() {
+        // Arrange
+        let parameters = Parameters {
+            crop: Some(Crop {
+                center_x: 500,
+                center_y: 250,
+                source_image_width: 1000,
+                source_image_height: 500,
+                ..Crop::default()
+            }),
+            ..Parameters::default()
+        };
+        let parameter_history = Arc::new(Mutex::new(ParameterHistory::from(parameters)));
+        let mut workspace = create_workspace(&parameter_history);
+
+        // Act
+        for angle_degrees in [1.0, 2.0, 3.0, 4.0, 5.0] {
+            workspace.set_crop_angle(angle_degrees);
+        }
+
+        // Assert
+        let parameter_history = parameter_history.lock().unwrap();
+        let labels: Vec<&str> = parameter_history.get_steps().iter()
+            .map(|step| step.label.as_str())
+            .collect();
+        assert_eq!(labels, vec!["Open", "Crop angle"]);
+        let crop = parameter_history.current().crop.clone().unwrap();
+        let mut constrained = crop.clone();
+        constrain_crop(&mut constrained);
+        assert_eq!(crop.angle_degrees, 5.0);
+        assert_eq!(crop, constrained);
+    }
+
+    #[rstest]
+    fn test_paste_with_too_many_masks_is_refused() {
+        // Arrange
+        let parameter_history = Arc::new(Mutex::new(ParameterHistory::from(Parameters::default())));
+        let mut workspace = create_workspace(&parameter_history);
+        let clipboard_parameters = Parameters {
+            radial_masks: vec![RadialMask::default(); MAX_RADIAL_MASKS + 1],
+            ..Parameters::default()
+        };
+
+        // Act
+        let is_pasted = workspace.paste_parameters(&clipboard_parameters, &[ParameterGroup::Masks]);
+
+        // Assert
+        let parameter_history = parameter_history.lock().unwrap();
+        assert!(!is_pasted);
+        assert_eq!(parameter_history.get_steps().len(), 1);
+        assert!(parameter_history.current().radial_masks.is_empty());
+    }
+}